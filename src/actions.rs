@@ -1,7 +1,14 @@
 use crate::entities::*;
 use crate::map::*;
 
-pub fn teleport(map: &Map, entity: &mut Entity, tile_x: u32, tile_y: u32) {
-    let (new_x, new_y) = map.get_tile_xy(tile_x, tile_y);
-    entity.set_pos_xy(new_x, new_y);
+/// entity를 (tile_x, tile_y) 타일로 옮긴다.
+/// entity의 hitbox 중심이 타일의 중심에 오도록 한다.
+pub fn teleport(map: &Map, entity: &mut Entity, tile_x: i32, tile_y: i32) {
+    let (center_x, center_y) = map.get_tile_center(tile_x, tile_y);
+    let (offset_x, offset_y) = match entity.hitbox {
+        Some(hitbox) => (hitbox.hx + hitbox.w / 2.0, hitbox.hy + hitbox.h / 2.0),
+        None => (0.0, 0.0),
+    };
+
+    entity.set_pos_xy(center_x - offset_x, center_y - offset_y);
 }
@@ -3,7 +3,8 @@
 use crate::physics::shadow_casting::LightMap;
 
 use super::{Component, Entity, Grid, Tile};
-use rand::{thread_rng, Rng};
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct GameState {
@@ -25,8 +26,16 @@ impl GameState {
     }
 
     pub fn add_entity(&mut self, coord: Option<super::Coord>, tile: Option<super::Tile>) -> Entity {
+        self.add_entity_with(&mut thread_rng(), coord, tile)
+    }
+
+    fn add_entity_with<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        coord: Option<super::Coord>,
+        tile: Option<super::Tile>,
+    ) -> Entity {
         // entities 에는 없는 u32 값을 만든다.
-        let mut rng = thread_rng();
         let mut x: u32 = rng.gen();
 
         while self.entities.iter().find(|e| **e == x).is_some() {
@@ -75,8 +84,16 @@ impl GameState {
     }
 
     pub fn generate_rooms(&mut self) {
+        self.generate_rooms_with(&mut thread_rng());
+    }
+
+    /// 같은 seed 는 같은 방을 만든다. (녹화한 입력을 재생할 때)
+    pub fn generate_rooms_with_seed(&mut self, seed: u64) {
+        self.generate_rooms_with(&mut StdRng::seed_from_u64(seed));
+    }
+
+    fn generate_rooms_with<R: Rng>(&mut self, rng: &mut R) {
         const NUM_TRIES: u32 = 100;
-        let mut rng = thread_rng();
         let mut grids: Vec<Grid> = vec![];
 
         for _ in 0..NUM_TRIES {
@@ -105,14 +122,14 @@ impl GameState {
 
                 let grid = Grid::new(x, y, 1u32, 1u32);
                 if grids.iter().find(|&g| g.aabb(&grid)).is_some() {
-                    self.add_entity(Some((x, y)), Some(Tile::Floor));
+                    self.add_entity_with(rng, Some((x, y)), Some(Tile::Floor));
                 } else {
-                    self.add_entity(Some((x, y)), Some(Tile::Wall));
+                    self.add_entity_with(rng, Some((x, y)), Some(Tile::Wall));
                 }
             }
         }
 
-        self.make_corridor(&grids);
+        self.make_corridor_with(rng, &grids);
     }
 
     /// 임의의 좌표에 어떤 entity가 있는지 반환한다.
@@ -127,9 +144,12 @@ impl GameState {
 
     /// 복도를 만든다.
     pub fn make_corridor(&mut self, grids: &Vec<Grid>) {
+        self.make_corridor_with(&mut thread_rng(), grids);
+    }
+
+    fn make_corridor_with<R: Rng>(&mut self, rng: &mut R, grids: &[Grid]) {
         // 임의의 Grid 2개를 뽑아서...
         // 해당 Grid 의 중점을 연결하는 선을 긋는다.
-        let mut rng = thread_rng();
         const NUM_TRIES: u32 = 100;
 
         for _ in 0..NUM_TRIES {
@@ -171,7 +191,7 @@ impl GameState {
                         }
                     }
 
-                    self.add_entity(Some((x, start_y)), Some(Tile::Floor));
+                    self.add_entity_with(rng, Some((x, start_y)), Some(Tile::Floor));
                 }
 
                 let iter_y = if start_y < end_y {
@@ -191,7 +211,7 @@ impl GameState {
                         }
                     }
 
-                    self.add_entity(Some((end_x, y)), Some(Tile::Floor));
+                    self.add_entity_with(rng, Some((end_x, y)), Some(Tile::Floor));
                 }
             }
         }
@@ -208,3 +228,19 @@ impl GameState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_rooms() {
+        let mut a = GameState::new(320 / 8, 240 / 16);
+        let mut b = GameState::new(320 / 8, 240 / 16);
+
+        a.generate_rooms_with_seed(7);
+        b.generate_rooms_with_seed(7);
+
+        assert_eq!(a, b);
+    }
+}
@@ -5,12 +5,14 @@ pub mod game_state;
 pub mod grid;
 pub mod map;
 pub mod screen;
+pub mod text_buffer;
 
 pub use entity::*;
 pub use game_state::*;
 pub use grid::*;
 pub use map::*;
 pub use screen::*;
+pub use text_buffer::*;
 
 const ASCII_TEXTURE: &'static str = " _.,-=+:;cba!?0123456789$W#@";
 
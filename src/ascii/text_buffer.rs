@@ -0,0 +1,539 @@
+//! 한글 입력을 이해하는 문장 입력 버퍼
+//! SDL 의 TextInput(확정된 글자), TextEditing(IME 가 조합 중인 글자) 이벤트를 받는다.
+//! IME 가 없을 때는 두벌식 자판으로 직접 자모를 조합한다.
+//! 조합 중인 글자는 지울 때 자모 단위로, 확정된 글자는 글자 단위로 지운다.
+
+use super::Screen;
+use sdl2::event::Event;
+use sdl2::keyboard::Scancode;
+
+/// 초성 (19)
+const CHO: [char; 19] = [
+    'ㄱ', 'ㄲ', 'ㄴ', 'ㄷ', 'ㄸ', 'ㄹ', 'ㅁ', 'ㅂ', 'ㅃ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅉ', 'ㅊ', 'ㅋ',
+    'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+/// 중성 (21)
+const JUNG: [char; 21] = [
+    'ㅏ', 'ㅐ', 'ㅑ', 'ㅒ', 'ㅓ', 'ㅔ', 'ㅕ', 'ㅖ', 'ㅗ', 'ㅘ', 'ㅙ', 'ㅚ', 'ㅛ', 'ㅜ', 'ㅝ', 'ㅞ',
+    'ㅟ', 'ㅠ', 'ㅡ', 'ㅢ', 'ㅣ',
+];
+
+/// 종성 (받침 없음 포함 28)
+const JONG: [char; 28] = [
+    '\0', 'ㄱ', 'ㄲ', 'ㄳ', 'ㄴ', 'ㄵ', 'ㄶ', 'ㄷ', 'ㄹ', 'ㄺ', 'ㄻ', 'ㄼ', 'ㄽ', 'ㄾ', 'ㄿ', 'ㅀ',
+    'ㅁ', 'ㅂ', 'ㅄ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅊ', 'ㅋ', 'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+/// 겹모음
+const COMPOUND_JUNG: [(char, char, char); 7] = [
+    ('ㅗ', 'ㅏ', 'ㅘ'),
+    ('ㅗ', 'ㅐ', 'ㅙ'),
+    ('ㅗ', 'ㅣ', 'ㅚ'),
+    ('ㅜ', 'ㅓ', 'ㅝ'),
+    ('ㅜ', 'ㅔ', 'ㅞ'),
+    ('ㅜ', 'ㅣ', 'ㅟ'),
+    ('ㅡ', 'ㅣ', 'ㅢ'),
+];
+
+/// 겹받침
+const COMPOUND_JONG: [(char, char, char); 11] = [
+    ('ㄱ', 'ㅅ', 'ㄳ'),
+    ('ㄴ', 'ㅈ', 'ㄵ'),
+    ('ㄴ', 'ㅎ', 'ㄶ'),
+    ('ㄹ', 'ㄱ', 'ㄺ'),
+    ('ㄹ', 'ㅁ', 'ㄻ'),
+    ('ㄹ', 'ㅂ', 'ㄼ'),
+    ('ㄹ', 'ㅅ', 'ㄽ'),
+    ('ㄹ', 'ㅌ', 'ㄾ'),
+    ('ㄹ', 'ㅍ', 'ㄿ'),
+    ('ㄹ', 'ㅎ', 'ㅀ'),
+    ('ㅂ', 'ㅅ', 'ㅄ'),
+];
+
+/// 두벌식 자판의 자모
+pub fn dubeolsik(c: char) -> Option<char> {
+    let jamo = match c {
+        'Q' => 'ㅃ',
+        'W' => 'ㅉ',
+        'E' => 'ㄸ',
+        'R' => 'ㄲ',
+        'T' => 'ㅆ',
+        'O' => 'ㅒ',
+        'P' => 'ㅖ',
+        _ => match c.to_ascii_lowercase() {
+            'q' => 'ㅂ',
+            'w' => 'ㅈ',
+            'e' => 'ㄷ',
+            'r' => 'ㄱ',
+            't' => 'ㅅ',
+            'y' => 'ㅛ',
+            'u' => 'ㅕ',
+            'i' => 'ㅑ',
+            'o' => 'ㅐ',
+            'p' => 'ㅔ',
+            'a' => 'ㅁ',
+            's' => 'ㄴ',
+            'd' => 'ㅇ',
+            'f' => 'ㄹ',
+            'g' => 'ㅎ',
+            'h' => 'ㅗ',
+            'j' => 'ㅓ',
+            'k' => 'ㅏ',
+            'l' => 'ㅣ',
+            'z' => 'ㅋ',
+            'x' => 'ㅌ',
+            'c' => 'ㅊ',
+            'v' => 'ㅍ',
+            'b' => 'ㅠ',
+            'n' => 'ㅜ',
+            'm' => 'ㅡ',
+            _ => return None,
+        },
+    };
+
+    Some(jamo)
+}
+
+fn is_vowel(c: char) -> bool {
+    JUNG.contains(&c)
+}
+
+/// 자모를 한 글자로 조합한다. 한 글자가 되지 않으면 None
+/// 초성이나 모음 하나만 있다면 그 자모를 그대로 돌려준다.
+pub fn compose(jamo: &[char]) -> Option<char> {
+    let cho = jamo
+        .first()
+        .and_then(|c| CHO.iter().position(|cho| cho == c));
+    let mut i = if cho.is_some() { 1 } else { 0 };
+
+    // 중성 (겹모음은 두 자모)
+    let jung = match jamo.get(i) {
+        Some(v) if is_vowel(*v) => {
+            match jamo
+                .get(i + 1)
+                .and_then(|next| COMPOUND_JUNG.iter().find(|(a, b, _)| a == v && b == next))
+            {
+                Some((_, _, compound)) => {
+                    i += 2;
+                    *compound
+                }
+                None => {
+                    i += 1;
+                    *v
+                }
+            }
+        }
+        _ => return if jamo.len() == 1 { Some(jamo[0]) } else { None },
+    };
+    let jung_idx = JUNG.iter().position(|v| *v == jung)?;
+
+    // 초성이 없는 모음은 받침을 가질 수 없다.
+    let cho_idx = match cho {
+        Some(cho_idx) => cho_idx,
+        None => return if i == jamo.len() { Some(jung) } else { None },
+    };
+
+    let jong = match &jamo[i..] {
+        [] => '\0',
+        [c] => *c,
+        [a, b] => {
+            COMPOUND_JONG
+                .iter()
+                .find(|(first, second, _)| first == a && second == b)?
+                .2
+        }
+        _ => return None,
+    };
+    let jong_idx = JONG.iter().position(|c| *c == jong)?;
+
+    std::char::from_u32(0xAC00 + ((cho_idx * 21 + jung_idx) * 28 + jong_idx) as u32)
+}
+
+/// 한 줄 문장 입력
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct TextBuffer {
+    pub text: Vec<char>,  // 확정된 글자
+    pub cursor: usize,    // text 안에서 커서의 위치 (글자 단위)
+    pub hangul: bool,     // 두벌식 자판으로 한글을 조합한다.
+    composing: Vec<char>, // 직접 조합 중인 자모
+    preedit: String,      // IME 가 조합 중인 글자
+}
+
+impl TextBuffer {
+    pub fn new() -> TextBuffer {
+        TextBuffer::default()
+    }
+
+    /// 처리한 이벤트라면 true
+    pub fn process_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::TextInput { text, .. } => {
+                self.preedit.clear();
+                for c in text.chars() {
+                    match dubeolsik(c) {
+                        Some(jamo) if self.hangul => self.input_jamo(jamo),
+                        _ => self.insert(c),
+                    }
+                }
+                true
+            }
+            Event::TextEditing { text, .. } => {
+                // IME 가 조합을 시작하면 직접 조합하던 글자는 확정한다.
+                self.commit();
+                self.preedit = text.clone();
+                true
+            }
+            // IME 가 조합 중일 때의 편집 키는 IME 의 것이다.
+            Event::KeyDown {
+                scancode: Some(scancode),
+                ..
+            } if self.preedit.is_empty() => {
+                match scancode {
+                    Scancode::Backspace => self.backspace(),
+                    Scancode::Delete => self.delete(),
+                    Scancode::Left => self.move_left(),
+                    Scancode::Right => self.move_right(),
+                    Scancode::Home => self.home(),
+                    Scancode::End => self.end(),
+                    // 한/영 키
+                    Scancode::Lang1 | Scancode::RAlt => {
+                        self.commit();
+                        self.hangul = !self.hangul;
+                    }
+                    _ => return false,
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 글자를 커서 위치에 넣는다.
+    pub fn insert(&mut self, c: char) {
+        self.commit();
+        self.text.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    /// 자모 하나를 조합 중인 글자에 더한다.
+    pub fn input_jamo(&mut self, jamo: char) {
+        let mut next = self.composing.clone();
+        next.push(jamo);
+        if compose(&next).is_some() {
+            self.composing = next;
+            return;
+        }
+
+        // 모음이 오면 앞 글자의 마지막 받침을 떼어 다음 글자의 초성으로 쓴다.
+        // 갉 + ㅏ = 갈가
+        if is_vowel(jamo) && self.composing.len() >= 3 {
+            let (last, rest) = self.composing.split_last().unwrap();
+            if compose(rest).is_some() && compose(&[*last, jamo]).is_some() {
+                let last = *last;
+                self.composing.pop();
+                self.commit();
+                self.composing = vec![last, jamo];
+                return;
+            }
+        }
+
+        self.commit();
+        self.composing = vec![jamo];
+    }
+
+    /// 조합 중인 글자를 확정한다.
+    pub fn commit(&mut self) {
+        if let Some(c) = compose(&self.composing) {
+            self.text.insert(self.cursor, c);
+            self.cursor += 1;
+        }
+        self.composing.clear();
+    }
+
+    /// 조합 중이라면 자모 하나, 아니라면 커서 앞의 글자 하나를 지운다.
+    pub fn backspace(&mut self) {
+        if self.composing.pop().is_some() {
+            return;
+        }
+
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.text.remove(self.cursor);
+        }
+    }
+
+    /// 커서 뒤의 글자 하나를 지운다.
+    pub fn delete(&mut self) {
+        self.commit();
+        if self.cursor < self.text.len() {
+            self.text.remove(self.cursor);
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        self.commit();
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.commit();
+        self.cursor = (self.cursor + 1).min(self.text.len());
+    }
+
+    pub fn home(&mut self) {
+        self.commit();
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.commit();
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        *self = TextBuffer {
+            hangul: self.hangul,
+            ..TextBuffer::default()
+        };
+    }
+
+    /// 조합 중인 글자
+    pub fn composition(&self) -> String {
+        if !self.preedit.is_empty() {
+            self.preedit.clone()
+        } else {
+            compose(&self.composing).into_iter().collect()
+        }
+    }
+
+    /// 조합 중인 글자까지 포함한 문장
+    pub fn value(&self) -> String {
+        let mut value: String = self.text[..self.cursor].iter().collect();
+        value.push_str(&self.composition());
+        value.extend(self.text[self.cursor..].iter());
+        value
+    }
+
+    /// 스크린의 x, y 에서부터 그 줄의 끝까지 출력한다.
+    /// 조합 중인 글자와 커서는 색을 뒤집어 보인다.
+    /// 줄이 모자라면 커서가 보이도록 앞 글자들을 잘라낸다.
+    pub fn render(
+        &self,
+        screen: &mut Screen,
+        x: u32,
+        y: u32,
+        fg: (u8, u8, u8, u8),
+        bg: (u8, u8, u8, u8),
+    ) {
+        let cols = screen.width / screen.cell_width;
+        let rows = screen.height / screen.cell_height;
+        if x >= cols || y >= rows {
+            return;
+        }
+        let room = cols - x;
+
+        // (글자, 색을 뒤집는가?)
+        // 조합 중인 글자가 없다면 커서 자리의 글자나 '_' 를 뒤집는다.
+        let composition: Vec<char> = self.composition().chars().collect();
+        let mut cells: Vec<(char, bool)> = self.text[..self.cursor]
+            .iter()
+            .map(|c| (*c, false))
+            .collect();
+        let cursor_start = cells.len();
+        if composition.is_empty() {
+            match self.text.get(self.cursor) {
+                Some(c) => cells.push((*c, true)),
+                None => cells.push(('_', false)),
+            }
+        } else {
+            cells.extend(composition.iter().map(|c| (*c, true)));
+        }
+        let cursor_end = cells.len();
+        let after = if composition.is_empty() {
+            self.cursor + 1
+        } else {
+            self.cursor
+        };
+        cells.extend(self.text.iter().skip(after).map(|c| (*c, false)));
+
+        // 커서가 줄 안에 들어올 때까지 앞 글자를 잘라낸다.
+        let mut start = 0;
+        while start < cursor_start && cells_width(&cells[start..cursor_end]) > room {
+            start += 1;
+        }
+
+        let mut x_ = x;
+        for (c, inverted) in cells[start..].iter() {
+            let width = char_width(*c);
+            if x_ + width > cols {
+                break;
+            }
+
+            if *inverted {
+                screen.put_char(x_, y, *c, Some(bg), Some(fg));
+            } else {
+                screen.put_char(x_, y, *c, Some(fg), Some(bg));
+            }
+            x_ += width;
+        }
+    }
+}
+
+/// 스크린에서 글자가 차지하는 칸 (한글 등의 전각 문자는 2칸)
+fn char_width(c: char) -> u32 {
+    if c.is_ascii() {
+        1
+    } else {
+        2
+    }
+}
+
+fn cells_width(cells: &[(char, bool)]) -> u32 {
+    cells.iter().map(|(c, _)| char_width(*c)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_keys(buffer: &mut TextBuffer, keys: &str) {
+        buffer.process_event(&Event::TextInput {
+            timestamp: 0,
+            window_id: 0,
+            text: keys.to_string(),
+        });
+    }
+
+    fn press(buffer: &mut TextBuffer, scancode: Scancode) {
+        buffer.process_event(&Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: None,
+            scancode: Some(scancode),
+            keymod: sdl2::keyboard::Mod::NOMOD,
+            repeat: false,
+        });
+    }
+
+    #[test]
+    fn compose_jamo() {
+        assert_eq!(compose(&['ㅎ', 'ㅏ', 'ㄴ']), Some('한'));
+        assert_eq!(compose(&['ㄱ', 'ㅗ', 'ㅏ', 'ㄹ', 'ㄱ']), Some('괅'));
+        assert_eq!(compose(&['ㅗ', 'ㅏ']), Some('ㅘ'));
+        assert_eq!(compose(&['ㄱ']), Some('ㄱ'));
+        assert_eq!(compose(&['ㄱ', 'ㄱ']), None);
+        assert_eq!(compose(&['ㅏ', 'ㄴ']), None);
+        // ㄸ 은 받침이 될 수 없다.
+        assert_eq!(compose(&['ㄱ', 'ㅏ', 'ㄸ']), None);
+    }
+
+    #[test]
+    fn dubeolsik_input() {
+        let mut buffer = TextBuffer::new();
+        buffer.hangul = true;
+
+        type_keys(&mut buffer, "gksrmf");
+        assert_eq!(buffer.value(), "한글");
+        assert_eq!(buffer.composition(), "글");
+
+        // 겹받침의 뒤 자음은 다음 글자로 넘어간다.
+        buffer.clear();
+        type_keys(&mut buffer, "rkfrk");
+        assert_eq!(buffer.value(), "갈가");
+
+        press(&mut buffer, Scancode::Lang1);
+        assert!(!buffer.hangul);
+        type_keys(&mut buffer, "ok");
+        assert_eq!(buffer.value(), "갈가ok");
+    }
+
+    #[test]
+    fn backspace_and_cursor() {
+        let mut buffer = TextBuffer::new();
+        buffer.hangul = true;
+
+        // 조합 중에는 자모 단위로 지운다.
+        type_keys(&mut buffer, "gkfr");
+        assert_eq!(buffer.value(), "핡");
+        press(&mut buffer, Scancode::Backspace);
+        assert_eq!(buffer.value(), "할");
+        press(&mut buffer, Scancode::Backspace);
+        assert_eq!(buffer.value(), "하");
+
+        // 확정된 글자는 글자 단위로 지운다.
+        type_keys(&mut buffer, "sm");
+        assert_eq!(buffer.value(), "하느");
+        press(&mut buffer, Scancode::Left);
+        assert_eq!(buffer.cursor, 1);
+        press(&mut buffer, Scancode::Backspace);
+        assert_eq!(buffer.value(), "느");
+
+        press(&mut buffer, Scancode::End);
+        type_keys(&mut buffer, "f");
+        press(&mut buffer, Scancode::Home);
+        press(&mut buffer, Scancode::Delete);
+        assert_eq!(buffer.value(), "ㄹ");
+    }
+
+    #[test]
+    fn ime_composition() {
+        let mut buffer = TextBuffer::new();
+
+        buffer.process_event(&Event::TextEditing {
+            timestamp: 0,
+            window_id: 0,
+            text: "하".to_string(),
+            start: 0,
+            length: 1,
+        });
+        assert_eq!(buffer.value(), "하");
+
+        // IME 가 조합 중인 동안 지우기는 IME 가 처리한다.
+        press(&mut buffer, Scancode::Backspace);
+        assert_eq!(buffer.value(), "하");
+
+        type_keys(&mut buffer, "한");
+        assert_eq!(buffer.value(), "한");
+        assert_eq!(buffer.composition(), "");
+        assert_eq!(buffer.cursor, 1);
+    }
+
+    fn row(screen: &Screen) -> String {
+        screen
+            .cells
+            .iter()
+            .map(|cell| cell.cell)
+            .filter(|c| *c != '\0')
+            .collect()
+    }
+
+    #[test]
+    fn render_within_row() {
+        let mut buffer = TextBuffer::new();
+        type_keys(&mut buffer, "abcdefgh");
+
+        // 한 줄 10 칸 중 6 칸째부터 그린다.
+        let mut screen = Screen::new(80, 8, 8, 8);
+        buffer.render(&mut screen, 6, 0, (255, 255, 255, 255), (0, 0, 0, 255));
+        assert_eq!(row(&screen), "fgh_");
+
+        // 커서를 앞으로 옮기면 앞 글자부터 보인다.
+        press(&mut buffer, Scancode::Home);
+        let mut screen = Screen::new(80, 8, 8, 8);
+        buffer.render(&mut screen, 6, 0, (255, 255, 255, 255), (0, 0, 0, 255));
+        assert_eq!(row(&screen), "abcd");
+
+        // 전각 문자가 줄 끝에 걸리면 그리지 않는다.
+        buffer.clear();
+        type_keys(&mut buffer, "한글");
+        press(&mut buffer, Scancode::Home);
+        let mut screen = Screen::new(80, 8, 8, 8);
+        buffer.render(&mut screen, 7, 0, (255, 255, 255, 255), (0, 0, 0, 255));
+        assert_eq!(row(&screen), "한");
+
+        // 줄이나 화면 밖이라면 아무것도 그리지 않는다.
+        buffer.render(&mut screen, 10, 0, (255, 255, 255, 255), (0, 0, 0, 255));
+        buffer.render(&mut screen, 0, 1, (255, 255, 255, 255), (0, 0, 0, 255));
+    }
+}
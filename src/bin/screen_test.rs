@@ -72,8 +72,16 @@ fn main() {
     let mut x = 10;
     let mut y = 10;
 
+    // 한/영 키(오른쪽 Alt)로 두벌식 한글 입력을 켜고 끈다.
+    let mut text = sdl_isometric::ascii::TextBuffer::new();
+
     'game_loop: loop {
         for event in event_pump.poll_iter() {
+            // 글자 입력과 좌우 커서 이동은 입력 버퍼가 먼저 처리한다.
+            if text.process_event(&event) {
+                continue;
+            }
+
             match event {
                 sdl2::event::Event::Quit { .. } => {
                     break 'game_loop;
@@ -126,6 +134,8 @@ fn main() {
             Some((0, 255, 0, 255)),
         );
 
+        text.render(&mut screen, 5, 9, (255, 255, 255, 255), (0, 0, 0, 255));
+
         screen.put_char(x, y, '@', Some((255, 255, 255, 255)), Some((0, 0, 0, 255)));
         screen.render(&fonts, &mut canvas);
         canvas.present();
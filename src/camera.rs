@@ -0,0 +1,506 @@
+//! 카메라
+//! 월드 중 화면에 보일 영역을 정하고, 월드 좌표와 화면 좌표를 서로 바꾼다.
+//! 대상을 따라갈 때는 dead zone 안의 움직임은 무시하고,
+//! 바깥으로 나가면 smoothing 비율로 부드럽게 따라간다.
+//! 확대(zoom)와 회전(rotation)은 화면 중앙을 기준으로 한다.
+
+use sdl2::rect::Rect;
+
+use crate::Rectangle;
+
+/// 흔들림 세기가 1 일 때 카메라가 움직이는 최대 픽셀
+const SHAKE_MAX_OFFSET: f64 = 16.0;
+
+/// 가장 작은 확대 비율
+pub const MIN_ZOOM: f64 = 0.25;
+
+/// 가장 큰 확대 비율
+pub const MAX_ZOOM: f64 = 4.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Camera {
+    /// 화면에 보이는 월드 영역의 좌상단 좌표 (흔들림, 회전 제외)
+    pub x: f64,
+    pub y: f64,
+    /// 지난 update 의 x, y. 두 update 사이를 그릴 때 쓴다.
+    pub last_x: f64,
+    pub last_y: f64,
+    /// 카메라가 그리는 화면 영역의 좌상단. 화면을 나누어 쓸 때 0 이 아니다.
+    pub screen_x: i32,
+    pub screen_y: i32,
+    /// 카메라가 그리는 화면의 크기
+    pub w: u32,
+    pub h: u32,
+    /// 확대 비율. 2 이면 월드가 두 배로 크게 보인다.
+    pub zoom: f64,
+    /// 시계방향 회전 각도 (도)
+    pub rotation: f64,
+    /// 화면 중앙의 (폭, 높이) 영역. 대상이 이 안에서 움직이면 카메라는 움직이지 않는다.
+    pub dead_zone: (f64, f64),
+    /// 초당 대상을 따라잡는 정도. 0 이면 바로 따라간다.
+    pub smoothing: f64,
+    /// 카메라가 벗어날 수 없는 월드 영역
+    pub bounds: Option<Rectangle>,
+    /// 흔들림 세기 (0 ~ 1)
+    pub trauma: f64,
+    /// 초당 줄어드는 흔들림 세기
+    pub shake_decay: f64,
+    shake_time: f64,
+    shake_offset: (f64, f64),
+}
+
+impl Camera {
+    pub fn new(w: u32, h: u32) -> Camera {
+        Camera {
+            x: 0.0,
+            y: 0.0,
+            last_x: 0.0,
+            last_y: 0.0,
+            screen_x: 0,
+            screen_y: 0,
+            w,
+            h,
+            zoom: 1.0,
+            rotation: 0.0,
+            dead_zone: (0.0, 0.0),
+            smoothing: 0.0,
+            bounds: None,
+            trauma: 0.0,
+            shake_decay: 1.5,
+            shake_time: 0.0,
+            shake_offset: (0.0, 0.0),
+        }
+    }
+
+    pub fn set_dead_zone(&mut self, w: f64, h: f64) {
+        self.dead_zone = (w, h);
+    }
+
+    pub fn set_smoothing(&mut self, smoothing: f64) {
+        self.smoothing = smoothing;
+    }
+
+    /// 카메라가 움직일 수 있는 월드 영역을 정한다. 보통 지도의 크기이다.
+    pub fn set_bounds(&mut self, bounds: Rectangle) {
+        self.bounds = Some(bounds);
+        self.clamp();
+    }
+
+    /// 카메라가 그릴 화면 영역
+    pub fn screen_rect(&self) -> Rect {
+        Rect::new(self.screen_x, self.screen_y, self.w, self.h)
+    }
+
+    /// 화면 중앙을 유지한 채 카메라가 그릴 화면 영역을 바꾼다.
+    pub fn set_screen_rect(&mut self, rect: Rect) {
+        let (center_x, center_y) = self.center();
+        self.screen_x = rect.x();
+        self.screen_y = rect.y();
+        self.w = rect.width();
+        self.h = rect.height();
+        self.look_at(center_x, center_y);
+    }
+
+    /// 화면 영역 중앙의 화면 좌표
+    fn screen_center(&self) -> (f64, f64) {
+        (
+            self.screen_x as f64 + self.w as f64 / 2.0,
+            self.screen_y as f64 + self.h as f64 / 2.0,
+        )
+    }
+
+    /// 화면 중앙을 유지한 채 확대 비율을 바꾼다.
+    pub fn set_zoom(&mut self, zoom: f64) {
+        let (center_x, center_y) = self.center();
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.look_at(center_x, center_y);
+    }
+
+    pub fn set_rotation(&mut self, rotation: f64) {
+        self.rotation = rotation.rem_euclid(360.0);
+    }
+
+    /// 회전하지 않았을 때 화면에 보이는 월드 영역의 크기
+    pub fn view_size(&self) -> (f64, f64) {
+        (self.w as f64 / self.zoom, self.h as f64 / self.zoom)
+    }
+
+    /// 카메라 중앙의 월드 좌표
+    pub fn center(&self) -> (f64, f64) {
+        let (view_w, view_h) = self.view_size();
+        (self.x + view_w / 2.0, self.y + view_h / 2.0)
+    }
+
+    /// (x, y) 가 화면 중앙에 오도록 바로 옮긴다.
+    /// 건너뛴 것이므로 두 update 사이를 그릴 때도 새 위치를 쓴다.
+    pub fn look_at(&mut self, x: f64, y: f64) {
+        let (view_w, view_h) = self.view_size();
+        self.x = x - view_w / 2.0;
+        self.y = y - view_h / 2.0;
+        self.clamp();
+        self.last_x = self.x;
+        self.last_y = self.y;
+    }
+
+    /// 지난 update 와 이번 update 사이의 카메라. alpha 는 0.0 ~ 1.0
+    pub fn lerp(&self, alpha: f64) -> Camera {
+        let mut camera = self.clone();
+        camera.x = self.last_x + (self.x - self.last_x) * alpha;
+        camera.y = self.last_y + (self.y - self.last_y) * alpha;
+        camera
+    }
+
+    /// 대상 (x, y) 를 따라간다.
+    /// 대상이 dead zone 밖에 있다면 dead zone 가장자리에 오도록 움직인다.
+    /// dead zone 은 화면 크기 기준이므로 확대하면 월드에서는 작아진다.
+    pub fn follow(&mut self, target_x: f64, target_y: f64, dt: f64) {
+        let (center_x, center_y) = self.center();
+        let half_w = self.dead_zone.0 / 2.0 / self.zoom;
+        let half_h = self.dead_zone.1 / 2.0 / self.zoom;
+
+        let goal_x = if target_x < center_x - half_w {
+            self.x + target_x - (center_x - half_w)
+        } else if target_x > center_x + half_w {
+            self.x + target_x - (center_x + half_w)
+        } else {
+            self.x
+        };
+
+        let goal_y = if target_y < center_y - half_h {
+            self.y + target_y - (center_y - half_h)
+        } else if target_y > center_y + half_h {
+            self.y + target_y - (center_y + half_h)
+        } else {
+            self.y
+        };
+
+        // 프레임 속도와 상관없이 같은 빠르기로 따라가도록 지수적으로 줄인다.
+        let t = if self.smoothing <= 0.0 {
+            1.0
+        } else {
+            1.0 - (-self.smoothing * dt).exp()
+        };
+
+        self.x += (goal_x - self.x) * t;
+        self.y += (goal_y - self.y) * t;
+        self.clamp();
+    }
+
+    /// 카메라를 bounds 안으로 옮긴다.
+    /// bounds 가 화면보다 작다면 bounds 의 가운데를 보인다.
+    pub fn clamp(&mut self) {
+        if let Some(bounds) = self.bounds {
+            let (view_w, view_h) = self.view_size();
+            self.x = Camera::clamp_axis(self.x, view_w, bounds.x, bounds.w);
+            self.y = Camera::clamp_axis(self.y, view_h, bounds.y, bounds.h);
+        }
+    }
+
+    fn clamp_axis(pos: f64, size: f64, min: f64, length: f64) -> f64 {
+        if length <= size {
+            min + (length - size) / 2.0
+        } else {
+            pos.max(min).min(min + length - size)
+        }
+    }
+
+    /// 화면을 흔든다. amount 는 0 ~ 1 사이의 세기로 누적된다.
+    pub fn shake(&mut self, amount: f64) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    /// 지금 위치를 지난 update 의 위치로 남기고,
+    /// 흔들림을 줄여 이번 프레임의 흔들림 위치를 구한다.
+    /// follow 보다 먼저 부른다.
+    pub fn update(&mut self, dt: f64) {
+        self.last_x = self.x;
+        self.last_y = self.y;
+
+        self.trauma = (self.trauma - self.shake_decay * dt).max(0.0);
+
+        if self.trauma > 0.0 {
+            self.shake_time += dt;
+
+            // 세기의 제곱을 써서 약한 흔들림은 더 약하게 한다.
+            let power = SHAKE_MAX_OFFSET * self.trauma * self.trauma;
+            self.shake_offset = (
+                power * (self.shake_time * 47.0).sin(),
+                power * (self.shake_time * 31.0 + 1.3).cos(),
+            );
+        } else {
+            self.shake_time = 0.0;
+            self.shake_offset = (0.0, 0.0);
+        }
+    }
+
+    /// 흔들림까지 반영한 화면 중앙의 월드 좌표
+    fn shaken_center(&self) -> (f64, f64) {
+        let (center_x, center_y) = self.center();
+        (
+            center_x + self.shake_offset.0,
+            center_y + self.shake_offset.1,
+        )
+    }
+
+    /// 화면 중앙을 기준으로 회전한다.
+    fn rotate(&self, (x, y): (f64, f64), degree: f64) -> (f64, f64) {
+        if degree == 0.0 {
+            return (x, y);
+        }
+
+        let (sin, cos) = degree.to_radians().sin_cos();
+        (x * cos - y * sin, x * sin + y * cos)
+    }
+
+    /// 월드 좌표를 실수형 화면 좌표로 바꾼다.
+    pub fn to_screen(&self, (world_x, world_y): (f64, f64)) -> (f64, f64) {
+        let (center_x, center_y) = self.shaken_center();
+        let (x, y) = self.rotate(
+            (
+                (world_x - center_x) * self.zoom,
+                (world_y - center_y) * self.zoom,
+            ),
+            self.rotation,
+        );
+        let (origin_x, origin_y) = self.screen_center();
+
+        (x + origin_x, y + origin_y)
+    }
+
+    /// 실수형 화면 좌표를 월드 좌표로 바꾼다.
+    pub fn to_world(&self, (screen_x, screen_y): (f64, f64)) -> (f64, f64) {
+        let (center_x, center_y) = self.shaken_center();
+        let (origin_x, origin_y) = self.screen_center();
+        let (x, y) = self.rotate((screen_x - origin_x, screen_y - origin_y), -self.rotation);
+
+        (x / self.zoom + center_x, y / self.zoom + center_y)
+    }
+
+    /// 흔들림과 회전까지 반영해, 화면에 보이는 월드 영역을 감싸는 사각형
+    pub fn view_rect(&self) -> Rect {
+        let (left, top) = (self.screen_x as f64, self.screen_y as f64);
+        let (right, bottom) = (left + self.w as f64, top + self.h as f64);
+        let corners = [
+            self.to_world((left, top)),
+            self.to_world((right, top)),
+            self.to_world((left, bottom)),
+            self.to_world((right, bottom)),
+        ];
+
+        let left = corners.iter().map(|p| p.0).fold(f64::MAX, f64::min);
+        let top = corners.iter().map(|p| p.1).fold(f64::MAX, f64::min);
+        let right = corners.iter().map(|p| p.0).fold(f64::MIN, f64::max);
+        let bottom = corners.iter().map(|p| p.1).fold(f64::MIN, f64::max);
+
+        let left = left.round() as i32;
+        let top = top.round() as i32;
+        Rect::new(
+            left,
+            top,
+            (right.round() as i32 - left).max(1) as u32,
+            (bottom.round() as i32 - top).max(1) as u32,
+        )
+    }
+
+    /// 월드 좌표를 화면 좌표로 바꾼다.
+    pub fn world_to_screen(&self, world: (f64, f64)) -> (i32, i32) {
+        let (x, y) = self.to_screen(world);
+        (x.floor() as i32, y.floor() as i32)
+    }
+
+    /// 화면 좌표를 월드 좌표로 바꾼다.
+    pub fn screen_to_world(&self, (screen_x, screen_y): (i32, i32)) -> (f64, f64) {
+        self.to_world((screen_x as f64, screen_y as f64))
+    }
+
+    /// 길이를 화면에서의 길이로 바꾼다.
+    pub fn scale(&self, length: f64) -> f64 {
+        length * self.zoom
+    }
+
+    /// 월드 영역을 화면 영역으로 바꾼다.
+    /// 이웃한 타일 사이에 틈이 생기지 않도록 양쪽 가장자리를 각각 바꾼다.
+    /// 회전한 경우에는 영역의 중심만 회전하므로, 그릴 때 rotation 만큼 돌려서 그려야 한다.
+    pub fn world_rect_to_screen(&self, rect: Rect) -> Rect {
+        let (center_x, center_y) = self.shaken_center();
+        let (origin_x, origin_y) = self.screen_center();
+        let edge_x = |x: i32| ((x as f64 - center_x) * self.zoom + origin_x).floor() as i32;
+        let edge_y = |y: i32| ((y as f64 - center_y) * self.zoom + origin_y).floor() as i32;
+
+        let left = edge_x(rect.left());
+        let top = edge_y(rect.top());
+        let dest = Rect::new(
+            left,
+            top,
+            (edge_x(rect.right()) - left).max(1) as u32,
+            (edge_y(rect.bottom()) - top).max(1) as u32,
+        );
+
+        if self.rotation == 0.0 {
+            return dest;
+        }
+
+        let (x, y) = self.rotate(
+            (
+                dest.center().x() as f64 - origin_x,
+                dest.center().y() as f64 - origin_y,
+            ),
+            self.rotation,
+        );
+        Rect::from_center(
+            ((x + origin_x) as i32, (y + origin_y) as i32),
+            dest.width(),
+            dest.height(),
+        )
+    }
+}
+
+/// 화면 (width, height) 를 count 개의 영역으로 나눈다.
+/// 둘이면 좌우로, 그 이상이면 격자로 나누고 마지막 줄의 남는 칸은 비워둔다.
+pub fn split_screen(width: u32, height: u32, count: usize) -> Vec<Rect> {
+    if count == 0 {
+        return vec![];
+    }
+
+    let columns = (count as f64).sqrt().ceil() as u32;
+    let rows = (count as u32).div_ceil(columns);
+
+    (0..count as u32)
+        .map(|idx| {
+            let (column, row) = (idx % columns, idx / columns);
+            let left = width * column / columns;
+            let top = height * row / rows;
+            let right = width * (column + 1) / columns;
+            let bottom = height * (row + 1) / rows;
+
+            Rect::new(left as i32, top as i32, right - left, bottom - top)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_zone_and_bounds() {
+        let mut camera = Camera::new(800, 600);
+        camera.set_dead_zone(160.0, 120.0);
+        camera.set_bounds(Rectangle::new(0.0, 0.0, 1600.0, 1200.0));
+
+        // dead zone 안의 움직임은 무시한다.
+        camera.follow(450.0, 320.0, 0.016);
+        assert_eq!((camera.x, camera.y), (0.0, 0.0));
+
+        // dead zone 을 벗어나면 대상이 가장자리에 오도록 움직인다.
+        camera.follow(600.0, 300.0, 0.016);
+        assert_eq!((camera.x, camera.y), (120.0, 0.0));
+
+        // 지도 밖은 보이지 않는다.
+        camera.follow(1590.0, 1190.0, 0.016);
+        assert_eq!((camera.x, camera.y), (800.0, 600.0));
+    }
+
+    #[test]
+    fn lerp_between_updates() {
+        let mut camera = Camera::new(800, 600);
+
+        camera.update(0.1);
+        camera.follow(500.0, 300.0, 0.1);
+        assert_eq!(camera.lerp(0.0).x, 0.0);
+        assert_eq!(camera.lerp(0.5).x, 50.0);
+        assert_eq!(camera.lerp(1.0).x, 100.0);
+
+        // 바로 옮기면 사이를 그리지 않는다.
+        camera.look_at(1000.0, 300.0);
+        assert_eq!(camera.lerp(0.0).x, 600.0);
+    }
+
+    #[test]
+    fn smoothing_follows_gradually() {
+        let mut camera = Camera::new(800, 600);
+        camera.set_smoothing(5.0);
+
+        camera.follow(500.0, 300.0, 0.1);
+        assert!(camera.x > 0.0 && camera.x < 100.0);
+
+        for _ in 0..100 {
+            camera.follow(500.0, 300.0, 0.1);
+        }
+        assert!((camera.x - 100.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn shake_decays() {
+        let mut camera = Camera::new(800, 600);
+        camera.shake(0.5);
+        camera.update(0.05);
+        assert!(camera.trauma > 0.0);
+
+        camera.update(1.0);
+        assert_eq!(camera.trauma, 0.0);
+        assert_eq!(camera.view_rect(), Rect::new(0, 0, 800, 600));
+    }
+
+    #[test]
+    fn world_screen_round_trip() {
+        let mut camera = Camera::new(800, 600);
+        camera.look_at(1000.0, 700.0);
+
+        assert_eq!(camera.world_to_screen((1000.0, 700.0)), (400, 300));
+        assert_eq!(camera.screen_to_world((400, 300)), (1000.0, 700.0));
+        assert_eq!(
+            camera.world_rect_to_screen(Rect::new(610, 410, 16, 16)),
+            Rect::new(10, 10, 16, 16)
+        );
+    }
+
+    #[test]
+    fn zoom_keeps_center() {
+        let mut camera = Camera::new(800, 600);
+        camera.look_at(1000.0, 700.0);
+        camera.set_zoom(2.0);
+
+        assert_eq!(camera.center(), (1000.0, 700.0));
+        assert_eq!(camera.view_rect(), Rect::new(800, 550, 400, 300));
+        // 월드의 16 픽셀이 화면의 32 픽셀이 된다.
+        assert_eq!(
+            camera.world_rect_to_screen(Rect::new(1000, 700, 16, 16)),
+            Rect::new(400, 300, 32, 32)
+        );
+        assert_eq!(camera.screen_to_world((432, 300)), (1016.0, 700.0));
+    }
+
+    #[test]
+    fn rotation_round_trip() {
+        let mut camera = Camera::new(800, 600);
+        camera.look_at(0.0, 0.0);
+        camera.set_zoom(2.0);
+        camera.set_rotation(90.0);
+
+        // 시계방향으로 90도 돌리면 오른쪽에 있던 점이 아래에 보인다.
+        let (x, y) = camera.to_screen((10.0, 0.0));
+        assert!((x - 400.0).abs() < 1e-9 && (y - 320.0).abs() < 1e-9);
+
+        let (x, y) = camera.to_world(camera.to_screen((12.0, -7.0)));
+        assert!((x - 12.0).abs() < 1e-9 && (y + 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn split_views() {
+        assert_eq!(split_screen(800, 600, 1), vec![Rect::new(0, 0, 800, 600)]);
+        assert_eq!(
+            split_screen(800, 600, 2),
+            vec![Rect::new(0, 0, 400, 600), Rect::new(400, 0, 400, 600)]
+        );
+        assert_eq!(split_screen(800, 600, 3)[2], Rect::new(0, 300, 400, 300));
+
+        // 오른쪽 화면의 가운데는 카메라 중앙을 가리킨다.
+        let mut camera = Camera::new(800, 600);
+        camera.look_at(100.0, 100.0);
+        camera.set_screen_rect(split_screen(800, 600, 2)[1]);
+        assert_eq!(camera.center(), (100.0, 100.0));
+        assert_eq!(camera.world_to_screen((100.0, 100.0)), (600, 300));
+        assert_eq!(camera.screen_to_world((600, 300)), (100.0, 100.0));
+    }
+}
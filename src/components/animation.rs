@@ -1,4 +1,4 @@
-use crate::constant::*;
+use crate::camera::Camera;
 use crate::texture_manager::*;
 use crate::*;
 use sdl2::render::WindowCanvas;
@@ -70,15 +70,11 @@ impl AnimationComponent {
     pub fn render(
         &self,
         canvas: &mut WindowCanvas,
-        camera: &Rect,
+        camera: &Camera,
         texture_manager: &TextureManager,
     ) {
-        let rect = Rect::new(
-            transform_value(self.x as i32 - camera.x, WIDTH_RATIO),
-            transform_value(self.y as i32 - camera.y, HEIGHT_RATIO),
-            transform_value(self.w, WIDTH_RATIO),
-            transform_value(self.h, WIDTH_RATIO),
-        );
+        let rect =
+            camera.world_rect_to_screen(Rect::new(self.x as i32, self.y as i32, self.w, self.h));
 
         if !self.frames.is_empty() {
             let src = self.frames[self.frame];
@@ -89,7 +85,7 @@ impl AnimationComponent {
                         texture,
                         Some(src),
                         Some(rect),
-                        0.,
+                        camera.rotation,
                         None,
                         self.flip_h,
                         self.flip_v,
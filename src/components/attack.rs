@@ -1,4 +1,3 @@
-use sdl2::rect::Rect;
 use sdl2::render::WindowCanvas;
 
 use sdl2::gfx::primitives::DrawRenderer;
@@ -6,9 +5,8 @@ use sdl2::pixels::Color;
 
 use std::convert::TryInto;
 
+use crate::camera::Camera;
 use crate::components::AnimationComponent;
-use crate::constant::*;
-use crate::*;
 
 /// 공격 관련 처리
 #[derive(Clone, Debug)]
@@ -60,19 +58,20 @@ impl AttackComponent {
         }
     }
 
-    pub fn render(&self, canvas: &mut WindowCanvas, camera: &Rect, comp: &AnimationComponent) {
+    pub fn render(&self, canvas: &mut WindowCanvas, camera: &Camera, comp: &AnimationComponent) {
         // 공격 가능한 영역 그리기
         // 공격 가능한 영역 그리기
-        let center_x: f64 = (comp.x as i32 - camera.x + (comp.w as i32 / 2)) as f64;
-        let center_y: f64 = (comp.y as i32 - camera.y + (comp.h as i32 / 2)) as f64;
+        let (center_x, center_y) =
+            camera.world_to_screen((comp.x + comp.w as f64 / 2.0, comp.y + comp.h as f64 / 2.0));
+        let rotation = camera.rotation.round() as i32;
 
         canvas
             .filled_pie(
-                transform_value(center_x as i16, WIDTH_RATIO),
-                transform_value(center_y as i16, HEIGHT_RATIO),
-                32,
-                (self.deg - 30).try_into().unwrap(),
-                (self.deg + 30).try_into().unwrap(),
+                center_x as i16,
+                center_y as i16,
+                camera.scale(32.0) as i16,
+                (self.deg + rotation - 30).try_into().unwrap(),
+                (self.deg + rotation + 30).try_into().unwrap(),
                 if self.attacking {
                     Color::RGBA(
                         255,
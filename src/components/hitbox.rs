@@ -1,7 +1,7 @@
-use crate::constant::*;
+use crate::camera::Camera;
 use crate::*;
 use sdl2::pixels::Color;
-use sdl2::rect::Rect;
+use sdl2::rect::Point;
 use sdl2::render::WindowCanvas;
 
 /// hitbox coordinates
@@ -25,17 +25,26 @@ impl HitboxComponent {
         self.y = y;
     }
 
-    pub fn render(&self, canvas: &mut WindowCanvas, camera: &Rect) {
+    /// (x, y) 에 있는 entity 의 hitbox 를 그린다.
+    /// 두 update 사이를 그릴 때는 hitbox 의 x, y 대신 보간한 위치를 넘긴다.
+    pub fn render(&self, canvas: &mut WindowCanvas, camera: &Camera, (x, y): (f64, f64)) {
         // draw hitbox
-        let hitbox_transformed_rect = Rect::new(
-            transform_value((self.x + self.hx) as i32 - camera.x, WIDTH_RATIO),
-            transform_value((self.y + self.hy) as i32 - camera.y, HEIGHT_RATIO),
-            transform_value(self.w as u32, WIDTH_RATIO),
-            transform_value(self.h as u32, HEIGHT_RATIO),
-        );
+        // 카메라가 회전할 수 있으므로 네 꼭지점을 각각 옮겨서 잇는다.
+        let left = x + self.hx;
+        let top = y + self.hy;
+        let points: Vec<Point> = [
+            (left, top),
+            (left + self.w, top),
+            (left + self.w, top + self.h),
+            (left, top + self.h),
+            (left, top),
+        ]
+        .iter()
+        .map(|corner| camera.world_to_screen(*corner).into())
+        .collect();
 
         canvas.set_draw_color(Color::RGBA(255, 0, 0, 200));
-        canvas.draw_rect(hitbox_transformed_rect).unwrap();
+        canvas.draw_lines(&points[..]).unwrap();
     }
 
     pub fn get_rect(&self) -> Rectangle {
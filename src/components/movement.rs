@@ -4,15 +4,19 @@ use crate::*;
 /// 이동진행을 위한 부분
 #[derive(Copy, Clone, Debug)]
 pub struct MovementComponent {
-    pub x: f64,  // 기준 x위치
-    pub y: f64,  // 기준 y위치
-    pub px: f64, // 다음 tick으로 이동할 때 x위치
-    pub py: f64, // 다음 tick으로 이동할 때 y위치
+    pub x: f64,      // 기준 x위치
+    pub y: f64,      // 기준 y위치
+    pub px: f64,     // 다음 tick으로 이동할 때 x위치
+    pub py: f64,     // 다음 tick으로 이동할 때 y위치
+    pub last_x: f64, // 지난 tick의 x위치
+    pub last_y: f64, // 지난 tick의 y위치
     pub facing: Vector2<i32>,
     pub velocity: Vector2<f64>,
     max_velocity: f64,
     accelaration: f64,
     decelaration: f64,
+    /// 이동할 수 있는 월드 영역
+    pub bounds: Rectangle,
 }
 
 impl MovementComponent {
@@ -30,14 +34,23 @@ impl MovementComponent {
             y,
             px: x,
             py: y,
+            last_x: x,
+            last_y: y,
             facing,
             velocity,
             max_velocity,
             accelaration,
             decelaration,
+            bounds: Rectangle::new(0.0, 0.0, WORLD_WIDTH as f64, WORLD_HEIGHT as f64),
         }
     }
 
+    /// 이동할 수 있는 월드 영역을 지정한다.
+    /// 지도를 읽은 후 지도의 크기로 지정한다.
+    pub fn set_bounds(&mut self, bounds: Rectangle) {
+        self.bounds = bounds;
+    }
+
     pub fn update_velocity(&mut self, dt: f64) {
         // dt는 1000 밀리초(1초) 기준으로 한 프레임의 크기이다.
         // 해당 캐릭터는 dt 만큼 감속된 값으로 이동하게된다.
@@ -103,13 +116,13 @@ impl MovementComponent {
     pub fn get_predict_y(&self, dt: f64) -> f64 {
         // 현재 속도상의 다음 y 위치를 구한다.
         let predict_y = self.y + self.velocity.1 * dt;
-        predict_y.clamp(0.0, WORLD_HEIGHT as f64)
+        predict_y.clamp(self.bounds.y, self.bounds.y + self.bounds.h)
     }
 
     pub fn get_predict_x(&self, dt: f64) -> f64 {
         // 현재 속도상의 다음 x 위치를 구한다.
         let predict_x = self.x + self.velocity.0 * dt;
-        predict_x.clamp(0.0, WORLD_WIDTH as f64)
+        predict_x.clamp(self.bounds.x, self.bounds.x + self.bounds.w)
     }
 
     pub fn update_predict(&mut self, dt: f64) {
@@ -123,8 +136,8 @@ impl MovementComponent {
         self.py += self.velocity.1 * dt;
 
         // x, y에 대한 Bound Condition
-        self.px = (self.px).clamp(0.0, WORLD_WIDTH as f64);
-        self.py = (self.py).clamp(0.0, WORLD_HEIGHT as f64);
+        self.px = (self.px).clamp(self.bounds.x, self.bounds.x + self.bounds.w);
+        self.py = (self.py).clamp(self.bounds.y, self.bounds.y + self.bounds.h);
     }
 
     /// 해당 캐릭터의 x 속도를 0으로 리셋한다.
@@ -154,14 +167,26 @@ impl MovementComponent {
     }
 
     /// 캐릭터의 이동 벡터를 설정한다.
+    /// 아날로그 스틱처럼 direction 이 1보다 작으면 그만큼 최대 속도를 줄인다.
     pub fn move_forward(&mut self, direction: (f64, f64), dt: f64) {
         self.velocity.0 += direction.0 * self.accelaration * dt;
         self.velocity.1 += direction.1 * self.accelaration * dt;
+
+        if direction.0 != 0.0 {
+            let limit = self.max_velocity * direction.0.abs().min(1.0);
+            self.velocity.0 = self.velocity.0.clamp(-limit, limit);
+        }
+        if direction.1 != 0.0 {
+            let limit = self.max_velocity * direction.1.abs().min(1.0);
+            self.velocity.1 = self.velocity.1.clamp(-limit, limit);
+        }
     }
 
     /// 해당 캐릭터를 움직이게한다.
     pub fn update(&mut self, _dt: f64) {
         // 먼저 계산한 px, py 값을 새로운 x, y값으로 전환한다.
+        self.last_x = self.x;
+        self.last_y = self.y;
 
         self.x = self.px;
         self.y = self.py;
@@ -173,19 +198,30 @@ impl MovementComponent {
     }
 
     /// x, y위치를 동시에 설정한다.
+    /// 순간 이동이므로 지난 tick의 위치도 같이 옮긴다.
     pub fn set_pos(&mut self, pos: (f64, f64)) {
-        self.x = pos.0;
-        self.y = pos.1;
+        self.set_pos_x(pos.0);
+        self.set_pos_y(pos.1);
     }
 
     /// x 위치를 설정한다.
     pub fn set_pos_x(&mut self, x: f64) {
         self.x = x;
+        self.last_x = x;
     }
 
     /// y 위치를 설정한다.
     pub fn set_pos_y(&mut self, y: f64) {
         self.y = y;
+        self.last_y = y;
+    }
+
+    /// 지난 tick과 이번 tick 사이의 위치. alpha 는 0.0 ~ 1.0
+    pub fn lerp_pos(&self, alpha: f64) -> (f64, f64) {
+        (
+            self.last_x + (self.x - self.last_x) * alpha,
+            self.last_y + (self.y - self.last_y) * alpha,
+        )
     }
 
     /// x, y위치를 동시에 가져온다.
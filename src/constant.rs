@@ -1,7 +1,7 @@
-/// 실제 물리화면의 가로크기
+/// 처음 만드는 창의 가로크기
 pub const SCREEN_WIDTH: u32 = 800;
 
-/// 실제 물리화면의 세로크기
+/// 처음 만드는 창의 세로크기
 pub const SCREEN_HEIGHT: u32 = 600;
 
 /// 카메라로 보이는 VIEWPORT의 크기는 VIRTUAL_* 인 것으로 가정한다.
@@ -33,8 +33,12 @@ pub const REVERSE_HEIGHT_RATIO: f32 = 1.0 / HEIGHT_RATIO;
 /// 1/60 초에 대한 마이크로초 (안쓰임)
 pub const TIME_SPAN: u32 = 1_000_000_000 / 60;
 
-/// delta t설정하기 (안쓰임)
+/// 고정 update 간격 (초)
 pub const DELTA_T: f64 = 1.0f64 / 60.0;
 
+/// 한 frame 에 update 로 넘길 수 있는 최대 시간 (초)
+/// 창을 끌거나 멈췄다 돌아왔을 때 update 가 한꺼번에 밀리지 않게 한다.
+pub const MAX_FRAME_TIME: f64 = 0.25;
+
 /// asset 파일이 들어있는 곳
 pub const ASSET_DIR: &str = "assets/";
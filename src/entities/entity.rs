@@ -1,3 +1,4 @@
+use crate::camera::Camera;
 use crate::components::*;
 use crate::entities::*;
 use crate::texture_manager::*;
@@ -20,6 +21,8 @@ pub struct Entity {
     pub attack: Option<AttackComponent>,
     pub alive: bool,
     pub skill: HashMap<String, Timer>,
+    /// 깊이 정렬시 사용할 명시적인 높이 순서 (없으면 0)
+    pub z: Option<i32>,
     //pub timer: Option<crate::timer::Timer>,
     //pub timer_result: Option<crate::timer::TimerResult>,
 }
@@ -35,6 +38,7 @@ impl Entity {
             attack: Some(AttackComponent::new()),
             alive: true,
             skill: HashMap::new(),
+            z: None,
         }
     }
 
@@ -78,6 +82,13 @@ impl Entity {
         ));
     }
 
+    /// 이동할 수 있는 월드 영역을 지정한다.
+    pub fn set_bounds(&mut self, bounds: Rectangle) {
+        if let Some(movement) = self.movement.as_mut() {
+            movement.set_bounds(bounds);
+        }
+    }
+
     pub fn set_attack(&mut self) {
         self.attack = Some(AttackComponent::new());
     }
@@ -179,23 +190,55 @@ impl Entity {
         }
     }
 
+    /// 그리기 전에 애니메이션을 지난 update 와 이번 update 사이의 위치에 놓는다.
+    pub fn interpolate(&mut self, alpha: f64) {
+        if let Some(movement) = self.movement.as_ref() {
+            let direction = facing_to_direction(movement.get_facing());
+            if let Some(animation) = self.animation.get_mut(&direction) {
+                let (x, y) = movement.lerp_pos(alpha);
+                animation.x = x;
+                animation.y = y;
+            }
+        }
+    }
+
+    /// 깊이 정렬에 사용할 월드 좌표상의 바닥 y값
+    /// 현재 애니메이션이 있다면 그 아래쪽, 없으면 hitbox의 아래쪽을 사용한다.
+    pub fn depth(&self) -> f64 {
+        if let Some(movement) = self.movement.as_ref() {
+            let direction = facing_to_direction(movement.get_facing());
+            if let Some(animation) = self.animation.get(&direction) {
+                return animation.y + animation.h as f64;
+            }
+        }
+
+        match self.hitbox.as_ref() {
+            Some(hitbox) => hitbox.y + hitbox.hy + hitbox.h,
+            None => self.movement.map(|m| m.get_pos_y()).unwrap_or(0.0),
+        }
+    }
+
     pub fn render(
         &self,
         canvas: &mut WindowCanvas,
-        camera: &Rect,
+        camera: &Camera,
         texture_manager: Option<&TextureManager>,
     ) {
         if let Some(movement) = self.movement.as_ref() {
+            // interpolate 한 위치. 애니메이션이 없다면 지금 위치
+            let mut pos = movement.get_pos();
+
             let direction = facing_to_direction(movement.get_facing());
             if let Some(animation) = self.animation.get(&direction) {
                 if let Some(attack) = self.attack.as_ref() {
                     attack.render(canvas, camera, animation);
                 }
                 animation.render(canvas, camera, texture_manager.unwrap());
+                pos = (animation.x, animation.y);
             }
 
             if let Some(hitbox) = self.hitbox.as_ref() {
-                hitbox.render(canvas, camera);
+                hitbox.render(canvas, camera, pos);
             }
         }
     }
@@ -0,0 +1,100 @@
+//! 고정 간격 게임 루프
+//! frame 마다 흐른 시간을 모아 두었다가 DELTA_T 간격으로 update 하고,
+//! 남은 시간의 비율(alpha)로 지난 update 와 이번 update 사이를 그린다.
+
+use sdl2::TimerSubsystem;
+
+use std::time::Duration;
+
+/// 흐른 시간을 모아 고정 간격의 update 횟수로 바꾼다.
+#[derive(Clone, Debug)]
+pub struct FixedStep {
+    pub step: f64,      // update 간격 (초)
+    pub max_frame: f64, // 한 frame 에 더할 수 있는 최대 시간 (초)
+    accumulator: f64,
+}
+
+impl FixedStep {
+    pub fn new(step: f64, max_frame: f64) -> FixedStep {
+        FixedStep {
+            step,
+            max_frame,
+            accumulator: 0.0,
+        }
+    }
+
+    /// 지난 frame 의 시간을 더하고 이번 frame 에 update 할 횟수를 돌려준다.
+    /// 멈췄다 돌아온 frame 은 max_frame 으로 잘라서 update 가 밀리지 않게 한다.
+    pub fn advance(&mut self, frame_time: f64) -> u32 {
+        self.accumulator += frame_time.clamp(0.0, self.max_frame);
+
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        steps
+    }
+
+    /// 지난 update 에서 다음 update 까지의 비율 (0.0 ~ 1.0)
+    pub fn alpha(&self) -> f64 {
+        self.accumulator / self.step
+    }
+}
+
+/// SDL 의 고해상도 카운터로 frame 시간을 잰다.
+pub struct FrameClock {
+    frequency: f64,
+    last: u64,
+}
+
+impl FrameClock {
+    pub fn new(timer: &TimerSubsystem) -> FrameClock {
+        FrameClock {
+            frequency: timer.performance_frequency() as f64,
+            last: timer.performance_counter(),
+        }
+    }
+
+    /// 지난 tick 부터 흐른 시간 (초)
+    pub fn tick(&mut self, timer: &TimerSubsystem) -> f64 {
+        let now = timer.performance_counter();
+        let elapsed = now.saturating_sub(self.last) as f64 / self.frequency;
+        self.last = now;
+
+        elapsed
+    }
+
+    /// 지난 tick 부터 1/fps 초가 될 때까지 기다린다. fps 가 0 이면 기다리지 않는다.
+    pub fn limit(&self, timer: &TimerSubsystem, fps: u32) {
+        if fps == 0 {
+            return;
+        }
+
+        let elapsed = timer.performance_counter().saturating_sub(self.last) as f64 / self.frequency;
+        let remain = 1.0 / fps as f64 - elapsed;
+        if remain > 0.0 {
+            std::thread::sleep(Duration::from_secs_f64(remain));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_step() {
+        let mut step = FixedStep::new(0.25, 1.0);
+
+        assert_eq!(step.advance(0.1), 0);
+        assert!((step.alpha() - 0.4).abs() < 1e-9);
+        assert_eq!(step.advance(0.4), 2);
+        assert_eq!(step.alpha(), 0.0);
+
+        // 오래 멈춘 frame 은 max_frame 만큼만 update 한다.
+        assert_eq!(step.advance(30.0), 4);
+        assert_eq!(step.advance(-1.0), 0);
+    }
+}
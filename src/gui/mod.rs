@@ -1,11 +1,9 @@
-use crate::constant::*;
+use crate::input::Input;
 use crate::texture_manager::*;
-use crate::*;
 
 use sdl2::event::Event;
 use sdl2::rect::Rect;
 use sdl2::render::WindowCanvas;
-use std::collections::HashSet;
 use uuid::Uuid;
 
 /// 화면 입력을 컨트롤할 수 있는
@@ -19,6 +17,7 @@ pub struct GuiElement {
     texture_normal: Sprite,
     texture_hover: Sprite,
     pub is_hover: bool,
+    pub is_pressed: bool, // 이 버튼 위에서 눌렀다.
     pub is_clicked: bool,
 }
 
@@ -62,6 +61,7 @@ impl GuiElement {
             texture_normal,
             texture_hover,
             is_hover: false,
+            is_pressed: false,
             is_clicked: false,
         }
     }
@@ -71,31 +71,26 @@ impl GuiElement {
     pub fn process_event(&mut self, _event: &Event) {}
 
     /// 마우스 입력부분만 여기서 처리
-    pub fn process_mouse(
-        &mut self,
-        x: i32,
-        y: i32,
-        new_buttons: &HashSet<sdl2::mouse::MouseButton>,
-        old_buttons: &HashSet<sdl2::mouse::MouseButton>,
-    ) {
-        // x와 y는 physical 데이터
-        // self.x와 self.y 는 transform 된 데이터
-        let rx = transform_value(x, REVERSE_WIDTH_RATIO);
-        let ry = transform_value(y, REVERSE_HEIGHT_RATIO);
-
-        self.is_hover = rx >= self.x
-            && rx <= self.x + self.w as i32
-            && ry >= self.y
-            && ry <= self.y + self.h as i32;
+    pub fn process_mouse(&mut self, input: &Input) {
+        // Viewport 로 바꾼 가상화면 좌표
+        let (x, y) = input.mouse_virtual();
+        self.is_hover = x >= self.x
+            && x <= self.x + self.w as i32
+            && y >= self.y
+            && y <= self.y + self.h as i32;
 
         // 버튼 press 체크
-        if self.is_hover && new_buttons.contains(&sdl2::mouse::MouseButton::Left) {
-            //println!("Left Button is down");
+        if self.is_hover && input.was_mouse_pressed(sdl2::mouse::MouseButton::Left) {
+            self.is_pressed = true;
         }
 
         // 버튼 release 체크
-        if self.is_hover && old_buttons.contains(&sdl2::mouse::MouseButton::Left) {
-            self.is_clicked = true;
+        // 이 버튼 위에서 누르고 뗐을 때만 클릭이다.
+        if input.was_mouse_release(sdl2::mouse::MouseButton::Left) {
+            if self.is_hover && self.is_pressed {
+                self.is_clicked = true;
+            }
+            self.is_pressed = false;
         }
     }
 
@@ -109,6 +104,7 @@ impl GuiElement {
 
     pub fn reset(&mut self) {
         self.is_hover = false;
+        self.is_pressed = false;
         self.is_clicked = false;
     }
 }
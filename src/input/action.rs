@@ -0,0 +1,389 @@
+//! 키 대신 행동(action) 이름으로 입력을 묻는다.
+//! 행동 하나에 여러 키, 마우스 버튼, 게임패드 버튼을 묶을 수 있고,
+//! 축(axis)은 -1.0 ~ 1.0 의 값을 돌려준다.
+
+use super::Input;
+
+use sdl2::controller::{Axis, Button};
+use sdl2::keyboard::Scancode;
+use sdl2::mouse::MouseButton;
+use std::collections::HashMap;
+
+/// 기본 행동 이름
+pub const MOVE_UP: &str = "move_up";
+pub const MOVE_DOWN: &str = "move_down";
+pub const MOVE_LEFT: &str = "move_left";
+pub const MOVE_RIGHT: &str = "move_right";
+pub const ATTACK: &str = "attack";
+pub const CONFIRM: &str = "confirm";
+pub const CANCEL: &str = "cancel";
+pub const PAUSE: &str = "pause";
+
+/// 설정 화면에 보여줄 행동 순서
+pub const ACTIONS: [&str; 8] = [
+    MOVE_UP, MOVE_DOWN, MOVE_LEFT, MOVE_RIGHT, ATTACK, CONFIRM, CANCEL, PAUSE,
+];
+
+/// 기본 축 이름
+pub const MOVE_X: &str = "move_x";
+pub const MOVE_Y: &str = "move_y";
+
+/// 행동에 묶는 입력 하나
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(Scancode),
+    Mouse(MouseButton),
+    Button(Button),
+}
+
+/// 축에 묶는 입력 하나
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AxisBinding {
+    /// (음의 방향, 양의 방향) 키
+    Keys(Scancode, Scancode),
+    /// (음의 방향, 양의 방향) 게임패드 버튼
+    Buttons(Button, Button),
+    /// 게임패드 아날로그 축
+    Gamepad(Axis),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ActionMap {
+    actions: HashMap<String, Vec<Binding>>,
+    axes: HashMap<String, Vec<AxisBinding>>,
+}
+
+impl ActionMap {
+    pub fn new() -> ActionMap {
+        ActionMap {
+            actions: HashMap::new(),
+            axes: HashMap::new(),
+        }
+    }
+
+    /// WASD/방향키 이동, Space 공격, Enter 확인, Escape 취소, P 일시 정지
+    pub fn default_map() -> ActionMap {
+        let mut map = ActionMap::new();
+
+        map.bind(MOVE_UP, Binding::Key(Scancode::W));
+        map.bind(MOVE_UP, Binding::Key(Scancode::Up));
+        map.bind(MOVE_UP, Binding::Button(Button::DPadUp));
+        map.bind(MOVE_DOWN, Binding::Key(Scancode::S));
+        map.bind(MOVE_DOWN, Binding::Key(Scancode::Down));
+        map.bind(MOVE_DOWN, Binding::Button(Button::DPadDown));
+        map.bind(MOVE_LEFT, Binding::Key(Scancode::A));
+        map.bind(MOVE_LEFT, Binding::Key(Scancode::Left));
+        map.bind(MOVE_LEFT, Binding::Button(Button::DPadLeft));
+        map.bind(MOVE_RIGHT, Binding::Key(Scancode::D));
+        map.bind(MOVE_RIGHT, Binding::Key(Scancode::Right));
+        map.bind(MOVE_RIGHT, Binding::Button(Button::DPadRight));
+
+        map.bind(ATTACK, Binding::Key(Scancode::Space));
+        map.bind(ATTACK, Binding::Button(Button::X));
+        map.bind(CONFIRM, Binding::Key(Scancode::Return));
+        map.bind(CONFIRM, Binding::Mouse(MouseButton::Left));
+        map.bind(CONFIRM, Binding::Button(Button::A));
+        map.bind(CANCEL, Binding::Key(Scancode::Escape));
+        map.bind(CANCEL, Binding::Button(Button::B));
+        map.bind(PAUSE, Binding::Key(Scancode::P));
+        map.bind(PAUSE, Binding::Button(Button::Start));
+
+        map.bind_axis(MOVE_X, AxisBinding::Keys(Scancode::A, Scancode::D));
+        map.bind_axis(MOVE_X, AxisBinding::Keys(Scancode::Left, Scancode::Right));
+        map.bind_axis(
+            MOVE_X,
+            AxisBinding::Buttons(Button::DPadLeft, Button::DPadRight),
+        );
+        map.bind_axis(MOVE_X, AxisBinding::Gamepad(Axis::LeftX));
+        map.bind_axis(MOVE_Y, AxisBinding::Keys(Scancode::W, Scancode::S));
+        map.bind_axis(MOVE_Y, AxisBinding::Keys(Scancode::Up, Scancode::Down));
+        map.bind_axis(
+            MOVE_Y,
+            AxisBinding::Buttons(Button::DPadUp, Button::DPadDown),
+        );
+        map.bind_axis(MOVE_Y, AxisBinding::Gamepad(Axis::LeftY));
+
+        map
+    }
+
+    /// 행동에 입력을 더한다. 이미 묶여 있다면 그대로 둔다.
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.actions.entry(action.to_owned()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// 행동에 묶인 입력을 모두 푼다.
+    pub fn unbind(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], |bindings| bindings)
+    }
+
+    /// 행동의 첫번째 키를 key 로 바꾼다.
+    /// 다른 행동이 key 를 쓰고 있었다면 그 행동은 이전 키를 대신 받는다. (서로 맞바꾼다)
+    /// 축도 같은 방법으로 맞바꾸므로 한 축의 양쪽이 같은 키가 되지 않는다.
+    pub fn set_key(&mut self, action: &str, key: Scancode) {
+        let old_key = self
+            .bindings(action)
+            .iter()
+            .find_map(|binding| match *binding {
+                Binding::Key(old_key) => Some(old_key),
+                _ => None,
+            });
+        if old_key == Some(key) {
+            return;
+        }
+
+        // 다른 행동에서 key 를 풀고, 이전 키가 있다면 그 자리에 넣는다.
+        for (name, bindings) in self.actions.iter_mut() {
+            if name == action || !bindings.contains(&Binding::Key(key)) {
+                continue;
+            }
+            match old_key {
+                Some(old_key) if !bindings.contains(&Binding::Key(old_key)) => {
+                    for binding in bindings.iter_mut() {
+                        if *binding == Binding::Key(key) {
+                            *binding = Binding::Key(old_key);
+                        }
+                    }
+                }
+                _ => bindings.retain(|binding| *binding != Binding::Key(key)),
+            }
+        }
+
+        let bindings = self.actions.entry(action.to_owned()).or_default();
+        // 같은 키가 두번 들어가지 않게 한다.
+        bindings.retain(|binding| *binding != Binding::Key(key));
+
+        match old_key {
+            Some(old_key) => {
+                for binding in bindings.iter_mut() {
+                    if *binding == Binding::Key(old_key) {
+                        *binding = Binding::Key(key);
+                    }
+                }
+
+                for binding in self.axes.values_mut().flatten() {
+                    if let AxisBinding::Keys(negative, positive) = binding {
+                        for slot in [negative, positive].iter_mut() {
+                            if **slot == old_key {
+                                **slot = key;
+                            } else if **slot == key {
+                                **slot = old_key;
+                            }
+                        }
+                    }
+                }
+            }
+            None => bindings.push(Binding::Key(key)),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<Binding>)> {
+        self.actions.iter()
+    }
+
+    pub fn iter_axes(&self) -> impl Iterator<Item = (&String, &Vec<AxisBinding>)> {
+        self.axes.iter()
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        let bindings = self.axes.entry(axis.to_owned()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind_axis(&mut self, axis: &str) {
+        self.axes.remove(axis);
+    }
+
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map_or(&[], |bindings| bindings)
+    }
+
+    /// 이번 프레임에 눌렸는가
+    pub fn pressed(&self, input: &Input, action: &str) -> bool {
+        self.pressed_by(input, None, action)
+    }
+
+    /// 눌린 채로 있는가
+    pub fn held(&self, input: &Input, action: &str) -> bool {
+        self.held_by(input, None, action)
+    }
+
+    /// 이번 프레임에 떼어졌는가
+    pub fn released(&self, input: &Input, action: &str) -> bool {
+        self.released_by(input, None, action)
+    }
+
+    /// 축의 값 (-1.0 ~ 1.0)
+    pub fn axis(&self, input: &Input, axis: &str) -> f64 {
+        self.axis_by(input, None, axis)
+    }
+
+    /// player 번째 플레이어의 입력만 본다. None 이면 모든 입력을 본다.
+    /// 키보드와 마우스는 첫번째 플레이어의 것이다.
+    pub fn pressed_by(&self, input: &Input, player: Option<usize>, action: &str) -> bool {
+        let keyboard = player.unwrap_or(0) == 0;
+
+        self.bindings(action).iter().any(|binding| match *binding {
+            Binding::Key(key) => keyboard && input.was_key_pressed(key),
+            Binding::Mouse(button) => keyboard && input.was_mouse_pressed(button),
+            Binding::Button(button) => input.was_button_pressed(player, button),
+        })
+    }
+
+    pub fn held_by(&self, input: &Input, player: Option<usize>, action: &str) -> bool {
+        let keyboard = player.unwrap_or(0) == 0;
+
+        self.bindings(action).iter().any(|binding| match *binding {
+            Binding::Key(key) => keyboard && input.is_key_held(key),
+            Binding::Mouse(button) => keyboard && input.is_mouse_held(button),
+            Binding::Button(button) => input.is_button_held(player, button),
+        })
+    }
+
+    pub fn released_by(&self, input: &Input, player: Option<usize>, action: &str) -> bool {
+        let keyboard = player.unwrap_or(0) == 0;
+
+        self.bindings(action).iter().any(|binding| match *binding {
+            Binding::Key(key) => keyboard && input.was_key_release(key),
+            Binding::Mouse(button) => keyboard && input.was_mouse_release(button),
+            Binding::Button(button) => input.was_button_release(player, button),
+        })
+    }
+
+    /// 여러 입력이 묶여 있다면 가장 크게 기울어진 값을 쓴다.
+    /// 아날로그 축은 dead zone 을 뺀 값이다.
+    pub fn axis_by(&self, input: &Input, player: Option<usize>, axis: &str) -> f64 {
+        let keyboard = player.unwrap_or(0) == 0;
+
+        self.axis_bindings(axis)
+            .iter()
+            .map(|binding| match *binding {
+                AxisBinding::Keys(negative, positive) if keyboard => {
+                    digital_axis(input.is_key_held(negative), input.is_key_held(positive))
+                }
+                AxisBinding::Keys(_, _) => 0.0,
+                AxisBinding::Buttons(negative, positive) => digital_axis(
+                    input.is_button_held(player, negative),
+                    input.is_button_held(player, positive),
+                ),
+                AxisBinding::Gamepad(axis) => input.axis(player, axis),
+            })
+            .max_by(|a, b| a.abs().partial_cmp(&b.abs()).unwrap())
+            .unwrap_or(0.0)
+    }
+}
+
+fn digital_axis(negative: bool, positive: bool) -> f64 {
+    match (negative, positive) {
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pressed_held_released() {
+        let map = ActionMap::default_map();
+        let mut input = Input::default();
+
+        input.key_down_event(&Some(Scancode::Up));
+        assert!(map.pressed(&input, MOVE_UP));
+        assert!(map.held(&input, MOVE_UP));
+
+        input.begin_new_frame();
+        assert!(!map.pressed(&input, MOVE_UP));
+        assert!(map.held(&input, MOVE_UP));
+
+        input.key_up_event(&Some(Scancode::Up));
+        assert!(map.released(&input, MOVE_UP));
+        assert!(!map.held(&input, MOVE_UP));
+
+        input.mouse_down_event(MouseButton::Left);
+        assert!(map.pressed(&input, CONFIRM));
+        assert!(!map.pressed(&input, "unknown"));
+    }
+
+    #[test]
+    fn axis() {
+        let map = ActionMap::default_map();
+        let mut input = Input::default();
+
+        input.key_down_event(&Some(Scancode::A));
+        assert_eq!(map.axis(&input, MOVE_X), -1.0);
+
+        // 양쪽을 모두 누르면 움직이지 않는다.
+        input.key_down_event(&Some(Scancode::D));
+        assert_eq!(map.axis(&input, MOVE_X), 0.0);
+
+        input.button_down_event(0, Button::DPadDown);
+        assert_eq!(map.axis(&input, MOVE_Y), 1.0);
+
+        input.button_up_event(0, Button::DPadDown);
+        input.axis_event(0, Axis::LeftY, i16::MIN);
+        assert_eq!(map.axis(&input, MOVE_Y), -1.0);
+    }
+
+    #[test]
+    fn per_player() {
+        let map = ActionMap::default_map();
+        let mut input = Input::default();
+
+        input.key_down_event(&Some(Scancode::Space));
+        input.button_down_event(5, Button::A);
+        input.button_down_event(6, Button::X);
+        input.axis_event(6, Axis::LeftX, i16::MAX);
+
+        // 키보드는 첫번째 플레이어이다.
+        assert!(map.held_by(&input, Some(0), ATTACK));
+        assert!(map.pressed_by(&input, Some(0), CONFIRM));
+        assert!(!map.pressed_by(&input, Some(1), CONFIRM));
+        assert!(map.held_by(&input, Some(1), ATTACK));
+        assert_eq!(map.axis_by(&input, Some(0), MOVE_X), 0.0);
+        assert_eq!(map.axis_by(&input, Some(1), MOVE_X), 1.0);
+    }
+
+    #[test]
+    fn set_key() {
+        let mut map = ActionMap::default_map();
+        let mut input = Input::default();
+
+        map.set_key(MOVE_LEFT, Scancode::J);
+        assert_eq!(map.bindings(MOVE_LEFT)[0], Binding::Key(Scancode::J));
+
+        // 축도 새 키를 따른다.
+        input.key_down_event(&Some(Scancode::J));
+        assert_eq!(map.axis(&input, MOVE_X), -1.0);
+        input.key_up_event(&Some(Scancode::J));
+        input.key_down_event(&Some(Scancode::A));
+        assert_eq!(map.axis(&input, MOVE_X), 0.0);
+    }
+
+    #[test]
+    fn set_key_swaps_conflict() {
+        let mut map = ActionMap::default_map();
+        let mut input = Input::default();
+
+        // D 는 move_right 의 키였으므로 move_right 는 A 를 받는다.
+        map.set_key(MOVE_LEFT, Scancode::D);
+        assert_eq!(map.bindings(MOVE_LEFT)[0], Binding::Key(Scancode::D));
+        assert_eq!(map.bindings(MOVE_RIGHT)[0], Binding::Key(Scancode::A));
+
+        input.key_down_event(&Some(Scancode::D));
+        assert_eq!(map.axis(&input, MOVE_X), -1.0);
+        input.key_up_event(&Some(Scancode::D));
+        input.key_down_event(&Some(Scancode::A));
+        assert_eq!(map.axis(&input, MOVE_X), 1.0);
+    }
+}
@@ -0,0 +1,68 @@
+use sdl2::controller::GameController;
+use sdl2::event::Event;
+use sdl2::GameControllerSubsystem;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// keeps the connected game controllers open
+/// SDL only sends controller events for controllers that are open
+pub struct Gamepads {
+    subsystem: GameControllerSubsystem,
+    /// controllers by joystick instance id
+    controllers: HashMap<u32, GameController>,
+}
+
+impl Gamepads {
+    /// open every controller that is already connected
+    pub fn new(subsystem: GameControllerSubsystem) -> Gamepads {
+        let mut gamepads = Gamepads {
+            subsystem,
+            controllers: HashMap::new(),
+        };
+
+        let count = gamepads.subsystem.num_joysticks().unwrap_or(0);
+        for index in 0..count {
+            gamepads.open(index);
+        }
+
+        gamepads
+    }
+
+    /// handle hot-plug events; the event should still be passed on to Input
+    pub fn process_event(&mut self, event: &Event) {
+        match event {
+            Event::ControllerDeviceAdded { which, .. } => self.open(*which),
+            Event::ControllerDeviceRemoved { which, .. } => {
+                if let Some(controller) = self.controllers.remove(which) {
+                    println!("controller removed : {}", controller.name());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// open the controller at a joystick device index
+    fn open(&mut self, index: u32) {
+        if !self.subsystem.is_game_controller(index) {
+            return;
+        }
+
+        match self.subsystem.open(index) {
+            Ok(controller) => {
+                if let Entry::Vacant(entry) = self.controllers.entry(controller.instance_id()) {
+                    println!("controller added : {}", controller.name());
+                    entry.insert(controller);
+                }
+            }
+            Err(e) => println!("failed to open controller {} : {}", index, e),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.controllers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.controllers.is_empty()
+    }
+}
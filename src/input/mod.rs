@@ -1,11 +1,89 @@
+use sdl2::controller::{Axis, Button};
+use sdl2::event::Event;
 use sdl2::keyboard::Scancode;
+use sdl2::mouse::{MouseButton, MouseWheelDirection};
 use std::collections::HashMap;
 
+pub mod action;
+pub mod gamepad;
+pub mod record;
+pub mod source;
+
+/// default dead zone of the analog sticks and triggers (0.0 ~ 1.0)
+pub const DEFAULT_DEAD_ZONE: f64 = 0.2;
+
+/// how far (in virtual pixels) the mouse has to move with a button held to start a drag
+pub const DRAG_THRESHOLD: i32 = 4;
+
+/// a mouse drag in virtual screen coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Drag {
+    pub start: (i32, i32),
+    pub end: (i32, i32),
+}
+
+impl Drag {
+    fn is_moved(&self) -> bool {
+        (self.end.0 - self.start.0).abs() >= DRAG_THRESHOLD
+            || (self.end.1 - self.start.1).abs() >= DRAG_THRESHOLD
+    }
+}
+
+/// button and axis state of one game controller
 #[derive(Default)]
+struct PadState {
+    held_buttons: HashMap<Button, bool>,
+    pressed_buttons: HashMap<Button, bool>,
+    release_buttons: HashMap<Button, bool>,
+    axes: HashMap<Axis, i16>,
+}
+
 pub struct Input {
     held_keys: HashMap<Scancode, bool>,
     pressed_keys: HashMap<Scancode, bool>,
     release_keys: HashMap<Scancode, bool>,
+    held_mouse: HashMap<MouseButton, bool>,
+    pressed_mouse: HashMap<MouseButton, bool>,
+    release_mouse: HashMap<MouseButton, bool>,
+    double_click_mouse: HashMap<MouseButton, bool>,
+    /// mouse position in window coordinates
+    mouse_position: (i32, i32),
+    /// mouse position in virtual screen coordinates
+    mouse_virtual: (i32, i32),
+    /// wheel movement during the current frame
+    wheel: (i32, i32),
+    /// virtual position where each held button went down
+    press_origin: HashMap<MouseButton, (i32, i32)>,
+    /// drags that ended during the current frame
+    drag_ended: HashMap<MouseButton, Drag>,
+    /// controllers by joystick instance id
+    pads: HashMap<u32, PadState>,
+    /// player slot -> joystick instance id
+    /// a controller takes the first free slot the first time it is used
+    players: Vec<Option<u32>>,
+    dead_zone: f64,
+}
+
+impl Default for Input {
+    fn default() -> Input {
+        Input {
+            held_keys: HashMap::new(),
+            pressed_keys: HashMap::new(),
+            release_keys: HashMap::new(),
+            held_mouse: HashMap::new(),
+            pressed_mouse: HashMap::new(),
+            release_mouse: HashMap::new(),
+            double_click_mouse: HashMap::new(),
+            mouse_position: (0, 0),
+            mouse_virtual: (0, 0),
+            wheel: (0, 0),
+            press_origin: HashMap::new(),
+            drag_ended: HashMap::new(),
+            pads: HashMap::new(),
+            players: vec![],
+            dead_zone: DEFAULT_DEAD_ZONE,
+        }
+    }
 }
 
 impl Input {
@@ -14,6 +92,66 @@ impl Input {
     pub fn begin_new_frame(&mut self) {
         self.pressed_keys.clear();
         self.release_keys.clear();
+        self.pressed_mouse.clear();
+        self.release_mouse.clear();
+        self.double_click_mouse.clear();
+        self.wheel = (0, 0);
+        self.drag_ended.clear();
+        for pad in self.pads.values_mut() {
+            pad.pressed_buttons.clear();
+            pad.release_buttons.clear();
+        }
+    }
+
+    /// forget every held key and button, e.g. when a state regains focus
+    /// and may have missed the release events; controller player slots are kept
+    pub fn release_all(&mut self) {
+        self.begin_new_frame();
+        self.held_keys.clear();
+        self.held_mouse.clear();
+        self.press_origin.clear();
+        for pad in self.pads.values_mut() {
+            pad.held_buttons.clear();
+        }
+    }
+
+    /// feed an SDL event; key repeats are ignored
+    pub fn process_event(&mut self, event: &Event) {
+        match event {
+            Event::KeyDown {
+                scancode,
+                repeat: false,
+                ..
+            } => self.key_down_event(scancode),
+            Event::KeyUp { scancode, .. } => self.key_up_event(scancode),
+            Event::MouseButtonDown {
+                mouse_btn, clicks, ..
+            } => {
+                self.mouse_down_event(*mouse_btn);
+                // SDL counts the clicks that land close together in time and space
+                if *clicks >= 2 {
+                    self.double_click_mouse.insert(*mouse_btn, true);
+                }
+            }
+            Event::MouseButtonUp { mouse_btn, .. } => self.mouse_up_event(*mouse_btn),
+            Event::MouseWheel {
+                x, y, direction, ..
+            } => match direction {
+                MouseWheelDirection::Flipped => self.wheel_event(-*x, -*y),
+                _ => self.wheel_event(*x, *y),
+            },
+            Event::ControllerButtonDown { which, button, .. } => {
+                self.button_down_event(*which, *button)
+            }
+            Event::ControllerButtonUp { which, button, .. } => {
+                self.button_up_event(*which, *button)
+            }
+            Event::ControllerAxisMotion {
+                which, axis, value, ..
+            } => self.axis_event(*which, *axis, *value),
+            Event::ControllerDeviceRemoved { which, .. } => self.pad_removed(*which),
+            _ => (),
+        }
     }
 
     pub fn key_up_event(&mut self, scancode: &Option<Scancode>) {
@@ -31,6 +169,115 @@ impl Input {
         }
     }
 
+    pub fn mouse_up_event(&mut self, button: MouseButton) {
+        self.release_mouse.insert(button, true);
+        self.held_mouse.insert(button, false);
+    }
+
+    pub fn mouse_down_event(&mut self, button: MouseButton) {
+        self.pressed_mouse.insert(button, true);
+        self.held_mouse.insert(button, true);
+    }
+
+    pub fn wheel_event(&mut self, x: i32, y: i32) {
+        self.wheel.0 += x;
+        self.wheel.1 += y;
+    }
+
+    /// set the mouse position once per frame, after the events of the frame
+    /// `position` is in window coordinates, `virtual_position` in virtual screen coordinates
+    pub fn set_mouse_position(&mut self, position: (i32, i32), virtual_position: (i32, i32)) {
+        self.mouse_position = position;
+        self.mouse_virtual = virtual_position;
+
+        for (button, _) in self.pressed_mouse.iter().filter(|(_, pressed)| **pressed) {
+            self.press_origin.insert(*button, virtual_position);
+        }
+
+        for (button, _) in self.release_mouse.iter().filter(|(_, released)| **released) {
+            if let Some(start) = self.press_origin.remove(button) {
+                let drag = Drag {
+                    start,
+                    end: virtual_position,
+                };
+                if drag.is_moved() {
+                    self.drag_ended.insert(*button, drag);
+                }
+            }
+        }
+    }
+
+    pub fn button_up_event(&mut self, which: u32, button: Button) {
+        let pad = self.pads.entry(which).or_default();
+        pad.release_buttons.insert(button, true);
+        pad.held_buttons.insert(button, false);
+    }
+
+    pub fn button_down_event(&mut self, which: u32, button: Button) {
+        self.assign_player(which);
+        let pad = self.pads.entry(which).or_default();
+        pad.pressed_buttons.insert(button, true);
+        pad.held_buttons.insert(button, true);
+    }
+
+    pub fn axis_event(&mut self, which: u32, axis: Axis, value: i16) {
+        // a resting stick also sends small values, so only a real tilt takes a player slot
+        if normalize(value).abs() > self.dead_zone {
+            self.assign_player(which);
+        }
+        self.pads.entry(which).or_default().axes.insert(axis, value);
+    }
+
+    /// forget a disconnected controller; the other players keep their slots
+    pub fn pad_removed(&mut self, which: u32) {
+        self.pads.remove(&which);
+        for slot in self.players.iter_mut() {
+            if *slot == Some(which) {
+                *slot = None;
+            }
+        }
+    }
+
+    fn assign_player(&mut self, which: u32) {
+        if self.pad_player(which).is_some() {
+            return;
+        }
+
+        match self.players.iter().position(|slot| slot.is_none()) {
+            Some(idx) => self.players[idx] = Some(which),
+            None => self.players.push(Some(which)),
+        }
+    }
+
+    /// player slot of a controller
+    pub fn pad_player(&self, which: u32) -> Option<usize> {
+        self.players.iter().position(|slot| *slot == Some(which))
+    }
+
+    /// number of player slots handed out to controllers so far
+    pub fn pad_players(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn set_dead_zone(&mut self, dead_zone: f64) {
+        self.dead_zone = dead_zone.clamp(0.0, 0.99);
+    }
+
+    /// controllers of a player, or all of them when player is None
+    fn player_pads(&self, player: Option<usize>) -> Vec<&PadState> {
+        match player {
+            None => self.pads.values().collect(),
+            Some(player) => self
+                .players
+                .get(player)
+                .copied()
+                .flatten()
+                .and_then(|which| self.pads.get(&which))
+                .into_iter()
+                .collect(),
+        }
+    }
+
     /// check if a certain key was pressed during the current frame
     pub fn was_key_pressed(&self, key: Scancode) -> bool {
         *self.pressed_keys.get(&key).unwrap_or(&false)
@@ -45,4 +292,210 @@ impl Input {
     pub fn is_key_held(&self, key: Scancode) -> bool {
         *self.held_keys.get(&key).unwrap_or(&false)
     }
+
+    pub fn was_mouse_pressed(&self, button: MouseButton) -> bool {
+        *self.pressed_mouse.get(&button).unwrap_or(&false)
+    }
+
+    pub fn was_mouse_release(&self, button: MouseButton) -> bool {
+        *self.release_mouse.get(&button).unwrap_or(&false)
+    }
+
+    pub fn is_mouse_held(&self, button: MouseButton) -> bool {
+        *self.held_mouse.get(&button).unwrap_or(&false)
+    }
+
+    pub fn was_mouse_double_clicked(&self, button: MouseButton) -> bool {
+        *self.double_click_mouse.get(&button).unwrap_or(&false)
+    }
+
+    /// mouse position in window coordinates
+    pub fn mouse_position(&self) -> (i32, i32) {
+        self.mouse_position
+    }
+
+    /// mouse position in virtual screen coordinates
+    pub fn mouse_virtual(&self) -> (i32, i32) {
+        self.mouse_virtual
+    }
+
+    /// wheel movement during the current frame; positive y scrolls up
+    pub fn wheel(&self) -> (i32, i32) {
+        self.wheel
+    }
+
+    /// the drag in progress, once the mouse has moved far enough with the button held
+    pub fn drag(&self, button: MouseButton) -> Option<Drag> {
+        if !self.is_mouse_held(button) {
+            return None;
+        }
+
+        self.press_origin
+            .get(&button)
+            .map(|start| Drag {
+                start: *start,
+                end: self.mouse_virtual,
+            })
+            .filter(|drag| drag.is_moved())
+    }
+
+    /// the drag that ended with the button released during the current frame
+    pub fn drag_released(&self, button: MouseButton) -> Option<Drag> {
+        self.drag_ended.get(&button).copied()
+    }
+
+    pub fn was_button_pressed(&self, player: Option<usize>, button: Button) -> bool {
+        self.player_pads(player)
+            .iter()
+            .any(|pad| *pad.pressed_buttons.get(&button).unwrap_or(&false))
+    }
+
+    pub fn was_button_release(&self, player: Option<usize>, button: Button) -> bool {
+        self.player_pads(player)
+            .iter()
+            .any(|pad| *pad.release_buttons.get(&button).unwrap_or(&false))
+    }
+
+    pub fn is_button_held(&self, player: Option<usize>, button: Button) -> bool {
+        self.player_pads(player)
+            .iter()
+            .any(|pad| *pad.held_buttons.get(&button).unwrap_or(&false))
+    }
+
+    /// value of a controller axis, -1.0 ~ 1.0, with the dead zone removed
+    /// sticks use a radial dead zone so diagonals are not cut off
+    /// with several controllers the one pushed the furthest wins
+    pub fn axis(&self, player: Option<usize>, axis: Axis) -> f64 {
+        self.player_pads(player)
+            .iter()
+            .map(|pad| {
+                let value = |axis| normalize(*pad.axes.get(&axis).unwrap_or(&0));
+                let pair = match axis {
+                    Axis::LeftX => Some((Axis::LeftX, Axis::LeftY)),
+                    Axis::LeftY => Some((Axis::LeftY, Axis::LeftX)),
+                    Axis::RightX => Some((Axis::RightX, Axis::RightY)),
+                    Axis::RightY => Some((Axis::RightY, Axis::RightX)),
+                    _ => None,
+                };
+
+                match pair {
+                    Some((this, other)) => {
+                        let (x, y) = (value(this), value(other));
+                        let length = (x * x + y * y).sqrt();
+                        if length <= self.dead_zone {
+                            0.0
+                        } else {
+                            let scaled =
+                                ((length - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0);
+                            x / length * scaled
+                        }
+                    }
+                    None => {
+                        let v = value(axis);
+                        if v.abs() <= self.dead_zone {
+                            0.0
+                        } else {
+                            v.signum() * (v.abs() - self.dead_zone) / (1.0 - self.dead_zone)
+                        }
+                    }
+                }
+            })
+            .max_by(|a, b| a.abs().partial_cmp(&b.abs()).unwrap())
+            .unwrap_or(0.0)
+    }
+}
+
+/// raw axis value to -1.0 ~ 1.0
+fn normalize(value: i16) -> f64 {
+    (value as f64 / i16::MAX as f64).clamp(-1.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn player_slots() {
+        let mut input = Input::default();
+
+        input.button_down_event(7, Button::A);
+        input.button_down_event(3, Button::B);
+        assert_eq!(input.pad_player(7), Some(0));
+        assert_eq!(input.pad_player(3), Some(1));
+        assert!(input.was_button_pressed(Some(1), Button::B));
+        assert!(!input.was_button_pressed(Some(0), Button::B));
+        assert!(input.was_button_pressed(None, Button::B));
+
+        // a freed slot goes to the next controller that is used
+        input.pad_removed(7);
+        input.axis_event(9, Axis::LeftX, 100);
+        assert_eq!(input.pad_player(9), None);
+        input.axis_event(9, Axis::LeftX, i16::MAX);
+        assert_eq!(input.pad_player(9), Some(0));
+        assert_eq!(input.pad_player(3), Some(1));
+    }
+
+    #[test]
+    fn mouse_drag() {
+        let mut input = Input::default();
+
+        input.mouse_down_event(MouseButton::Left);
+        input.set_mouse_position((20, 20), (10, 10));
+        assert!(input.was_mouse_pressed(MouseButton::Left));
+        assert_eq!(input.drag(MouseButton::Left), None);
+
+        input.begin_new_frame();
+        input.wheel_event(0, 1);
+        input.wheel_event(0, 1);
+        input.set_mouse_position((40, 30), (20, 15));
+        assert_eq!(input.wheel(), (0, 2));
+        assert_eq!(
+            input.drag(MouseButton::Left),
+            Some(Drag {
+                start: (10, 10),
+                end: (20, 15)
+            })
+        );
+
+        input.begin_new_frame();
+        input.mouse_up_event(MouseButton::Left);
+        input.set_mouse_position((40, 30), (20, 15));
+        assert_eq!(input.wheel(), (0, 0));
+        assert_eq!(input.drag(MouseButton::Left), None);
+        assert_eq!(
+            input
+                .drag_released(MouseButton::Left)
+                .map(|drag| drag.start),
+            Some((10, 10))
+        );
+
+        // a click without moving is not a drag
+        input.begin_new_frame();
+        input.mouse_down_event(MouseButton::Right);
+        input.mouse_up_event(MouseButton::Right);
+        input.set_mouse_position((40, 30), (21, 15));
+        assert_eq!(input.drag_released(MouseButton::Left), None);
+        assert_eq!(input.drag_released(MouseButton::Right), None);
+        assert_eq!(input.mouse_position(), (40, 30));
+    }
+
+    #[test]
+    fn dead_zone() {
+        let mut input = Input::default();
+
+        input.axis_event(0, Axis::LeftX, i16::MAX / 10);
+        assert_eq!(input.axis(Some(0), Axis::LeftX), 0.0);
+
+        input.axis_event(0, Axis::LeftX, i16::MAX);
+        assert_eq!(input.axis(Some(0), Axis::LeftX), 1.0);
+
+        // a diagonal leaves the radial dead zone even when each axis alone would not
+        input.axis_event(0, Axis::LeftX, i16::MAX / 6);
+        input.axis_event(0, Axis::LeftY, i16::MAX / 6);
+        assert!(input.axis(Some(0), Axis::LeftX) > 0.0);
+
+        input.axis_event(0, Axis::TriggerLeft, i16::MAX / 2);
+        let trigger = input.axis(None, Axis::TriggerLeft);
+        assert!((trigger - 0.375).abs() < 1e-3);
+    }
 }
@@ -0,0 +1,659 @@
+//! 입력 녹화와 재생
+//! 테스터가 겪은 버그를 다시 보기 위해 매 프레임의 dt, 이벤트, 마우스 상태를 RON 파일로 남긴다.
+//! 재생할 때는 녹화할 때의 난수 seed 와 창 크기를 그대로 쓴다.
+//!
+//! 파일의 첫 줄은 seed, 창 크기, 설정이고 다음 줄부터 한 줄에 한 프레임씩 적는다.
+//! 프레임마다 바로 파일에 쓰므로 게임이 중간에 죽어도 그때까지의 입력은 남는다.
+
+use super::source::{FrameInput, InputSource};
+use crate::settings::Settings;
+
+use sdl2::controller::{Axis, Button};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod, Scancode};
+use sdl2::mouse::{MouseButton, MouseState, MouseWheelDirection};
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// 파일에 남기는 이벤트
+/// 게임에 쓰이는 이벤트만 남기고, 키와 버튼은 SDL 의 값이나 이름으로 적는다.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RecordedEvent {
+    Quit,
+    WindowResized(i32, i32),
+    KeyDown {
+        keycode: Option<i32>,
+        scancode: Option<i32>,
+        keymod: u16,
+        repeat: bool,
+    },
+    KeyUp {
+        keycode: Option<i32>,
+        scancode: Option<i32>,
+        keymod: u16,
+        repeat: bool,
+    },
+    TextEditing {
+        text: String,
+        start: i32,
+        length: i32,
+    },
+    TextInput(String),
+    MouseMotion {
+        state: u32,
+        x: i32,
+        y: i32,
+        xrel: i32,
+        yrel: i32,
+    },
+    MouseButtonDown {
+        button: u8,
+        clicks: u8,
+        x: i32,
+        y: i32,
+    },
+    MouseButtonUp {
+        button: u8,
+        clicks: u8,
+        x: i32,
+        y: i32,
+    },
+    MouseWheel {
+        x: i32,
+        y: i32,
+        direction: u32,
+    },
+    ControllerAxisMotion {
+        which: u32,
+        axis: String,
+        value: i16,
+    },
+    ControllerButtonDown {
+        which: u32,
+        button: String,
+    },
+    ControllerButtonUp {
+        which: u32,
+        button: String,
+    },
+    ControllerDeviceAdded(u32),
+    ControllerDeviceRemoved(u32),
+}
+
+impl RecordedEvent {
+    /// 남길 필요가 없는 이벤트는 None
+    pub fn from_event(event: &Event) -> Option<RecordedEvent> {
+        let recorded = match event {
+            Event::Quit { .. } => RecordedEvent::Quit,
+            Event::Window {
+                win_event: WindowEvent::Resized(w, h),
+                ..
+            }
+            | Event::Window {
+                win_event: WindowEvent::SizeChanged(w, h),
+                ..
+            } => RecordedEvent::WindowResized(*w, *h),
+            Event::KeyDown {
+                keycode,
+                scancode,
+                keymod,
+                repeat,
+                ..
+            } => RecordedEvent::KeyDown {
+                keycode: keycode.map(|k| k as i32),
+                scancode: scancode.map(|s| s as i32),
+                keymod: keymod.bits(),
+                repeat: *repeat,
+            },
+            Event::KeyUp {
+                keycode,
+                scancode,
+                keymod,
+                repeat,
+                ..
+            } => RecordedEvent::KeyUp {
+                keycode: keycode.map(|k| k as i32),
+                scancode: scancode.map(|s| s as i32),
+                keymod: keymod.bits(),
+                repeat: *repeat,
+            },
+            Event::TextEditing {
+                text,
+                start,
+                length,
+                ..
+            } => RecordedEvent::TextEditing {
+                text: text.clone(),
+                start: *start,
+                length: *length,
+            },
+            Event::TextInput { text, .. } => RecordedEvent::TextInput(text.clone()),
+            Event::MouseMotion {
+                mousestate,
+                x,
+                y,
+                xrel,
+                yrel,
+                ..
+            } => RecordedEvent::MouseMotion {
+                state: mousestate.to_sdl_state(),
+                x: *x,
+                y: *y,
+                xrel: *xrel,
+                yrel: *yrel,
+            },
+            Event::MouseButtonDown {
+                mouse_btn,
+                clicks,
+                x,
+                y,
+                ..
+            } => RecordedEvent::MouseButtonDown {
+                button: *mouse_btn as u8,
+                clicks: *clicks,
+                x: *x,
+                y: *y,
+            },
+            Event::MouseButtonUp {
+                mouse_btn,
+                clicks,
+                x,
+                y,
+                ..
+            } => RecordedEvent::MouseButtonUp {
+                button: *mouse_btn as u8,
+                clicks: *clicks,
+                x: *x,
+                y: *y,
+            },
+            Event::MouseWheel {
+                x, y, direction, ..
+            } => RecordedEvent::MouseWheel {
+                x: *x,
+                y: *y,
+                direction: direction.to_ll(),
+            },
+            Event::ControllerAxisMotion {
+                which, axis, value, ..
+            } => RecordedEvent::ControllerAxisMotion {
+                which: *which,
+                axis: axis.string(),
+                value: *value,
+            },
+            Event::ControllerButtonDown { which, button, .. } => {
+                RecordedEvent::ControllerButtonDown {
+                    which: *which,
+                    button: button.string(),
+                }
+            }
+            Event::ControllerButtonUp { which, button, .. } => RecordedEvent::ControllerButtonUp {
+                which: *which,
+                button: button.string(),
+            },
+            Event::ControllerDeviceAdded { which, .. } => {
+                RecordedEvent::ControllerDeviceAdded(*which)
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                RecordedEvent::ControllerDeviceRemoved(*which)
+            }
+            _ => return None,
+        };
+
+        Some(recorded)
+    }
+
+    /// 다시 SDL 이벤트로 만든다. timestamp 와 window_id 는 0 이다.
+    pub fn to_event(&self) -> Option<Event> {
+        let event = match self {
+            RecordedEvent::Quit => Event::Quit { timestamp: 0 },
+            RecordedEvent::WindowResized(w, h) => Event::Window {
+                timestamp: 0,
+                window_id: 0,
+                win_event: WindowEvent::Resized(*w, *h),
+            },
+            RecordedEvent::KeyDown {
+                keycode,
+                scancode,
+                keymod,
+                repeat,
+            } => Event::KeyDown {
+                timestamp: 0,
+                window_id: 0,
+                keycode: keycode.and_then(Keycode::from_i32),
+                scancode: scancode.and_then(Scancode::from_i32),
+                keymod: Mod::from_bits_truncate(*keymod),
+                repeat: *repeat,
+            },
+            RecordedEvent::KeyUp {
+                keycode,
+                scancode,
+                keymod,
+                repeat,
+            } => Event::KeyUp {
+                timestamp: 0,
+                window_id: 0,
+                keycode: keycode.and_then(Keycode::from_i32),
+                scancode: scancode.and_then(Scancode::from_i32),
+                keymod: Mod::from_bits_truncate(*keymod),
+                repeat: *repeat,
+            },
+            RecordedEvent::TextEditing {
+                text,
+                start,
+                length,
+            } => Event::TextEditing {
+                timestamp: 0,
+                window_id: 0,
+                text: text.clone(),
+                start: *start,
+                length: *length,
+            },
+            RecordedEvent::TextInput(text) => Event::TextInput {
+                timestamp: 0,
+                window_id: 0,
+                text: text.clone(),
+            },
+            RecordedEvent::MouseMotion {
+                state,
+                x,
+                y,
+                xrel,
+                yrel,
+            } => Event::MouseMotion {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mousestate: MouseState::from_sdl_state(*state),
+                x: *x,
+                y: *y,
+                xrel: *xrel,
+                yrel: *yrel,
+            },
+            RecordedEvent::MouseButtonDown {
+                button,
+                clicks,
+                x,
+                y,
+            } => Event::MouseButtonDown {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mouse_btn: MouseButton::from_ll(*button),
+                clicks: *clicks,
+                x: *x,
+                y: *y,
+            },
+            RecordedEvent::MouseButtonUp {
+                button,
+                clicks,
+                x,
+                y,
+            } => Event::MouseButtonUp {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mouse_btn: MouseButton::from_ll(*button),
+                clicks: *clicks,
+                x: *x,
+                y: *y,
+            },
+            RecordedEvent::MouseWheel { x, y, direction } => Event::MouseWheel {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                x: *x,
+                y: *y,
+                direction: MouseWheelDirection::from_ll(*direction),
+            },
+            RecordedEvent::ControllerAxisMotion { which, axis, value } => {
+                Event::ControllerAxisMotion {
+                    timestamp: 0,
+                    which: *which,
+                    axis: Axis::from_string(axis)?,
+                    value: *value,
+                }
+            }
+            RecordedEvent::ControllerButtonDown { which, button } => Event::ControllerButtonDown {
+                timestamp: 0,
+                which: *which,
+                button: Button::from_string(button)?,
+            },
+            RecordedEvent::ControllerButtonUp { which, button } => Event::ControllerButtonUp {
+                timestamp: 0,
+                which: *which,
+                button: Button::from_string(button)?,
+            },
+            RecordedEvent::ControllerDeviceAdded(which) => Event::ControllerDeviceAdded {
+                timestamp: 0,
+                which: *which,
+            },
+            RecordedEvent::ControllerDeviceRemoved(which) => Event::ControllerDeviceRemoved {
+                timestamp: 0,
+                which: *which,
+            },
+        };
+
+        Some(event)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub dt: f64,
+    pub events: Vec<RecordedEvent>,
+    pub mouse: (i32, i32),
+}
+
+impl RecordedFrame {
+    pub fn from_frame(frame: &FrameInput) -> RecordedFrame {
+        RecordedFrame {
+            dt: frame.dt,
+            events: frame
+                .events
+                .iter()
+                .filter_map(RecordedEvent::from_event)
+                .collect(),
+            mouse: frame.mouse,
+        }
+    }
+
+    pub fn to_frame(&self) -> FrameInput {
+        FrameInput {
+            dt: self.dt,
+            events: self
+                .events
+                .iter()
+                .filter_map(RecordedEvent::to_event)
+                .collect(),
+            mouse: self.mouse,
+        }
+    }
+}
+
+/// 녹화 파일
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    /// 게임에서 쓰는 난수의 seed
+    pub seed: u64,
+    /// 녹화할 때의 창 크기
+    pub window: (u32, u32),
+    /// 녹화할 때의 설정 (키 설정이 달라도 같은 입력이 되도록)
+    pub settings: Settings,
+    pub frames: Vec<RecordedFrame>,
+}
+
+/// 녹화 파일의 첫 줄
+#[derive(Serialize, Deserialize)]
+struct RecordingHeader {
+    seed: u64,
+    window: (u32, u32),
+    settings: Settings,
+}
+
+impl Recording {
+    pub fn new(seed: u64, window: (u32, u32), settings: Settings) -> Recording {
+        Recording {
+            seed,
+            window,
+            settings,
+            frames: vec![],
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Recording, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Recording::from_text(&text)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_text()?).map_err(|e| e.to_string())
+    }
+
+    /// 녹화 파일의 내용을 읽는다.
+    /// 쓰는 도중에 죽어서 마지막 줄이 잘렸다면 그 줄은 버린다.
+    pub fn from_text(text: &str) -> Result<Recording, String> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+
+        let header: RecordingHeader = match lines.next() {
+            Some(line) => ron::from_str(line).map_err(|e| e.to_string())?,
+            None => return Err("empty recording".to_owned()),
+        };
+        let mut recording = Recording::new(header.seed, header.window, header.settings);
+
+        let mut lines = lines.peekable();
+        while let Some(line) = lines.next() {
+            match ron::from_str(line) {
+                Ok(frame) => recording.frames.push(frame),
+                Err(_) if lines.peek().is_none() => break,
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        Ok(recording)
+    }
+
+    pub fn to_text(&self) -> Result<String, String> {
+        let mut text = header_line(self)?;
+        for frame in self.frames.iter() {
+            text.push_str(&frame_line(frame)?);
+        }
+
+        Ok(text)
+    }
+}
+
+fn header_line(recording: &Recording) -> Result<String, String> {
+    let header = RecordingHeader {
+        seed: recording.seed,
+        window: recording.window,
+        settings: recording.settings.clone(),
+    };
+    ron::ser::to_string(&header)
+        .map(|line| line + "\n")
+        .map_err(|e| e.to_string())
+}
+
+fn frame_line(frame: &RecordedFrame) -> Result<String, String> {
+    ron::ser::to_string(frame)
+        .map(|line| line + "\n")
+        .map_err(|e| e.to_string())
+}
+
+/// 다른 InputSource 를 감싸서 지나가는 입력을 녹화한다.
+/// 프레임마다 파일에 덧붙이고 바로 flush 한다.
+pub struct Recorder {
+    source: Box<dyn InputSource>,
+    writer: BufWriter<File>,
+    path: PathBuf,
+    frames: usize,
+}
+
+impl Recorder {
+    /// 파일을 만들고 recording 의 seed, 창 크기, 설정을 먼저 적는다.
+    pub fn new(
+        source: Box<dyn InputSource>,
+        recording: Recording,
+        path: PathBuf,
+    ) -> Result<Recorder, String> {
+        let file = File::create(&path).map_err(|e| e.to_string())?;
+        let mut recorder = Recorder {
+            source,
+            writer: BufWriter::new(file),
+            path,
+            frames: 0,
+        };
+        recorder.write(&recording.to_text()?)?;
+        recorder.frames = recording.frames.len();
+
+        Ok(recorder)
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        self.writer
+            .write_all(text.as_bytes())
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl InputSource for Recorder {
+    fn next_frame(&mut self, dt: f64) -> Option<FrameInput> {
+        let frame = self.source.next_frame(dt)?;
+
+        let line = frame_line(&RecordedFrame::from_frame(&frame));
+        match line.and_then(|line| self.write(&line)) {
+            Ok(()) => self.frames += 1,
+            Err(e) => println!("failed to record {} : {}", self.path.display(), e),
+        }
+
+        Some(frame)
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        self.source.finish()?;
+        println!("recorded {} frames to {}", self.frames, self.path.display());
+
+        Ok(())
+    }
+}
+
+/// 녹화한 입력을 프레임 순서대로 돌려준다.
+pub struct Replay {
+    frames: VecDeque<RecordedFrame>,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Replay {
+        Replay {
+            frames: recording.frames.into(),
+        }
+    }
+}
+
+impl InputSource for Replay {
+    fn next_frame(&mut self, _dt: f64) -> Option<FrameInput> {
+        self.frames.pop_front().map(|frame| frame.to_frame())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_and_replay() {
+        let frame = FrameInput {
+            dt: 0.016,
+            events: vec![
+                Event::KeyDown {
+                    timestamp: 12,
+                    window_id: 1,
+                    keycode: Some(Keycode::W),
+                    scancode: Some(Scancode::W),
+                    keymod: Mod::LSHIFTMOD,
+                    repeat: false,
+                },
+                Event::MouseWheel {
+                    timestamp: 13,
+                    window_id: 1,
+                    which: 0,
+                    x: 0,
+                    y: -1,
+                    direction: MouseWheelDirection::Normal,
+                },
+                Event::MouseButtonDown {
+                    timestamp: 14,
+                    window_id: 1,
+                    which: 0,
+                    mouse_btn: MouseButton::Right,
+                    clicks: 2,
+                    x: 10,
+                    y: 20,
+                },
+                // 남기지 않는 이벤트
+                Event::AppLowMemory { timestamp: 14 },
+            ],
+            mouse: (10, 20),
+        };
+
+        let mut recording = Recording::new(42, (800, 600), Settings::default());
+        recording.frames.push(RecordedFrame::from_frame(&frame));
+
+        let text = ron::ser::to_string(&recording).unwrap();
+        let loaded: Recording = ron::from_str(&text).unwrap();
+        assert_eq!(loaded, recording);
+
+        let mut replay = Replay::new(loaded);
+        let replayed = replay.next_frame(1.0).unwrap();
+        assert_eq!(replayed.dt, 0.016);
+        assert_eq!(replayed.mouse, (10, 20));
+        assert_eq!(replayed.events.len(), 3);
+        match replayed.events[0] {
+            Event::KeyDown {
+                keycode, keymod, ..
+            } => {
+                assert_eq!(keycode, Some(Keycode::W));
+                assert_eq!(keymod, Mod::LSHIFTMOD);
+            }
+            _ => panic!("expected KeyDown"),
+        }
+        match replayed.events[2] {
+            Event::MouseButtonDown {
+                mouse_btn, clicks, ..
+            } => {
+                assert_eq!(mouse_btn, MouseButton::Right);
+                assert_eq!(clicks, 2);
+            }
+            _ => panic!("expected MouseButtonDown"),
+        }
+        assert!(replay.next_frame(1.0).is_none());
+    }
+
+    /// 입력을 몇 프레임 돌려주는 source
+    struct Frames(usize);
+
+    impl InputSource for Frames {
+        fn next_frame(&mut self, dt: f64) -> Option<FrameInput> {
+            if self.0 == 0 {
+                return None;
+            }
+            self.0 -= 1;
+
+            Some(FrameInput {
+                dt,
+                events: vec![Event::Quit { timestamp: 0 }],
+                mouse: (self.0 as i32, 0),
+            })
+        }
+    }
+
+    #[test]
+    fn recorder_flushes_every_frame() {
+        let path = std::env::temp_dir().join(format!("record_{}.ron", std::process::id()));
+        let recording = Recording::new(7, (320, 240), Settings::default());
+
+        // finish 없이 버려져도 (게임이 죽어도) 지나간 프레임은 남는다.
+        {
+            let mut recorder = Recorder::new(Box::new(Frames(3)), recording, path.clone()).unwrap();
+            recorder.next_frame(0.5).unwrap();
+            recorder.next_frame(0.25).unwrap();
+        }
+
+        let mut text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let loaded = Recording::from_text(&text).unwrap();
+        assert_eq!(loaded.seed, 7);
+        assert_eq!(loaded.frames.len(), 2);
+        assert_eq!(loaded.frames[1].dt, 0.25);
+        assert_eq!(loaded.frames[1].events, vec![RecordedEvent::Quit]);
+
+        // 쓰다 만 마지막 줄은 버린다.
+        text.push_str("(dt: 0.1, eve");
+        assert_eq!(Recording::from_text(&text).unwrap().frames.len(), 2);
+    }
+}
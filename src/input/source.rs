@@ -0,0 +1,51 @@
+//! 게임 루프가 한 프레임마다 받는 입력
+//! 실제 SDL 이벤트 대신 녹화한 입력을 넣을 수 있도록 InputSource 로 감싼다.
+
+use sdl2::event::Event;
+use sdl2::EventPump;
+
+/// 한 프레임 동안 state 로 들어가는 입력
+#[derive(Clone, Debug)]
+pub struct FrameInput {
+    /// 이번 프레임의 시간 (초)
+    pub dt: f64,
+    pub events: Vec<Event>,
+    /// 창 좌표의 마우스 위치
+    /// 마우스 버튼은 events 로 Input 에서 처리한다.
+    pub mouse: (i32, i32),
+}
+
+pub trait InputSource {
+    /// 다음 프레임의 입력. 더 이상 입력이 없다면 None
+    /// dt 는 실제로 흐른 시간이며, 녹화한 입력은 녹화할 때의 dt 를 돌려준다.
+    fn next_frame(&mut self, dt: f64) -> Option<FrameInput>;
+
+    /// 게임 루프가 끝났을 때
+    fn finish(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// SDL 의 EventPump 에서 읽는 입력
+pub struct EventPumpSource {
+    event_pump: EventPump,
+}
+
+impl EventPumpSource {
+    pub fn new(event_pump: EventPump) -> EventPumpSource {
+        EventPumpSource { event_pump }
+    }
+}
+
+impl InputSource for EventPumpSource {
+    fn next_frame(&mut self, dt: f64) -> Option<FrameInput> {
+        let events = self.event_pump.poll_iter().collect();
+        let mouse_state = self.event_pump.mouse_state();
+
+        Some(FrameInput {
+            dt,
+            events,
+            mouse: (mouse_state.x(), mouse_state.y()),
+        })
+    }
+}
@@ -5,22 +5,32 @@ pub mod actions;
 pub mod ai;
 pub mod animation;
 pub mod ascii;
+pub mod camera;
 pub mod components;
 pub mod constant;
 pub mod entities;
+pub mod game_loop;
 pub mod gui;
 pub mod input;
 pub mod map;
 pub mod physics;
 pub mod quadtree;
+pub mod render_queue;
+pub mod settings;
 pub mod states;
 pub mod texture_manager;
 pub mod tile;
 pub mod timer;
 pub mod tween;
+pub mod viewport;
+pub use states::builder_state::*;
+pub use states::controls_state::*;
 pub use states::game_state::*;
 pub use states::init_state::*;
+pub use states::manager::*;
+pub use states::pause_state::*;
 pub use states::timer_state::*;
+pub use states::transition::*;
 
 use num_traits::cast::{FromPrimitive, ToPrimitive};
 use num_traits::int::PrimInt;
@@ -60,16 +70,44 @@ pub mod character {
     pub const ATTACK: &str = "attack";
 }
 
-#[derive(PartialEq, Copy, Clone)]
+/// 만들 state 의 종류와 이름
+/// 종류로 StateManager 에 등록된 생성 함수를 찾고, 이름은 생성 함수에 넘긴다.
+#[derive(PartialEq, Copy, Clone, Debug)]
 pub enum StateInfo {
     Init(&'static str),
     Game(&'static str),
+    Builder(&'static str),
+    Controls(&'static str),
+    Pause(&'static str),
 }
 
-#[derive(PartialEq, Copy, Clone)]
+impl StateInfo {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            StateInfo::Init(_) => "init",
+            StateInfo::Game(_) => "game",
+            StateInfo::Builder(_) => "builder",
+            StateInfo::Controls(_) => "controls",
+            StateInfo::Pause(_) => "pause",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StateInfo::Init(name)
+            | StateInfo::Game(name)
+            | StateInfo::Builder(name)
+            | StateInfo::Controls(name)
+            | StateInfo::Pause(name) => name,
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
 pub enum StateResult {
-    Push(StateInfo),
-    Trans(StateInfo),
+    Push(StateInfo),       // 위에 새 state 를 올린다.
+    Trans(StateInfo),      // 맨 위의 state 를 새 state 로 바꾼다.
+    ReplaceAll(StateInfo), // 모든 state 를 내리고 새 state 를 올린다.
     Pop,
     Default,
 }
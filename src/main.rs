@@ -1,15 +1,86 @@
+use rand::prelude::*;
 use sdl2::event::Event;
 use sdl2::image::InitFlag;
 use sdl2::pixels::Color;
 use sdl2::TimerSubsystem;
 use sdl_isometric::constant::*;
-use sdl_isometric::states::*;
+use sdl_isometric::game_loop::{FixedStep, FrameClock};
+use sdl_isometric::input::gamepad::Gamepads;
+use sdl_isometric::input::record::{Recorder, Recording, Replay};
+use sdl_isometric::input::source::{EventPumpSource, InputSource};
+use sdl_isometric::input::Input;
+use sdl_isometric::settings::*;
+use sdl_isometric::viewport::*;
 use sdl_isometric::*;
-use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// 실행 인자
+/// --record <file> : 입력을 파일로 녹화한다.
+/// --replay <file> : 녹화한 입력으로 화면 없이 게임을 돌린다.
+#[derive(Default)]
+struct Args {
+    record: Option<PathBuf>,
+    replay: Option<PathBuf>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut iter = std::env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--record" => {
+                args.record = Some(iter.next().ok_or("--record needs a file")?.into());
+            }
+            "--replay" => {
+                args.replay = Some(iter.next().ok_or("--replay needs a file")?.into());
+            }
+            _ => return Err(format!("unknown argument : {}", arg)),
+        }
+    }
+
+    Ok(args)
+}
 
 fn main() -> Result<(), String> {
+    let args = parse_args()?;
+    let replay = match &args.replay {
+        Some(path) => Some(Recording::load(path)?),
+        None => None,
+    };
+
+    // 재생할 때는 화면과 소리를 내보내지 않는다. SDL 을 초기화하기 전에 정해야 한다.
+    // 녹화할 때의 설정이 사용자 설정 파일을 덮어쓰지 않게 한다.
+    if replay.is_some() {
+        set_persistent(false);
+        sdl2::hint::set("SDL_VIDEODRIVER", "dummy");
+        sdl2::hint::set("SDL_AUDIODRIVER", "dummy");
+    }
     let sdl_context = sdl2::init().expect("ERROR on SDL CONTEXT");
 
+    // 사용자 설정을 읽는다. 처음 실행이라면 기본 설정 파일을 만든다.
+    // 재생할 때는 녹화할 때의 설정을 쓴다.
+    let settings = match &replay {
+        Some(recording) => recording.settings.clone(),
+        None => {
+            let settings = Settings::load();
+            if !config_path().exists() {
+                if let Err(e) = settings.save() {
+                    println!("failed to save {} : {}", config_path().display(), e);
+                }
+            }
+            settings
+        }
+    };
+    *G_SETTINGS.write().unwrap() = settings.clone();
+
+    // 게임에서 쓰는 난수는 이 seed 에서 나온다.
+    let seed = match &replay {
+        Some(recording) => recording.seed,
+        None => rand::random(),
+    };
+    let mut seeds = StdRng::seed_from_u64(seed);
+
     // Video
     let video_subsystem = sdl_context.video().expect("ERROR on Video_subsystem");
     let _image_context = sdl2::image::init(InitFlag::PNG | InitFlag::JPG)?;
@@ -17,6 +88,9 @@ fn main() -> Result<(), String> {
 
     // Timer
     let timer_subsystem: TimerSubsystem = sdl_context.timer().unwrap();
+    // Game Controller
+    let mut gamepads = Gamepads::new(sdl_context.game_controller()?);
+
     // Audio
     let _audio = sdl_context.audio().expect("ERROR on audio_subsystem");
 
@@ -33,42 +107,150 @@ fn main() -> Result<(), String> {
             | sdl2::mixer::InitFlag::OGG,
     )
     .expect("ERROR ON makeing mixer context");
+    settings.apply_audio();
 
-    let window = video_subsystem
-        .window("isometric rust-sdl2 demo", SCREEN_WIDTH, SCREEN_HEIGHT)
-        .position_centered()
-        .resizable()
-        .build()
-        .expect("ERROR on window");
+    let window_builder = match &replay {
+        // 재생할 때는 창을 보이지 않고, 녹화할 때의 크기를 쓴다.
+        Some(recording) => {
+            let mut builder = video_subsystem.window(
+                "isometric rust-sdl2 demo",
+                recording.window.0,
+                recording.window.1,
+            );
+            builder.hidden();
+            builder
+        }
+        None => {
+            let mut builder = video_subsystem.window(
+                "isometric rust-sdl2 demo",
+                settings.display.width,
+                settings.display.height,
+            );
+            builder.position_centered().resizable();
+            if settings.display.fullscreen {
+                builder.fullscreen_desktop();
+            }
+            builder
+        }
+    };
+    let window = window_builder.build().expect("ERROR on window");
 
     // Renderer 만들기
-    let mut canvas = window.into_canvas().build().expect("ERROR on canvas");
+    // 재생할 때는 dummy 창이므로 소프트웨어로 그리고 수직 동기화를 하지 않는다.
+    let mut canvas_builder = window.into_canvas();
+    if replay.is_some() {
+        canvas_builder = canvas_builder.software();
+    } else if settings.display.vsync {
+        canvas_builder = canvas_builder.present_vsync();
+    }
+    let mut canvas = canvas_builder.build().expect("ERROR on canvas");
     let texture_creator = canvas.texture_creator();
-    let mut event_pump = sdl_context.event_pump().expect("ERROR on event_pump");
+    let event_pump = sdl_context.event_pump().expect("ERROR on event_pump");
+
+    // 가상화면을 창 크기에 맞춘다.
+    let (window_width, window_height) = match &replay {
+        Some(recording) => recording.window,
+        None => canvas.output_size()?,
+    };
+
+    // 입력은 SDL 에서 읽거나, 녹화한 파일에서 읽는다.
+    let headless = replay.is_some();
+    let mut source: Box<dyn InputSource> = match replay {
+        Some(recording) => Box::new(Replay::new(recording)),
+        None => {
+            let source = Box::new(EventPumpSource::new(event_pump));
+            match args.record {
+                Some(path) => Box::new(Recorder::new(
+                    source,
+                    Recording::new(seed, (window_width, window_height), settings.clone()),
+                    path,
+                )?),
+                None => source,
+            }
+        }
+    };
+    let mut viewport = Viewport::new(
+        VIRTUAL_WIDTH,
+        VIRTUAL_HEIGHT,
+        window_width,
+        window_height,
+        settings.display.scale_mode,
+    );
 
     // State 생성하기
-    let mut states: Vec<Box<dyn States>> = vec![];
-    let mut init_state = InitState::new();
-    init_state.init(&texture_creator, &font_context);
-    states.push(Box::new(init_state));
+    // StateInfo 의 종류마다 state 를 만드는 함수를 등록한다.
+    let mut states = StateManager::new();
+    states.set_texture_creator(&texture_creator);
+    states.register("init", |_name| {
+        let mut init_state = InitState::new();
+        init_state.init(&texture_creator, &font_context);
+        Box::new(init_state)
+    });
+    states.register("game", |_name| {
+        let mut game_state = GameState::with_seed(seeds.gen());
+        game_state.init(&texture_creator, &font_context);
+        Box::new(game_state)
+    });
+    states.register("builder", |_name| {
+        let mut builder_state = BuilderState::new();
+        builder_state.init(&texture_creator, &font_context);
+        Box::new(builder_state)
+    });
+    states.register("controls", |_name| {
+        let mut controls_state = ControlsState::new();
+        controls_state.init(&texture_creator, &font_context);
+        Box::new(controls_state)
+    });
+    states.register("pause", |_name| {
+        let mut pause_state = PauseState::new();
+        pause_state.init(&texture_creator, &font_context);
+        Box::new(pause_state)
+    });
+
+    // 화면 전환. 전환하는 동안은 입력을 받지 않는다.
+    states.set_transition(
+        "game",
+        Transition::new(TransitionEffect::Fade(Color::RGB(0, 0, 0)), 0.6),
+    );
+    states.set_transition("builder", Transition::new(TransitionEffect::Iris, 0.8));
+    states.set_transition(
+        "controls",
+        Transition::new(TransitionEffect::Wipe(Direction::Left), 0.4),
+    );
+    states.set_transition("pause", Transition::new(TransitionEffect::Crossfade, 0.2));
+
+    states.push(StateInfo::Init("init"))?;
 
-    let mut prev_buttons = HashSet::new();
+    // 마우스 위치, 버튼, 휠, 드래그는 여기에서 모아 state 로 넘긴다.
+    let mut input = Input::default();
 
-    // delta time
-    let mut dt: f64;
-    let mut now: u32 = timer_subsystem.ticks();
-    let mut last_time: u32 = 0;
+    // 고정 간격으로 update 하고, 남은 시간의 비율로 그 사이를 그린다.
+    let mut fixed_step = FixedStep::new(DELTA_T, MAX_FRAME_TIME);
+    let mut clock = FrameClock::new(&timer_subsystem);
     'running: loop {
         // 모든 state가 비어있다면 게임 종료
         if states.is_empty() {
             break 'running;
         }
 
-        dt = (now - last_time) as f64 / 1000.; // 1000분의 1초로 dt를 계산한다.
-        last_time = now;
-        for event in event_pump.poll_iter() {
+        // 재생할 입력이 끝나면 종료
+        // 재생할 때는 녹화한 frame 의 시간을 쓴다.
+        let frame = match source.next_frame(clock.tick(&timer_subsystem)) {
+            Some(frame) => frame,
+            None => break 'running,
+        };
+        let dt = frame.dt;
+
+        for event in frame.events.iter() {
+            // 패드 연결, 해제. 이벤트는 state 에도 그대로 넘긴다.
+            if !headless {
+                gamepads.process_event(event);
+            }
+            input.process_event(event);
+
             match event {
                 Event::Quit { .. } => break 'running,
+                _ if viewport.process_event(event) => (),
                 _ => {
                     // 가장 상단의 sates에 대한 처리
                     // 이 초기화 루틴을 어덯게 빼야하지??
@@ -77,66 +259,46 @@ fn main() -> Result<(), String> {
                     // 어떤 데이터를 초기화하는데 이용해야할까?
                     //
 
-                    if let Some(state) = states.last_mut() {
-                        // state 생성도 여기에서 함
-                        // 각 state에서는 생성할 state를 돌려줄 수 있음
-                        // 전역 state 보관함에서 넣었다 뺐다 해야함
-                        state.process_event(&event, dt);
-                    }
+                    states.process_event(event, dt);
                 }
             }
         }
 
         // The rest of the game loop goes here...
 
-        // mouse 처리는 events를 가지고 함
-        let mouse_state = event_pump.mouse_state();
-
-        // Create a set of pressed Keys.
-        let buttons = mouse_state.pressed_mouse_buttons().collect();
-
-        // Get the difference between the new and old sets.
-        let new_buttons = &buttons - &prev_buttons;
-        let old_buttons = &prev_buttons - &buttons;
-
-        canvas.set_draw_color(Color::RGBA(0, 0, 0, 255));
-        canvas.clear();
-        if let Some(state) = states.last_mut() {
-            state.process_mouse(
-                mouse_state.x(),
-                mouse_state.y(),
-                &new_buttons,
-                &old_buttons,
-                dt,
-            );
-            state.update(dt);
-            state.render(&mut canvas);
+        if !headless {
+            canvas.set_draw_color(Color::RGBA(0, 0, 0, 255));
+            canvas.clear();
+            viewport.apply(&mut canvas);
         }
 
-        prev_buttons = buttons;
-        canvas.present();
+        // 마우스 좌표는 가상화면 좌표로도 바꾸어 둔다.
+        input.set_mouse_position(frame.mouse, viewport.to_virtual(frame.mouse));
+        states.process_mouse(&input, dt);
 
-        // State의 최종 결과에 대한 처리
+        // 모인 시간만큼 DELTA_T 간격으로 update 한다.
+        // State의 결과는 update 마다 반영해서 다음 update 는 바뀐 state 가 받는다.
+        for _ in 0..fixed_step.advance(dt) {
+            states.update(DELTA_T);
 
-        let state_result = states.last_mut().unwrap().next_result();
-        match state_result {
-            StateResult::Push(s) => {
-                if let StateInfo::Game(_name) = s {
-                    let mut game_state = GameState::new();
-                    game_state.init(&texture_creator, &font_context);
-                    states.push(Box::new(game_state));
-                }
+            if let Err(e) = states.handle_result() {
+                println!("{}", e);
             }
+        }
 
-            StateResult::Pop => {
-                states.pop().unwrap();
-            }
-            _ => (),
+        if !headless {
+            states.interpolate(fixed_step.alpha());
+            states.render(&mut canvas);
+        }
+
+        input.begin_new_frame();
+        if !headless {
+            canvas.present();
+
+            // 초당 frame 수 제한
+            clock.limit(&timer_subsystem, settings.display.fps_cap);
         }
-        // 1/60 초 스킵한다.
-        //::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
-        now = timer_subsystem.ticks();
     }
 
-    Ok(())
+    source.finish()
 }
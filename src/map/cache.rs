@@ -0,0 +1,142 @@
+//! 정적 레이어 캐시
+//! 애니메이션 타일이 없는 청크는 render target 텍스쳐에 한 번 그려두고(bake)
+//! 매 프레임 청크 텍스쳐 한 장만 복사한다.
+//! 타일이 바뀐 청크는 Map::set_tile 에서 캐시를 지워 다음 프레임에 다시 굽는다.
+
+use std::collections::HashMap;
+
+use sdl2::rect::Rect;
+use sdl2::render::Texture;
+
+use crate::map::layer::{Chunk, TileLayer, CHUNK_SIZE};
+
+/// (레이어 index, 청크 좌표)
+pub type ChunkKey = (usize, (i32, i32));
+
+/// 한 레이어를 그리는 방법
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DrawCall {
+    /// 구워둔 청크 텍스쳐 한 장
+    Chunk((i32, i32)),
+    /// 타일 하나 (타일 좌표)
+    Tile(i32, i32),
+}
+
+/// 레이어의 타일 범위 (left, top, right, bottom) 를 그리는 순서를 정한다.
+/// is_static 인 청크는 통째로, 나머지 청크는 타일 단위로 그린다.
+pub fn plan_layer<F>(
+    layer: &TileLayer,
+    (left, top, right, bottom): (i32, i32, i32, i32),
+    is_static: F,
+) -> Vec<DrawCall>
+where
+    F: Fn(&Chunk) -> bool,
+{
+    let mut calls = vec![];
+
+    if left > right || top > bottom {
+        return calls;
+    }
+
+    let (chunk_left, chunk_top) = TileLayer::chunk_key(left, top);
+    let (chunk_right, chunk_bottom) = TileLayer::chunk_key(right, bottom);
+
+    for chunk_y in chunk_top..=chunk_bottom {
+        for chunk_x in chunk_left..=chunk_right {
+            let chunk = match layer.chunks.get(&(chunk_x, chunk_y)) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            if is_static(chunk) {
+                calls.push(DrawCall::Chunk((chunk_x, chunk_y)));
+                continue;
+            }
+
+            for y in top.max(chunk.y)..=bottom.min(chunk.y + CHUNK_SIZE - 1) {
+                for x in left.max(chunk.x)..=right.min(chunk.x + CHUNK_SIZE - 1) {
+                    if chunk.get(x - chunk.x, y - chunk.y).gid != 0 {
+                        calls.push(DrawCall::Tile(x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    calls
+}
+
+/// 구워둔 청크 텍스쳐
+/// 값이 None 이면 render target 을 쓸 수 없어 타일 단위로 그려야 하는 청크이다.
+#[derive(Default)]
+pub struct ChunkCache<'a> {
+    /// 청크 텍스쳐와 그 텍스쳐가 차지하는 월드 영역
+    pub chunks: HashMap<ChunkKey, Option<(Texture<'a>, Rect)>>,
+}
+
+impl<'a> ChunkCache<'a> {
+    pub fn new() -> ChunkCache<'a> {
+        ChunkCache {
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// 청크를 다시 굽도록 지운다.
+    pub fn invalidate(&mut self, key: ChunkKey) {
+        self.chunks.remove(&key);
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled_layer(width: i32, height: i32) -> TileLayer {
+        let mut layer = TileLayer::new("ground");
+        for y in 0..height {
+            for x in 0..width {
+                layer.set(
+                    x,
+                    y,
+                    tiled::LayerTile {
+                        gid: 1,
+                        flip_h: false,
+                        flip_v: false,
+                        flip_d: false,
+                    },
+                );
+            }
+        }
+        layer
+    }
+
+    #[test]
+    fn cached_chunks_reduce_draw_calls() {
+        let layer = filled_layer(100, 100);
+        // 800x600 화면에 32x32 타일이 보이는 범위
+        let range = (3, 5, 3 + 25, 5 + 19);
+
+        let per_tile = plan_layer(&layer, range, |_| false);
+        let cached = plan_layer(&layer, range, |_| true);
+
+        assert_eq!(per_tile.len(), 26 * 20);
+        assert_eq!(cached.len(), 2 * 2);
+        assert!(cached.len() * 100 < per_tile.len());
+    }
+
+    #[test]
+    fn animated_chunk_falls_back_to_tiles() {
+        let layer = filled_layer(32, 16);
+
+        // (1, 0) 청크에 애니메이션 타일이 있다고 가정한다.
+        let calls = plan_layer(&layer, (0, 0, 31, 15), |chunk| chunk.x == 0);
+
+        assert_eq!(calls[0], DrawCall::Chunk((0, 0)));
+        assert_eq!(calls.len(), 1 + 16 * 16);
+        assert_eq!(calls[1], DrawCall::Tile(16, 0));
+    }
+}
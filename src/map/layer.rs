@@ -0,0 +1,217 @@
+//! 청크 단위 타일 레이어
+//! 유한(finite) 지도와 무한(infinite) 지도의 타일 레이어를 같은 방식으로 보관한다.
+//! 타일은 CHUNK_SIZE x CHUNK_SIZE 크기의 청크로 나누어 두고,
+//! 비어있는 청크는 만들지 않으므로 넓은 지도에서도 필요한 부분만 메모리를 차지한다.
+
+use std::collections::HashMap;
+
+/// 청크 한 변의 타일 수
+pub const CHUNK_SIZE: i32 = 16;
+
+/// 빈 타일
+const EMPTY_TILE: tiled::LayerTile = tiled::LayerTile {
+    gid: 0,
+    flip_h: false,
+    flip_v: false,
+    flip_d: false,
+};
+
+/// CHUNK_SIZE x CHUNK_SIZE 타일 묶음
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chunk {
+    /// 청크 좌상단의 타일 좌표
+    pub x: i32,
+    pub y: i32,
+    /// 행 우선 배열
+    pub tiles: Vec<tiled::LayerTile>,
+}
+
+impl Chunk {
+    fn new(x: i32, y: i32) -> Chunk {
+        Chunk {
+            x,
+            y,
+            tiles: vec![EMPTY_TILE; (CHUNK_SIZE * CHUNK_SIZE) as usize],
+        }
+    }
+
+    /// 청크 안의 상대좌표로 타일을 구한다.
+    pub fn get(&self, local_x: i32, local_y: i32) -> &tiled::LayerTile {
+        &self.tiles[(local_y * CHUNK_SIZE + local_x) as usize]
+    }
+}
+
+/// 청크로 나눈 타일 레이어
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileLayer {
+    /// TMX 의 레이어 id. 새로 만든 레이어라면 0 이고 저장할 때 정한다.
+    pub id: u32,
+    /// 객체 레이어까지 포함한 TMX 안에서의 순서
+    pub layer_index: u32,
+    pub name: String,
+    pub visible: bool,
+    pub opacity: f32,
+    pub properties: tiled::Properties,
+    /// 청크 좌표 -> 청크
+    pub chunks: HashMap<(i32, i32), Chunk>,
+}
+
+impl TileLayer {
+    pub fn new(name: &str) -> TileLayer {
+        TileLayer {
+            id: 0,
+            layer_index: 0,
+            name: name.to_owned(),
+            visible: true,
+            opacity: 1.0,
+            properties: tiled::Properties::new(),
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Tiled 레이어를 청크로 나눈다.
+    /// 무한 지도의 청크는 크기와 위치가 CHUNK_SIZE 와 다를 수 있으므로 다시 나눈다.
+    pub fn from_tiled(layer: &tiled::Layer) -> TileLayer {
+        let mut tile_layer = TileLayer::new(&layer.name);
+        tile_layer.id = layer.id;
+        tile_layer.layer_index = layer.layer_index;
+        tile_layer.visible = layer.visible;
+        tile_layer.opacity = layer.opacity;
+        tile_layer.properties = layer.properties.clone();
+
+        match &layer.tiles {
+            tiled::LayerData::Finite(tiles) => {
+                tile_layer.put_rows(0, 0, tiles);
+            }
+            tiled::LayerData::Infinite(chunks) => {
+                for chunk in chunks.values() {
+                    tile_layer.put_rows(chunk.x, chunk.y, &chunk.tiles);
+                }
+            }
+        }
+
+        tile_layer
+    }
+
+    /// (left, top) 부터 행 단위의 타일들을 넣는다.
+    fn put_rows(&mut self, left: i32, top: i32, rows: &[Vec<tiled::LayerTile>]) {
+        for (y, row) in rows.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                if tile.gid != 0 {
+                    self.set(left + x as i32, top + y as i32, *tile);
+                }
+            }
+        }
+    }
+
+    /// 타일 좌표가 속한 청크 좌표
+    pub fn chunk_key(tile_x: i32, tile_y: i32) -> (i32, i32) {
+        (tile_x.div_euclid(CHUNK_SIZE), tile_y.div_euclid(CHUNK_SIZE))
+    }
+
+    /// 타일 좌표의 타일. 비어있으면 gid 가 0 인 타일을 돌려준다.
+    pub fn get(&self, tile_x: i32, tile_y: i32) -> &tiled::LayerTile {
+        match self.chunks.get(&TileLayer::chunk_key(tile_x, tile_y)) {
+            Some(chunk) => chunk.get(tile_x - chunk.x, tile_y - chunk.y),
+            None => &EMPTY_TILE,
+        }
+    }
+
+    /// 타일 좌표에 타일을 넣는다. 필요하면 청크를 만든다.
+    pub fn set(&mut self, tile_x: i32, tile_y: i32, tile: tiled::LayerTile) {
+        let (cx, cy) = TileLayer::chunk_key(tile_x, tile_y);
+        let chunk = self
+            .chunks
+            .entry((cx, cy))
+            .or_insert_with(|| Chunk::new(cx * CHUNK_SIZE, cy * CHUNK_SIZE));
+
+        let idx = ((tile_y - chunk.y) * CHUNK_SIZE + tile_x - chunk.x) as usize;
+        chunk.tiles[idx] = tile;
+    }
+
+    /// 타일이 들어있는 청크들을 감싸는 타일 범위 (left, top, right, bottom)
+    /// right, bottom 은 포함되는 값이다. 빈 레이어는 None 이다.
+    pub fn bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        self.chunks.values().fold(None, |bounds, chunk| {
+            let (left, top, right, bottom) = (
+                chunk.x,
+                chunk.y,
+                chunk.x + CHUNK_SIZE - 1,
+                chunk.y + CHUNK_SIZE - 1,
+            );
+
+            Some(match bounds {
+                Some((l, t, r, b)) => (l.min(left), t.min(top), r.max(right), b.max(bottom)),
+                None => (left, top, right, bottom),
+            })
+        })
+    }
+
+    /// 타일 범위 안에 있는 타일을 위에서 아래로, 왼쪽에서 오른쪽 순서로 방문한다.
+    /// 비어있는 청크는 건너뛴다.
+    pub fn for_each_in<F>(&self, left: i32, top: i32, right: i32, bottom: i32, mut f: F)
+    where
+        F: FnMut(i32, i32, &tiled::LayerTile),
+    {
+        let (chunk_left, _) = TileLayer::chunk_key(left, top);
+        let (chunk_right, _) = TileLayer::chunk_key(right, bottom);
+
+        for y in top..=bottom {
+            let chunk_y = y.div_euclid(CHUNK_SIZE);
+
+            for chunk_x in chunk_left..=chunk_right {
+                if let Some(chunk) = self.chunks.get(&(chunk_x, chunk_y)) {
+                    let from = left.max(chunk.x);
+                    let to = right.min(chunk.x + CHUNK_SIZE - 1);
+
+                    for x in from..=to {
+                        let tile = chunk.get(x - chunk.x, y - chunk.y);
+                        if tile.gid != 0 {
+                            f(x, y, tile);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(gid: u32) -> tiled::LayerTile {
+        tiled::LayerTile {
+            gid,
+            flip_h: false,
+            flip_v: false,
+            flip_d: false,
+        }
+    }
+
+    #[test]
+    fn negative_chunks() {
+        let mut layer = TileLayer::new("ground");
+        layer.set(-1, -1, tile(3));
+        layer.set(20, 5, tile(4));
+
+        assert_eq!(layer.get(-1, -1).gid, 3);
+        assert_eq!(layer.get(20, 5).gid, 4);
+        assert_eq!(layer.get(0, 0).gid, 0);
+        assert_eq!(layer.chunks.len(), 2);
+        assert_eq!(layer.bounds(), Some((-16, -16, 31, 15)));
+    }
+
+    #[test]
+    fn visit_only_filled_tiles_in_order() {
+        let mut layer = TileLayer::new("ground");
+        layer.set(17, 0, tile(2));
+        layer.set(0, 1, tile(1));
+        layer.set(100, 100, tile(9));
+
+        let mut visited = vec![];
+        layer.for_each_in(0, 0, 31, 31, |x, y, tile| visited.push((x, y, tile.gid)));
+
+        assert_eq!(visited, vec![(17, 0, 2), (0, 1, 1)]);
+    }
+}
@@ -0,0 +1,1023 @@
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::camera::Camera;
+use crate::constant::*;
+use crate::physics::shape::CollisionShape;
+use crate::render_queue::*;
+/// Tiled를 읽어서 맵을 채운다.
+use crate::tile;
+use crate::{detect_collision, Rectangle};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::render::BlendMode;
+use sdl2::video::WindowContext;
+use sdl2::{image::LoadTexture, render::Texture, render::TextureCreator};
+use std::path::Path;
+use tiled::parse_file;
+
+pub mod cache;
+pub mod layer;
+pub mod object;
+pub mod projection;
+pub mod tile_info;
+pub mod writer;
+
+pub use cache::*;
+pub use layer::*;
+pub use object::*;
+pub use projection::*;
+pub use tile_info::*;
+pub use writer::*;
+
+/// 맵의 가로 타일 수
+pub const MAP_WIDTH: i32 = 20;
+
+/// 맵의 세로 타일 수
+pub const MAP_HEIGHT: i32 = 15;
+
+/// 지도용 구조체
+/// 지도에는 map용 파일과
+/// 각 map 블럭에 대한 정보를 넣는다.
+pub struct Map<'a> {
+    map_id: String,
+    pub x: i32,     //  x
+    pub y: i32,     //  y
+    pub cam_x: i32, // camera_x
+    pub cam_y: i32, // camera_y
+    pub tile_atlases: HashMap<usize, tile::TileAtlas>,
+    pub width: u32,  // total number of tile in horizontal in this map
+    pub height: u32, // total numbr of tile in vertical in this map
+    /// 가장 왼쪽 위 타일의 좌표. 무한 지도는 음수일 수 있다.
+    pub tile_left: i32,
+    pub tile_top: i32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub projection: Projection,
+    pub layers: Vec<TileLayer>,
+    pub textures: HashMap<usize, Texture<'a>>,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    /// 정적 레이어의 청크 텍스쳐
+    chunk_cache: RefCell<ChunkCache<'a>>,
+    /// 막힌 타일들을 묶은 사각형 (orthogonal 지도)
+    pub blocks: Vec<Rect>,
+    /// 청크마다의 blocks 와 타일 충돌 도형
+    colliders: ChunkColliders,
+    /// gid 로 찾는 타일 그림 정보
+    pub sources: Vec<Option<tile::TileSource>>,
+    /// gid 별 타일 속성
+    pub tile_infos: HashMap<u32, TileInfo>,
+    /// gid 별 타일 애니메이션
+    pub animations: HashMap<u32, tile::TileAnimation>,
+    /// 타일 애니메이션용 시계 (초)
+    pub elapsed: f64,
+    /// 객체 레이어에서 읽은 spawn 정보
+    pub spawns: Vec<SpawnDescriptor>,
+    /// 객체 레이어에서 읽은 충돌 도형
+    object_shapes: Vec<CollisionShape>,
+    /// 객체 레이어와 막힌 타일의 모든 충돌 도형
+    pub shapes: Vec<CollisionShape>,
+    /// TMX 로 저장할 때 필요한 원본 정보
+    pub orientation: tiled::Orientation,
+    pub tilesets: Vec<tiled::Tileset>,
+    pub object_groups: Vec<tiled::ObjectGroup>,
+    pub properties: tiled::Properties,
+}
+
+impl<'a> Map<'a> {
+    pub fn new(
+        map_id: String,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        path: &'static str,
+    ) -> Map<'a> {
+        // read tmx file
+        let map: tiled::Map = parse_file(Path::new(&(ASSET_DIR.to_owned() + path))).unwrap();
+
+        let layers: Vec<TileLayer> = map.layers.iter().map(TileLayer::from_tiled).collect();
+
+        // 무한 지도는 타일이 들어있는 청크들을 모두 감싸는 범위가 지도의 크기가 된다.
+        let (tile_left, tile_top, tile_right, tile_bottom) = if map.infinite {
+            layers
+                .iter()
+                .filter_map(|layer| layer.bounds())
+                .reduce(|(l, t, r, b), (left, top, right, bottom)| {
+                    (l.min(left), t.min(top), r.max(right), b.max(bottom))
+                })
+                .unwrap_or((0, 0, map.width as i32 - 1, map.height as i32 - 1))
+        } else {
+            (0, 0, map.width as i32 - 1, map.height as i32 - 1)
+        };
+        let width = (tile_right - tile_left + 1) as u32;
+        let height = (tile_bottom - tile_top + 1) as u32;
+
+        // orientation 에 따라 타일 좌표와 픽셀 좌표 사이의 변환 방법이 달라진다.
+        // Hexagonal 은 지원하지 않으므로 Orthogonal 로 처리한다.
+        let projection = match map.orientation {
+            tiled::Orientation::Isometric => {
+                Projection::isometric(height, map.tile_width, map.tile_height)
+            }
+            tiled::Orientation::Staggered => Projection::staggered(map.tile_width, map.tile_height),
+            _ => Projection::orthogonal(map.tile_width, map.tile_height),
+        };
+        let tile_sets: Vec<tiled::Tileset> = map.tilesets;
+
+        let mut textures = HashMap::new();
+        let mut tile_atlases = HashMap::new();
+
+        // gid 로 바로 찾을 수 있도록 gid 순서대로 그림 정보를 넣는다.
+        // 0 은 빈 타일이다.
+        let mut sources: Vec<Option<tile::TileSource>> = vec![None];
+        let mut tile_infos = HashMap::new();
+        let mut animations = HashMap::new();
+
+        fn set_source(
+            sources: &mut Vec<Option<tile::TileSource>>,
+            gid: u32,
+            source: tile::TileSource,
+        ) {
+            if sources.len() <= gid as usize {
+                sources.resize(gid as usize + 1, None);
+            }
+            sources[gid as usize] = Some(source);
+        }
+
+        for tileset in tile_sets.iter() {
+            if let Some(image) = tileset.images.first() {
+                // 한 장의 이미지를 타일 크기로 나눈 타일셋
+                let texture = texture_creator
+                    .load_texture(Path::new(&(ASSET_DIR.to_owned() + &image.source)))
+                    .unwrap();
+
+                let tile_atlas = tile::TileAtlas::new_with_spacing(
+                    &texture,
+                    tileset.first_gid,
+                    tileset.tile_width,
+                    tileset.tile_height,
+                    tileset.margin,
+                    tileset.spacing,
+                );
+
+                let texture_idx = textures.len();
+                textures.insert(texture_idx, texture);
+
+                //tile atlas에 정의된 모든 tile 정보에 texture id를 넣는다.
+                for (j, _) in tile_atlas.atlas.iter().enumerate() {
+                    let gid = j as u32 + tileset.first_gid;
+                    set_source(
+                        &mut sources,
+                        gid,
+                        tile::TileSource {
+                            texture: texture_idx,
+                            src: tile_atlas.get_tile_rect(gid),
+                        },
+                    );
+                }
+
+                tile_atlases.insert(texture_idx, tile_atlas);
+            } else {
+                // 타일마다 이미지가 따로 있는 타일셋 (image collection)
+                for tile in tileset.tiles.iter() {
+                    if let Some(image) = tile.images.first() {
+                        let texture = texture_creator
+                            .load_texture(Path::new(&(ASSET_DIR.to_owned() + &image.source)))
+                            .unwrap();
+                        let query = texture.query();
+
+                        let texture_idx = textures.len();
+                        textures.insert(texture_idx, texture);
+
+                        set_source(
+                            &mut sources,
+                            tileset.first_gid + tile.id,
+                            tile::TileSource {
+                                texture: texture_idx,
+                                src: Rect::new(0, 0, query.width, query.height),
+                            },
+                        );
+                    }
+                }
+            }
+
+            // 타일셋에 속성이나 충돌 도형이 지정된 타일
+            for tile in tileset.tiles.iter() {
+                let gid = tileset.first_gid + tile.id;
+
+                if let Some(frames) = &tile.animation {
+                    if !frames.is_empty() {
+                        animations.insert(gid, tile::TileAnimation::new(tileset.first_gid, frames));
+                    }
+                }
+
+                let (tile_width, tile_height) = match sources.get(gid as usize) {
+                    Some(Some(source)) => (source.src.width(), source.src.height()),
+                    _ => (tileset.tile_width, tileset.tile_height),
+                };
+                tile_infos.insert(gid, TileInfo::from_tile(tile, tile_width, tile_height));
+            }
+        }
+
+        // 객체 레이어
+        let (spawns, object_shapes) = load_objects(&map.object_groups, &projection);
+
+        let mut new_map = Map {
+            map_id,
+            x: 0,
+            y: 0,
+            cam_x: 0,
+            cam_y: 0,
+            tile_atlases,
+            width,
+            height,
+            tile_left,
+            tile_top,
+            tile_width: map.tile_width,
+            tile_height: map.tile_height,
+            projection,
+            layers,
+            textures,
+            texture_creator,
+            chunk_cache: RefCell::new(ChunkCache::new()),
+            blocks: vec![],
+            colliders: ChunkColliders::default(),
+            sources,
+            tile_infos,
+            animations,
+            elapsed: 0.0,
+            spawns,
+            object_shapes,
+            shapes: vec![],
+            orientation: map.orientation,
+            tilesets: tile_sets,
+            object_groups: map.object_groups,
+            properties: map.properties,
+        };
+
+        new_map.rebuild_collision();
+        new_map
+    }
+
+    /// 타일 레이어로부터 blocks 와 타일 충돌 도형을 모두 다시 만든다.
+    /// 충돌 요소는 청크마다 따로 만들어 둔다.
+    pub fn rebuild_collision(&mut self) {
+        let (chunk_left, chunk_top) = TileLayer::chunk_key(self.tile_left, self.tile_top);
+        let (chunk_right, chunk_bottom) = TileLayer::chunk_key(
+            self.tile_left + self.width as i32 - 1,
+            self.tile_top + self.height as i32 - 1,
+        );
+
+        self.colliders.clear();
+        for chunk_y in chunk_top..=chunk_bottom {
+            for chunk_x in chunk_left..=chunk_right {
+                let colliders = self.chunk_colliders((chunk_x, chunk_y));
+                self.colliders.set((chunk_x, chunk_y), colliders);
+            }
+        }
+
+        self.collect_colliders();
+    }
+
+    /// 청크 하나의 충돌 요소를 다시 만든다.
+    fn rebuild_chunk_collision(&mut self, key: (i32, i32)) {
+        let colliders = self.chunk_colliders(key);
+        self.colliders.set(key, colliders);
+        self.collect_colliders();
+    }
+
+    /// 지도 범위 안에 있는 청크 부분의 충돌 요소
+    fn chunk_colliders(&self, (chunk_x, chunk_y): (i32, i32)) -> (Vec<Rect>, Vec<CollisionShape>) {
+        let range = (
+            (chunk_x * CHUNK_SIZE).max(self.tile_left),
+            (chunk_y * CHUNK_SIZE).max(self.tile_top),
+            (chunk_x * CHUNK_SIZE + CHUNK_SIZE - 1).min(self.tile_left + self.width as i32 - 1),
+            (chunk_y * CHUNK_SIZE + CHUNK_SIZE - 1).min(self.tile_top + self.height as i32 - 1),
+        );
+        let image_height = |gid: u32| match self.source(gid) {
+            Some(source) => source.src.height(),
+            None => self.tile_height,
+        };
+
+        tile_colliders(
+            &self.projection,
+            &self.layers,
+            &self.tile_infos,
+            image_height,
+            range,
+        )
+    }
+
+    /// 청크마다의 충돌 요소를 blocks 와 shapes 로 모은다.
+    fn collect_colliders(&mut self) {
+        let (blocks, tile_shapes) = self.colliders.collect();
+
+        self.blocks = blocks;
+        self.shapes = self.object_shapes.clone();
+        self.shapes.extend(tile_shapes);
+    }
+
+    /// type 이 object_type 인 spawn 정보들
+    pub fn spawns_of(&self, object_type: &str) -> Vec<&SpawnDescriptor> {
+        self.spawns
+            .iter()
+            .filter(|spawn| spawn.object_type == object_type)
+            .collect()
+    }
+
+    /// 사각 영역이 지도의 막힌 타일이나 충돌 도형과 겹치는가
+    pub fn collides(&self, rect: &Rectangle) -> bool {
+        self.blocks.iter().any(|block| {
+            detect_collision(
+                rect,
+                &Rectangle::new(
+                    block.x as f64,
+                    block.y as f64,
+                    block.w as f64,
+                    block.h as f64,
+                ),
+            )
+        }) || self.shapes.iter().any(|shape| shape.intersects_rect(rect))
+    }
+
+    /// 타일 애니메이션의 시계를 진행한다.
+    pub fn update(&mut self, dt: f64) {
+        self.elapsed += dt;
+    }
+
+    /// 지금 그려야 할 gid
+    /// 애니메이션이 있는 타일이라면 현재 프레임의 gid 를 돌려준다.
+    pub fn animated_gid(&self, gid: u32) -> u32 {
+        match self.animations.get(&gid) {
+            Some(animation) => animation.frame_at(self.elapsed),
+            None => gid,
+        }
+    }
+
+    /// gid 에 해당하는 타일 속성
+    pub fn tile_info(&self, gid: u32) -> Option<&TileInfo> {
+        self.tile_infos.get(&gid)
+    }
+
+    /// 타일 (tile_x, tile_y) 에 있는 모든 레이어의 타일과 그 속성을 합친 정보
+    /// 지도 밖이라면 빈 정보를 돌려준다.
+    pub fn tile_at(&self, tile_x: i32, tile_y: i32) -> TileQuery {
+        let mut query = TileQuery::default();
+
+        if !self.contains_tile(tile_x, tile_y) {
+            return query;
+        }
+
+        for layer in self.layers.iter() {
+            let gid = layer.get(tile_x, tile_y).gid;
+            if gid != 0 {
+                query.add(&layer.name, gid, self.tile_info(gid));
+                if layer.name == "collision" {
+                    query.solid = true;
+                }
+            }
+        }
+
+        query
+    }
+
+    /// 월드 좌표 (x, y) 에 있는 타일 정보
+    pub fn tile_at_point(&self, x: f64, y: f64) -> TileQuery {
+        let (tile_x, tile_y) = self
+            .projection
+            .world_to_tile(x - self.x as f64, y - self.y as f64);
+
+        self.tile_at(tile_x, tile_y)
+    }
+
+    /// 타일 좌표가 지도 범위 안에 있는가
+    pub fn contains_tile(&self, tile_x: i32, tile_y: i32) -> bool {
+        tile_x >= self.tile_left
+            && tile_y >= self.tile_top
+            && tile_x < self.tile_left + self.width as i32
+            && tile_y < self.tile_top + self.height as i32
+    }
+
+    /// 지도 전체의 픽셀 크기
+    pub fn pixel_size(&self) -> (u32, u32) {
+        self.projection.pixel_size(self.width, self.height)
+    }
+
+    /// 지도 전체를 감싸는 월드 좌표의 사각형
+    /// entity의 이동 범위와 카메라의 이동 범위로 쓴다.
+    pub fn world_bounds(&self) -> Rectangle {
+        let (tw, th) = self.projection.tile_size();
+        let right = self.tile_left + self.width as i32 - 1;
+        let bottom = self.tile_top + self.height as i32 - 1;
+
+        let corners = [
+            self.projection.tile_to_world(self.tile_left, self.tile_top),
+            self.projection.tile_to_world(right, self.tile_top),
+            self.projection.tile_to_world(self.tile_left, bottom),
+            self.projection.tile_to_world(right, bottom),
+        ];
+
+        let min_x = corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min);
+        let min_y = corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min);
+        let max_x = corners
+            .iter()
+            .map(|c| c.0)
+            .fold(f64::NEG_INFINITY, f64::max)
+            + tw as f64;
+        let max_y = corners
+            .iter()
+            .map(|c| c.1)
+            .fold(f64::NEG_INFINITY, f64::max)
+            + th as f64;
+
+        Rectangle::new(
+            min_x + self.x as f64,
+            min_y + self.y as f64,
+            max_x - min_x,
+            max_y - min_y,
+        )
+    }
+
+    /// translate world position (x, y) to tile
+    /// map is display from self.x, self.y
+    /// 지도 밖의 좌표는 가장 가까운 가장자리 타일로 제한된다.
+    pub fn point_to_tile(&self, x: i32, y: i32) -> (i32, i32) {
+        let (tile_x, tile_y) = self
+            .projection
+            .world_to_tile((x - self.x) as f64, (y - self.y) as f64);
+
+        (
+            tile_x.clamp(self.tile_left, self.tile_left + self.width as i32 - 1),
+            tile_y.clamp(self.tile_top, self.tile_top + self.height as i32 - 1),
+        )
+    }
+
+    /// 카메라 기준의 화면 좌표(마우스 위치 등)가 가리키는 타일
+    pub fn screen_to_tile(&self, screen_x: i32, screen_y: i32, camera: &Camera) -> (i32, i32) {
+        let (world_x, world_y) = camera.screen_to_world((screen_x, screen_y));
+
+        self.point_to_tile(world_x as i32, world_y as i32)
+    }
+
+    /// translate (tile_x, tile_y)
+    /// to coordinates
+    /// 반환값은 타일을 감싸는 사각형의 좌상단이다.
+    /// 지도 범위 밖의 타일은 가장 가까운 가장자리 타일로 한다.
+    pub fn get_tile_xy(&self, tile_x: i32, tile_y: i32) -> (f64, f64) {
+        let (tile_x, tile_y) = self.clamp_tile(tile_x, tile_y);
+
+        let (x, y) = self.projection.tile_to_world(tile_x, tile_y);
+        (x + self.x as f64, y + self.y as f64)
+    }
+
+    /// 타일의 중심점을 월드 좌표로 반환한다.
+    /// 지도 범위 밖의 타일은 가장 가까운 가장자리 타일로 한다.
+    pub fn get_tile_center(&self, tile_x: i32, tile_y: i32) -> (f64, f64) {
+        let (tile_x, tile_y) = self.clamp_tile(tile_x, tile_y);
+
+        let (x, y) = self.projection.tile_center(tile_x, tile_y);
+        (x + self.x as f64, y + self.y as f64)
+    }
+
+    /// 타일 좌표를 지도 범위 (tile_left, tile_top) ~ 마지막 타일 안으로 옮긴다.
+    pub fn clamp_tile(&self, tile_x: i32, tile_y: i32) -> (i32, i32) {
+        let right = self.tile_left + (self.width as i32 - 1).max(0);
+        let bottom = self.tile_top + (self.height as i32 - 1).max(0);
+
+        (
+            tile_x.clamp(self.tile_left, right),
+            tile_y.clamp(self.tile_top, bottom),
+        )
+    }
+
+    /// 타일이 차지할 월드 좌표의 영역을 구한다.
+    /// 타일셋의 타일이 지도의 타일보다 크다면 Tiled와 같이 타일의 아래쪽에 맞춘다.
+    fn tile_world_rect(&self, tile_x: i32, tile_y: i32, width: u32, height: u32) -> Rect {
+        let (world_x, world_y) = self.projection.tile_to_world(tile_x, tile_y);
+
+        Rect::new(
+            world_x as i32 + self.x,
+            world_y as i32 + self.y + self.tile_height as i32 - height as i32,
+            width,
+            height,
+        )
+    }
+
+    /// 타일 하나를 그리는 데 필요한 (texture index, 텍스쳐 영역, 월드 영역, 뒤집기)
+    /// 타일셋에 없는 gid 라면 None 이다.
+    fn tile_draw(
+        &self,
+        tile_x: i32,
+        tile_y: i32,
+        layer_tile: &tiled::LayerTile,
+    ) -> Option<(usize, Rect, Rect, tile::TileFlip)> {
+        let source = self.source(self.animated_gid(layer_tile.gid))?;
+        let flip = tile::TileFlip::from_layer_tile(layer_tile);
+
+        // 대각선으로 뒤집힌 타일은 가로 세로가 바뀐 영역을 차지한다.
+        let (width, height) = if flip.d {
+            (source.src.height(), source.src.width())
+        } else {
+            (source.src.width(), source.src.height())
+        };
+
+        Some((
+            source.texture,
+            source.src,
+            self.tile_world_rect(tile_x, tile_y, width, height),
+            flip,
+        ))
+    }
+
+    /// 카메라에 보이는 타일 범위 (left, top, right, bottom)
+    fn visible_tile_range(&self, camera_rect: &Rect) -> (i32, i32, i32, i32) {
+        let world_rect = Rect::new(
+            camera_rect.x - self.x,
+            camera_rect.y - self.y,
+            camera_rect.width(),
+            camera_rect.height(),
+        );
+        let (tile_left, tile_top, tile_right, tile_bottom) =
+            self.projection.tile_range(&world_rect);
+
+        (
+            tile_left.max(self.tile_left),
+            tile_top.max(self.tile_top),
+            tile_right.min(self.tile_left + self.width as i32 - 1),
+            tile_bottom.min(self.tile_top + self.height as i32 - 1),
+        )
+    }
+
+    /// entity와 함께 깊이 정렬할 레이어라면 그 z 값을 돌려준다.
+    /// 레이어의 custom property 로 depth_sort(bool) 와 z(int) 를 지정한다.
+    pub fn layer_depth_sort(layer: &TileLayer) -> Option<i32> {
+        match layer.properties.get("depth_sort") {
+            Some(tiled::PropertyValue::BoolValue(true)) => match layer.properties.get("z") {
+                Some(tiled::PropertyValue::IntValue(z)) => Some(*z),
+                _ => Some(0),
+            },
+            _ => None,
+        }
+    }
+
+    /// 카메라에 보이는 레이어의 모든 타일에 대해 f 를 호출한다.
+    /// f 는 (texture index, 텍스쳐 영역, 화면 영역, 뒤집기, 깊이) 를 받는다.
+    /// 깊이는 타일 바닥의 월드 y좌표이다.
+    fn each_visible_tile<F>(&self, layer: &TileLayer, camera: &Camera, mut f: F)
+    where
+        F: FnMut(usize, Rect, Rect, tile::TileFlip, f64),
+    {
+        let screen_rect = camera.screen_rect();
+        // 회전한 타일은 dest 보다 넓게 그려지므로 화면을 넓혀서 비교한다.
+        let margin = if camera.rotation == 0.0 {
+            0
+        } else {
+            camera.scale(self.tile_width.max(self.tile_height) as f64) as u32
+        };
+        let screen_rect = Rect::from_center(
+            screen_rect.center(),
+            screen_rect.width() + margin * 2,
+            screen_rect.height() + margin * 2,
+        );
+        let (tile_left, tile_top, tile_right, tile_bottom) =
+            self.visible_tile_range(&camera.view_rect());
+
+        // 위에서 아래로, 왼쪽에서 오른쪽으로 그리면
+        // orthogonal, isometric 모두 뒤쪽 타일이 먼저 그려진다.
+        // 카메라 범위에 걸친 청크만 방문한다.
+        layer.for_each_in(
+            tile_left,
+            tile_top,
+            tile_right,
+            tile_bottom,
+            |x, y, layer_tile| {
+                // 타일셋에 없는 gid 는 그리지 않는다.
+                let (texture, src, world, flip) = match self.tile_draw(x, y, layer_tile) {
+                    Some(draw) => draw,
+                    None => return,
+                };
+                let dest = camera.world_rect_to_screen(world);
+
+                // 화면 밖의 타일은 그리지 않는다.
+                if !dest.has_intersection(screen_rect) {
+                    return;
+                }
+
+                f(texture, src, dest, flip, world.bottom() as f64);
+            },
+        );
+    }
+
+    /// gid 에 해당하는 타일 그림 정보
+    pub fn source(&self, gid: u32) -> Option<&tile::TileSource> {
+        self.sources
+            .get(gid as usize)
+            .and_then(|source| source.as_ref())
+    }
+
+    /// 타일 하나를 그린다.
+    /// dest 는 타일이 화면에 차지할 영역이고, rotation 은 카메라의 회전 각도이다.
+    pub fn render_tile(
+        &self,
+        canvas: &mut WindowCanvas,
+        texture: usize,
+        src: Rect,
+        dest: Rect,
+        flip: tile::TileFlip,
+        rotation: f64,
+    ) {
+        let (angle, flip_horizontal, flip_vertical) = flip.to_copy_ex();
+
+        canvas
+            .copy_ex(
+                &self.textures[&texture],
+                Some(src),
+                Some(flip.draw_rect(dest)),
+                angle + rotation,
+                None,
+                flip_horizontal,
+                flip_vertical,
+            )
+            .unwrap();
+    }
+
+    /// 애니메이션 타일이 없어 구워둘 수 있는 청크인가
+    fn is_static_chunk(&self, chunk: &Chunk) -> bool {
+        chunk
+            .tiles
+            .iter()
+            .all(|tile| !self.animations.contains_key(&tile.gid))
+    }
+
+    /// 청크의 모든 타일을 텍스쳐 한 장에 그린다.
+    /// render target 을 쓸 수 없다면 None 이다.
+    fn bake_chunk(
+        &self,
+        canvas: &mut WindowCanvas,
+        layer_idx: usize,
+        chunk_key: (i32, i32),
+    ) -> Option<(Texture<'a>, Rect)> {
+        let chunk = self.layers[layer_idx].chunks.get(&chunk_key)?;
+
+        let mut draws = vec![];
+        for local_y in 0..CHUNK_SIZE {
+            for local_x in 0..CHUNK_SIZE {
+                let layer_tile = chunk.get(local_x, local_y);
+                if layer_tile.gid != 0 {
+                    if let Some(draw) =
+                        self.tile_draw(chunk.x + local_x, chunk.y + local_y, layer_tile)
+                    {
+                        draws.push(draw);
+                    }
+                }
+            }
+        }
+
+        // 청크 텍스쳐가 차지할 월드 영역
+        let bounds = draws
+            .iter()
+            .map(|(_, _, world, _)| *world)
+            .reduce(|a, b| a.union(b))?;
+
+        if !canvas.render_target_supported() {
+            return None;
+        }
+
+        let mut texture = self
+            .texture_creator
+            .create_texture_target(PixelFormatEnum::RGBA8888, bounds.width(), bounds.height())
+            .ok()?;
+        texture.set_blend_mode(BlendMode::Blend);
+
+        let draw_color = canvas.draw_color();
+        canvas
+            .with_texture_canvas(&mut texture, |texture_canvas| {
+                texture_canvas.set_draw_color(Color::RGBA(0, 0, 0, 0));
+                texture_canvas.clear();
+
+                for (texture, src, world, flip) in draws.iter() {
+                    let dest = relative_rect(*world, bounds.x, bounds.y);
+                    self.render_tile(texture_canvas, *texture, *src, dest, *flip, 0.0);
+                }
+            })
+            .ok()?;
+        canvas.set_draw_color(draw_color);
+
+        Some((texture, bounds))
+    }
+
+    /// 구워둔 청크를 그린다. 청크를 구울 수 없다면 false 를 돌려준다.
+    fn render_chunk(
+        &self,
+        canvas: &mut WindowCanvas,
+        layer_idx: usize,
+        chunk_key: (i32, i32),
+        camera: &Camera,
+    ) -> bool {
+        let mut cache = self.chunk_cache.borrow_mut();
+        let baked = cache
+            .chunks
+            .entry((layer_idx, chunk_key))
+            .or_insert_with(|| self.bake_chunk(canvas, layer_idx, chunk_key));
+
+        match baked {
+            Some((texture, bounds)) => {
+                let dest = camera.world_rect_to_screen(*bounds);
+                canvas
+                    .copy_ex(
+                        texture,
+                        None,
+                        Some(dest),
+                        camera.rotation,
+                        None,
+                        false,
+                        false,
+                    )
+                    .unwrap();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 깊이 정렬하지 않는 바닥 레이어들을 그린다.
+    /// 애니메이션 타일이 없는 청크는 구워둔 텍스쳐로 그리고
+    /// 나머지 청크는 타일 단위로 그린다.
+    pub fn render(&self, canvas: &mut WindowCanvas, camera: &Camera) {
+        let range = self.visible_tile_range(&camera.view_rect());
+        let (tile_left, tile_top, tile_right, tile_bottom) = range;
+
+        for (layer_idx, layer) in self.layers.iter().enumerate() {
+            if layer.name == "collision" || Map::layer_depth_sort(layer).is_some() {
+                continue;
+            }
+
+            let render_tile_at = |canvas: &mut WindowCanvas, x: i32, y: i32| {
+                if let Some((texture, src, world, flip)) = self.tile_draw(x, y, layer.get(x, y)) {
+                    let dest = camera.world_rect_to_screen(world);
+                    self.render_tile(canvas, texture, src, dest, flip, camera.rotation);
+                }
+            };
+
+            for call in plan_layer(layer, range, |chunk| self.is_static_chunk(chunk)) {
+                match call {
+                    DrawCall::Chunk(chunk_key) => {
+                        if !self.render_chunk(canvas, layer_idx, chunk_key, camera) {
+                            // 구울 수 없는 청크는 보이는 타일만 그린다.
+                            let left = tile_left.max(chunk_key.0 * CHUNK_SIZE);
+                            let top = tile_top.max(chunk_key.1 * CHUNK_SIZE);
+                            let right = tile_right.min(chunk_key.0 * CHUNK_SIZE + CHUNK_SIZE - 1);
+                            let bottom = tile_bottom.min(chunk_key.1 * CHUNK_SIZE + CHUNK_SIZE - 1);
+
+                            layer.for_each_in(left, top, right, bottom, |x, y, _| {
+                                render_tile_at(canvas, x, y);
+                            });
+                        }
+                    }
+                    DrawCall::Tile(x, y) => render_tile_at(canvas, x, y),
+                }
+            }
+        }
+    }
+
+    /// 레이어의 타일. 비어있다면 gid 가 0 인 타일이다.
+    pub fn get_tile(&self, layer_idx: usize, tile_x: i32, tile_y: i32) -> &tiled::LayerTile {
+        self.layers[layer_idx].get(tile_x, tile_y)
+    }
+
+    /// 이름으로 레이어 index 를 찾는다.
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+
+    /// 빈 타일 레이어를 맨 위에 추가하고 그 index 를 돌려준다.
+    /// 저장할 때도 객체 레이어까지 포함한 모든 레이어의 위에 쓴다.
+    pub fn add_layer(&mut self, name: &str) -> usize {
+        let mut layer = TileLayer::new(name);
+        layer.layer_index = self
+            .layers
+            .iter()
+            .map(|layer| layer.layer_index)
+            .chain(
+                self.object_groups
+                    .iter()
+                    .filter_map(|group| group.layer_index),
+            )
+            .max()
+            .map_or(0, |index| index + 1);
+
+        self.layers.push(layer);
+        self.layers.len() - 1
+    }
+
+    /// 타일을 바꾼다. gid 가 0 이면 지운다.
+    /// 해당 청크의 캐시를 지우므로 다음 render 에서 다시 굽는다.
+    /// 충돌 요소는 해당 청크만 다시 만든다.
+    /// 지도 범위 밖이라면 지도를 넓히고, 모든 청크의 충돌 요소를 다시 만든다.
+    pub fn set_tile(&mut self, layer_idx: usize, tile_x: i32, tile_y: i32, tile: tiled::LayerTile) {
+        if self.layers[layer_idx].get(tile_x, tile_y) == &tile {
+            return;
+        }
+
+        self.layers[layer_idx].set(tile_x, tile_y, tile);
+        self.chunk_cache
+            .borrow_mut()
+            .invalidate((layer_idx, TileLayer::chunk_key(tile_x, tile_y)));
+
+        if tile.gid != 0 && !self.contains_tile(tile_x, tile_y) {
+            let right = (self.tile_left + self.width as i32 - 1).max(tile_x);
+            let bottom = (self.tile_top + self.height as i32 - 1).max(tile_y);
+            self.tile_left = self.tile_left.min(tile_x);
+            self.tile_top = self.tile_top.min(tile_y);
+            self.width = (right - self.tile_left + 1) as u32;
+            self.height = (bottom - self.tile_top + 1) as u32;
+
+            self.rebuild_collision();
+        } else {
+            self.rebuild_chunk_collision(TileLayer::chunk_key(tile_x, tile_y));
+        }
+    }
+
+    /// TMX 로 쓸 정보
+    pub fn to_tmx(&self) -> TmxDocument<'_> {
+        TmxDocument {
+            orientation: self.orientation,
+            tile_width: self.tile_width,
+            tile_height: self.tile_height,
+            tile_left: self.tile_left,
+            tile_top: self.tile_top,
+            width: self.width,
+            height: self.height,
+            tilesets: &self.tilesets,
+            layers: &self.layers,
+            object_groups: &self.object_groups,
+            properties: &self.properties,
+        }
+    }
+
+    /// ASSET_DIR 아래의 path 에 TMX 로 저장한다.
+    /// 타일셋 이미지 경로는 ASSET_DIR 기준이므로 같은 디렉토리에 저장해야 다시 읽을 수 있다.
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(ASSET_DIR.to_owned() + path)?);
+        self.to_tmx().write(&mut file)
+    }
+
+    /// spawn 위치를 추가한다.
+    /// 저장할 때 함께 쓰이도록 spawns 객체 레이어에도 객체를 넣는다.
+    pub fn add_spawn(&mut self, object_type: &str, x: f64, y: f64) {
+        let next_id = self
+            .object_groups
+            .iter()
+            .flat_map(|group| group.objects.iter().map(|object| object.id))
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        let group_idx = match self
+            .object_groups
+            .iter()
+            .position(|group| group.name == "spawns")
+        {
+            Some(idx) => idx,
+            None => {
+                let group_id = self
+                    .object_groups
+                    .iter()
+                    .map(|group| group.id)
+                    .chain(self.layers.iter().map(|layer| layer.id))
+                    .max();
+                self.object_groups.push(tiled::ObjectGroup {
+                    name: "spawns".to_owned(),
+                    opacity: 1.0,
+                    visible: true,
+                    objects: vec![],
+                    colour: None,
+                    layer_index: None,
+                    properties: tiled::Properties::new(),
+                    id: group_id.unwrap_or(0) + 1,
+                });
+                self.object_groups.len() - 1
+            }
+        };
+
+        let (object_x, object_y) = self
+            .projection
+            .world_to_object(x - self.x as f64, y - self.y as f64);
+        self.object_groups[group_idx].objects.push(tiled::Object {
+            id: next_id,
+            gid: 0,
+            name: String::new(),
+            obj_type: object_type.to_owned(),
+            width: 0.0,
+            height: 0.0,
+            x: object_x as f32,
+            y: object_y as f32,
+            rotation: 0.0,
+            visible: true,
+            shape: tiled::ObjectShape::Point(object_x as f32, object_y as f32),
+            properties: tiled::Properties::new(),
+        });
+
+        self.spawns.push(SpawnDescriptor {
+            object_type: object_type.to_owned(),
+            name: String::new(),
+            layer: "spawns".to_owned(),
+            x,
+            y,
+            w: 0.0,
+            h: 0.0,
+            properties: tiled::Properties::new(),
+        });
+    }
+
+    /// (x, y) 에서 radius 안에 있는 spawns 객체 레이어의 spawn 을 지운다.
+    /// 지운 것이 있다면 true 를 돌려준다.
+    pub fn remove_spawn_at(&mut self, x: f64, y: f64, radius: f64) -> bool {
+        let near = |spawn: &SpawnDescriptor| {
+            let (cx, cy) = spawn.center();
+            (cx - x).powi(2) + (cy - y).powi(2) <= radius * radius
+        };
+
+        let idx = match self
+            .spawns
+            .iter()
+            .position(|spawn| spawn.layer == "spawns" && near(spawn))
+        {
+            Some(idx) => idx,
+            None => return false,
+        };
+        let spawn = self.spawns.remove(idx);
+
+        // 같은 위치의 객체를 객체 레이어에서도 지운다.
+        let (object_x, object_y) = self
+            .projection
+            .world_to_object(spawn.x - self.x as f64, spawn.y - self.y as f64);
+        if let Some(group) = self
+            .object_groups
+            .iter_mut()
+            .find(|group| group.name == "spawns")
+        {
+            if let Some(object_idx) = group.objects.iter().position(|object| {
+                object.obj_type == spawn.object_type
+                    && (object.x as f64 - object_x).abs() < 1.0
+                    && (object.y as f64 - object_y).abs() < 1.0
+            }) {
+                group.objects.remove(object_idx);
+            }
+        }
+
+        true
+    }
+
+    /// 구워둔 청크를 모두 지운다.
+    /// 타일셋 텍스쳐를 바꾸는 등 여러 청크가 한꺼번에 바뀌었을 때 쓴다.
+    pub fn invalidate_cache(&self) {
+        self.chunk_cache.borrow_mut().clear();
+    }
+
+    /// 레이어 하나를 캐시 없이 타일 단위로 그린다.
+    /// 편집기에서 collision 레이어처럼 평소에는 그리지 않는 레이어를 볼 때 쓴다.
+    pub fn render_layer(&self, canvas: &mut WindowCanvas, layer_idx: usize, camera: &Camera) {
+        self.each_visible_tile(
+            &self.layers[layer_idx],
+            camera,
+            |texture, src, dest, flip, _| {
+                self.render_tile(canvas, texture, src, dest, flip, camera.rotation);
+            },
+        );
+    }
+
+    /// 깊이 정렬할 레이어의 타일들을 render queue 에 넣는다.
+    /// 나무나 벽처럼 entity가 뒤로 지나갈 수 있는 레이어가 해당된다.
+    pub fn queue_tiles(&self, queue: &mut RenderQueue, camera: &Camera) {
+        for layer in self.layers.iter() {
+            if layer.name == "collision" {
+                continue;
+            }
+
+            if let Some(z) = Map::layer_depth_sort(layer) {
+                self.each_visible_tile(layer, camera, |texture, src, dest, flip, depth| {
+                    queue.push(
+                        z,
+                        depth,
+                        RenderCommand::Tile {
+                            texture,
+                            src,
+                            dest,
+                            flip,
+                        },
+                    );
+                });
+            }
+        }
+    }
+}
+
+/// (origin_x, origin_y) 를 원점으로 하는 좌표계로 옮긴 사각형
+fn relative_rect(rect: Rect, origin_x: i32, origin_y: i32) -> Rect {
+    Rect::new(
+        rect.x() - origin_x,
+        rect.y() - origin_y,
+        rect.width(),
+        rect.height(),
+    )
+}
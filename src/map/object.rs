@@ -0,0 +1,329 @@
+//! Tiled 객체 레이어
+//! 객체 레이어(objectgroup)의 객체들을 spawn 정보와 충돌 도형으로 나눈다.
+//!
+//! - 타원, 다각형 객체는 충돌 도형이 된다.
+//! - type 이 block/collision 이거나 collision 레이어에 있는 사각형도 충돌 도형이 된다.
+//! - 나머지(점, 사각형, 타일 객체)는 spawn 정보가 된다.
+
+use crate::entities::EntityType;
+use crate::map::Projection;
+use crate::physics::collision::rotate_deg;
+use crate::physics::shape::CollisionShape;
+
+/// 객체 레이어에서 읽어들인 spawn 정보
+/// state 에서 이 정보를 이용해 Entity를 만든다.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpawnDescriptor {
+    /// Tiled 객체의 type (player, mob, trigger 등)
+    pub object_type: String,
+    pub name: String,
+    /// 객체가 속한 레이어의 이름
+    pub layer: String,
+    /// 월드 좌표
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+    pub properties: tiled::Properties,
+}
+
+impl SpawnDescriptor {
+    /// type 에 해당하는 EntityType
+    pub fn entity_type(&self) -> Option<EntityType> {
+        match self.object_type.to_lowercase().as_str() {
+            "player" => Some(EntityType::PLAYER),
+            "mob" | "enemy" => Some(EntityType::MOB),
+            "attack" => Some(EntityType::ATTACK),
+            "block" => Some(EntityType::BLOCK),
+            _ => None,
+        }
+    }
+
+    /// 영역의 중심 좌표
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.w / 2.0, self.y + self.h / 2.0)
+    }
+
+    /// 숫자형 custom property 를 f64 로 가져온다.
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        match self.properties.get(key) {
+            Some(tiled::PropertyValue::FloatValue(v)) => Some(*v as f64),
+            Some(tiled::PropertyValue::IntValue(v)) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    /// bool 형 custom property
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.properties.get(key) {
+            Some(tiled::PropertyValue::BoolValue(v)) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// 문자열 custom property
+    pub fn get_string(&self, key: &str) -> Option<&str> {
+        match self.properties.get(key) {
+            Some(tiled::PropertyValue::StringValue(v)) => Some(v.as_str()),
+            Some(tiled::PropertyValue::FileValue(v)) => Some(v.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// 객체 레이어들을 spawn 정보와 충돌 도형으로 나눈다.
+pub fn load_objects(
+    object_groups: &[tiled::ObjectGroup],
+    projection: &Projection,
+) -> (Vec<SpawnDescriptor>, Vec<CollisionShape>) {
+    let mut spawns = vec![];
+    let mut shapes = vec![];
+
+    for group in object_groups.iter() {
+        let is_collision_layer = group.name == "collision";
+
+        for object in group.objects.iter() {
+            let is_block =
+                is_collision_layer || object.obj_type == "block" || object.obj_type == "collision";
+
+            match &object.shape {
+                tiled::ObjectShape::Polygon { points } => {
+                    shapes.push(CollisionShape::Polygon(object_points(
+                        object, points, projection,
+                    )));
+                }
+                tiled::ObjectShape::Ellipse { width, height } => {
+                    let (rx, ry) = (*width as f64 / 2.0, *height as f64 / 2.0);
+                    shapes.push(object_shape(
+                        object,
+                        CollisionShape::Ellipse {
+                            cx: object.x as f64 + rx,
+                            cy: object.y as f64 + ry,
+                            rx,
+                            ry,
+                        },
+                        projection,
+                    ));
+                }
+                tiled::ObjectShape::Rect { width, height } if is_block => {
+                    shapes.push(object_shape(
+                        object,
+                        CollisionShape::Rect {
+                            x: object.x as f64,
+                            y: object.y as f64,
+                            w: *width as f64,
+                            h: *height as f64,
+                        },
+                        projection,
+                    ));
+                }
+                // 선은 면적이 없으므로 무시한다.
+                tiled::ObjectShape::Polyline { .. } => (),
+                _ => {
+                    let (width, height) = match object.shape {
+                        tiled::ObjectShape::Rect { width, height } => (width as f64, height as f64),
+                        _ => (0.0, 0.0),
+                    };
+
+                    // 타일 객체는 좌하단이 기준점이다.
+                    let object_y = if object.gid != 0 {
+                        object.y as f64 - height
+                    } else {
+                        object.y as f64
+                    };
+
+                    // 투영한 영역을 감싸는 사각형
+                    let corners = CollisionShape::Rect {
+                        x: object.x as f64,
+                        y: object_y,
+                        w: width,
+                        h: height,
+                    }
+                    .to_polygon();
+                    let corners = project_points(object, &corners, projection);
+                    let (x, y, w, h) = bounding_box(&corners);
+
+                    spawns.push(SpawnDescriptor {
+                        object_type: object.obj_type.clone(),
+                        name: object.name.clone(),
+                        layer: group.name.clone(),
+                        x,
+                        y,
+                        w,
+                        h,
+                        properties: object.properties.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    (spawns, shapes)
+}
+
+/// 다각형 객체의 꼭지점을 월드 좌표로 바꾼다.
+/// 꼭지점은 객체 위치에 대한 상대좌표이다.
+fn object_points(
+    object: &tiled::Object,
+    points: &[(f32, f32)],
+    projection: &Projection,
+) -> Vec<(f64, f64)> {
+    let points: Vec<(f64, f64)> = points
+        .iter()
+        .map(|(px, py)| (object.x as f64 + *px as f64, object.y as f64 + *py as f64))
+        .collect();
+
+    project_points(object, &points, projection)
+}
+
+/// 객체 좌표의 사각형, 타원을 월드 좌표의 도형으로 바꾼다.
+/// 회전이 없고 투영이 축을 그대로 두면 도형을 그대로 쓰고,
+/// 아니라면 다각형으로 바꾸어 꼭지점마다 회전, 투영한다.
+fn object_shape(
+    object: &tiled::Object,
+    shape: CollisionShape,
+    projection: &Projection,
+) -> CollisionShape {
+    let is_isometric = matches!(projection, Projection::Isometric { .. });
+
+    if object.rotation == 0.0 && !is_isometric {
+        shape
+    } else {
+        CollisionShape::Polygon(project_points(object, &shape.to_polygon(), projection))
+    }
+}
+
+/// 객체 좌표의 점들을 객체 위치를 중심으로 rotation 만큼 회전한 후 월드 좌표로 바꾼다.
+fn project_points(
+    object: &tiled::Object,
+    points: &[(f64, f64)],
+    projection: &Projection,
+) -> Vec<(f64, f64)> {
+    let origin = (object.x as f64, object.y as f64);
+
+    points
+        .iter()
+        .map(|point| {
+            let (x, y) = rotate_deg(*point, origin, object.rotation as f64);
+            projection.object_to_world(x, y)
+        })
+        .collect()
+}
+
+/// 점들을 감싸는 (x, y, w, h)
+fn bounding_box(points: &[(f64, f64)]) -> (f64, f64, f64, f64) {
+    let min_x = points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let min_y = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let max_x = points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+    let max_y = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+
+    (min_x, min_y, max_x - min_x, max_y - min_y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn load_object_group() {
+        let map = tiled::parse_file(Path::new("assets/tiled_csv.tmx")).unwrap();
+        let projection = Projection::orthogonal(map.tile_width, map.tile_height);
+
+        let (spawns, shapes) = load_objects(&map.object_groups, &projection);
+
+        // 사각형 1개는 spawn, 타원과 다각형은 충돌 도형, 선은 무시
+        assert_eq!(spawns.len(), 1);
+        assert_eq!(spawns[0].x, 14.0);
+        assert_eq!(spawns[0].w, 285.0);
+        assert_eq!(shapes.len(), 2);
+        assert_eq!(
+            shapes[0],
+            CollisionShape::Ellipse {
+                cx: 380.0,
+                cy: 271.5,
+                rx: 51.0,
+                ry: 54.5
+            }
+        );
+    }
+
+    fn object(shape: tiled::ObjectShape, obj_type: &str, rotation: f32) -> tiled::Object {
+        tiled::Object {
+            id: 1,
+            gid: 0,
+            name: String::new(),
+            obj_type: obj_type.to_owned(),
+            width: 0.0,
+            height: 0.0,
+            x: 32.0,
+            y: 0.0,
+            rotation,
+            visible: true,
+            shape,
+            properties: tiled::Properties::new(),
+        }
+    }
+
+    fn group(objects: Vec<tiled::Object>) -> tiled::ObjectGroup {
+        tiled::ObjectGroup {
+            name: "objects".to_owned(),
+            opacity: 1.0,
+            visible: true,
+            objects,
+            colour: None,
+            layer_index: None,
+            properties: tiled::Properties::new(),
+            id: 1,
+        }
+    }
+
+    #[test]
+    fn project_isometric_objects() {
+        // 64x32 타일, 세로 2칸 -> origin_x = 32
+        let projection = Projection::isometric(2, 64, 32);
+        let rect = tiled::ObjectShape::Rect {
+            width: 32.0,
+            height: 32.0,
+        };
+        let groups = vec![group(vec![
+            object(rect.clone(), "block", 0.0),
+            object(rect, "player", 0.0),
+        ])];
+
+        let (spawns, shapes) = load_objects(&groups, &projection);
+
+        // 타일 한 칸의 객체는 다이아몬드가 된다.
+        assert_eq!(
+            shapes[0],
+            CollisionShape::Polygon(vec![
+                (96.0, 16.0),
+                (128.0, 32.0),
+                (96.0, 48.0),
+                (64.0, 32.0)
+            ])
+        );
+        assert_eq!(
+            (spawns[0].x, spawns[0].y, spawns[0].w, spawns[0].h),
+            (64.0, 16.0, 64.0, 32.0)
+        );
+    }
+
+    #[test]
+    fn rotated_block() {
+        let projection = Projection::orthogonal(32, 32);
+        let rect = tiled::ObjectShape::Rect {
+            width: 10.0,
+            height: 20.0,
+        };
+        let groups = vec![group(vec![object(rect, "block", 90.0)])];
+
+        let (_, shapes) = load_objects(&groups, &projection);
+
+        let points = shapes[0].to_polygon();
+        let expected = [(32.0, 0.0), (32.0, 10.0), (12.0, 10.0), (12.0, 0.0)];
+        for (point, expected) in points.iter().zip(expected.iter()) {
+            assert!((point.0 - expected.0).abs() < 1e-9 && (point.1 - expected.1).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! 지도 투영
+//! 타일 좌표 <-> 월드(픽셀) 좌표 <-> 화면 좌표 사이의 변환을 담당한다.
+//!
+//! 월드 좌표는 지도를 한 장의 큰 그림으로 펼쳤을 때의 픽셀 좌표이며,
+//! Entity의 위치 역시 월드 좌표로 보관한다.
+//! 화면 좌표는 월드 좌표에서 카메라의 좌상단 좌표를 뺀 값이다.
+
+use sdl2::rect::Rect;
+
+/// 지도 투영 방식
+/// Tiled의 orientation 값에 대응한다.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// 화면에 정렬된 사각 타일
+    Orthogonal { tile_width: u32, tile_height: u32 },
+    /// 다이아몬드 모양의 타일
+    /// origin_x 는 (0, 0) 타일을 감싸는 사각형의 왼쪽 x좌표
+    Isometric {
+        tile_width: u32,
+        tile_height: u32,
+        origin_x: i32,
+    },
+    /// 홀수 행이 반 타일씩 오른쪽으로 밀린 다이아몬드 타일
+    /// (Tiled의 staggeraxis="y", staggerindex="odd" 기준)
+    Staggered { tile_width: u32, tile_height: u32 },
+}
+
+impl Projection {
+    /// 직교 투영
+    pub fn orthogonal(tile_width: u32, tile_height: u32) -> Projection {
+        Projection::Orthogonal {
+            tile_width,
+            tile_height,
+        }
+    }
+
+    /// 아이소메트릭 투영
+    /// 지도의 세로 타일 수(height)를 이용해 가장 왼쪽 타일이 x = 0 에 오도록 한다.
+    pub fn isometric(height: u32, tile_width: u32, tile_height: u32) -> Projection {
+        Projection::Isometric {
+            tile_width,
+            tile_height,
+            origin_x: (height as i32 - 1) * tile_width as i32 / 2,
+        }
+    }
+
+    /// 스태거드 투영
+    pub fn staggered(tile_width: u32, tile_height: u32) -> Projection {
+        Projection::Staggered {
+            tile_width,
+            tile_height,
+        }
+    }
+
+    /// 타일 한 칸의 (가로, 세로) 픽셀 크기
+    pub fn tile_size(&self) -> (u32, u32) {
+        match *self {
+            Projection::Orthogonal {
+                tile_width,
+                tile_height,
+            }
+            | Projection::Isometric {
+                tile_width,
+                tile_height,
+                ..
+            }
+            | Projection::Staggered {
+                tile_width,
+                tile_height,
+            } => (tile_width, tile_height),
+        }
+    }
+
+    /// width x height 타일로 된 지도의 전체 픽셀 크기
+    pub fn pixel_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let (tw, th) = self.tile_size();
+        match self {
+            Projection::Orthogonal { .. } => (width * tw, height * th),
+            Projection::Isometric { .. } => ((width + height) * tw / 2, (width + height) * th / 2),
+            Projection::Staggered { .. } => (width * tw + tw / 2, (height + 1) * th / 2),
+        }
+    }
+
+    /// 타일 좌표를 월드 좌표로 변환한다.
+    /// 반환값은 해당 타일을 감싸는 사각형의 좌상단이다.
+    pub fn tile_to_world(&self, tile_x: i32, tile_y: i32) -> (f64, f64) {
+        let (tw, th) = self.tile_size();
+        let (tw, th) = (tw as f64, th as f64);
+
+        match *self {
+            Projection::Orthogonal { .. } => (tile_x as f64 * tw, tile_y as f64 * th),
+            Projection::Isometric { origin_x, .. } => (
+                origin_x as f64 + (tile_x - tile_y) as f64 * tw / 2.0,
+                (tile_x + tile_y) as f64 * th / 2.0,
+            ),
+            Projection::Staggered { .. } => {
+                let shift = if tile_y.rem_euclid(2) == 1 {
+                    tw / 2.0
+                } else {
+                    0.0
+                };
+                (tile_x as f64 * tw + shift, tile_y as f64 * th / 2.0)
+            }
+        }
+    }
+
+    /// 타일의 중심점을 월드 좌표로 반환한다.
+    pub fn tile_center(&self, tile_x: i32, tile_y: i32) -> (f64, f64) {
+        let (tw, th) = self.tile_size();
+        let (x, y) = self.tile_to_world(tile_x, tile_y);
+
+        (x + tw as f64 / 2.0, y + th as f64 / 2.0)
+    }
+
+    /// 월드 좌표가 포함된 타일 좌표를 구한다.
+    /// 지도 범위를 벗어난 값도 그대로 돌려주므로, 필요하면 호출하는 쪽에서 제한한다.
+    pub fn world_to_tile(&self, world_x: f64, world_y: f64) -> (i32, i32) {
+        let (tw, th) = self.tile_size();
+        let (tw, th) = (tw as f64, th as f64);
+
+        match *self {
+            Projection::Orthogonal { .. } => {
+                ((world_x / tw).floor() as i32, (world_y / th).floor() as i32)
+            }
+            Projection::Isometric { origin_x, .. } => {
+                // 다이아몬드의 윗 꼭지점을 기준으로 두 축으로 분해한다.
+                let a = (world_x - origin_x as f64 - tw / 2.0) / (tw / 2.0);
+                let b = world_y / (th / 2.0);
+
+                (
+                    ((a + b) / 2.0).floor() as i32,
+                    ((b - a) / 2.0).floor() as i32,
+                )
+            }
+            Projection::Staggered { .. } => {
+                // 행 하나의 높이는 반 타일이므로 인접한 두 행의 다이아몬드 중
+                // 좌표를 포함하는 쪽을 고른다.
+                let row = (world_y / (th / 2.0)).floor() as i32;
+                let mut fallback = None;
+
+                for tile_y in [row, row - 1].iter() {
+                    let shift = if tile_y.rem_euclid(2) == 1 {
+                        tw / 2.0
+                    } else {
+                        0.0
+                    };
+                    let tile_x = ((world_x - shift) / tw).floor() as i32;
+                    let (cx, cy) = self.tile_center(tile_x, *tile_y);
+
+                    if (world_x - cx).abs() / (tw / 2.0) + (world_y - cy).abs() / (th / 2.0) <= 1.0
+                    {
+                        return (tile_x, *tile_y);
+                    }
+
+                    if fallback.is_none() {
+                        fallback = Some((tile_x, *tile_y));
+                    }
+                }
+
+                fallback.unwrap()
+            }
+        }
+    }
+
+    /// Tiled 객체 레이어의 좌표를 월드 좌표로 바꾼다.
+    /// isometric 지도의 객체 좌표는 타일 축을 따라 tile_height 단위로 기록되므로 투영이 필요하다.
+    pub fn object_to_world(&self, object_x: f64, object_y: f64) -> (f64, f64) {
+        match *self {
+            Projection::Isometric {
+                tile_width,
+                tile_height,
+                origin_x,
+            } => {
+                let tile_x = object_x / tile_height as f64;
+                let tile_y = object_y / tile_height as f64;
+
+                (
+                    origin_x as f64 + (tile_x - tile_y + 1.0) * tile_width as f64 / 2.0,
+                    (tile_x + tile_y) * tile_height as f64 / 2.0,
+                )
+            }
+            _ => (object_x, object_y),
+        }
+    }
+
+    /// 월드 좌표를 Tiled 객체 레이어의 좌표로 바꾼다. object_to_world 의 역변환이다.
+    pub fn world_to_object(&self, world_x: f64, world_y: f64) -> (f64, f64) {
+        match *self {
+            Projection::Isometric {
+                tile_width,
+                tile_height,
+                origin_x,
+            } => {
+                let a = (world_x - origin_x as f64) / (tile_width as f64 / 2.0) - 1.0;
+                let b = world_y / (tile_height as f64 / 2.0);
+
+                (
+                    (a + b) / 2.0 * tile_height as f64,
+                    (b - a) / 2.0 * tile_height as f64,
+                )
+            }
+            _ => (world_x, world_y),
+        }
+    }
+
+    /// 월드 좌표를 카메라 기준의 화면 좌표로 바꾼다.
+    pub fn world_to_screen(&self, (world_x, world_y): (f64, f64), camera: &Rect) -> (i32, i32) {
+        (world_x as i32 - camera.x, world_y as i32 - camera.y)
+    }
+
+    /// 카메라 기준의 화면 좌표를 월드 좌표로 바꾼다.
+    pub fn screen_to_world(&self, (screen_x, screen_y): (i32, i32), camera: &Rect) -> (f64, f64) {
+        ((screen_x + camera.x) as f64, (screen_y + camera.y) as f64)
+    }
+
+    /// 월드 좌표의 사각 영역에 걸치는 타일 범위 (left, top, right, bottom) 를 구한다.
+    /// right, bottom 은 포함되는 값이다.
+    pub fn tile_range(&self, rect: &Rect) -> (i32, i32, i32, i32) {
+        let corners = [
+            (rect.x() as f64, rect.y() as f64),
+            (rect.right() as f64, rect.y() as f64),
+            (rect.x() as f64, rect.bottom() as f64),
+            (rect.right() as f64, rect.bottom() as f64),
+        ];
+
+        let mut left = i32::MAX;
+        let mut top = i32::MAX;
+        let mut right = i32::MIN;
+        let mut bottom = i32::MIN;
+
+        for (x, y) in corners.iter() {
+            let (tile_x, tile_y) = self.world_to_tile(*x, *y);
+            left = left.min(tile_x);
+            top = top.min(tile_y);
+            right = right.max(tile_x);
+            bottom = bottom.max(tile_y);
+        }
+
+        // 다이아몬드 타일은 모서리가 이웃 타일에 걸치므로 한 칸씩 여유를 둔다.
+        match self {
+            Projection::Orthogonal { .. } => (left, top, right, bottom),
+            _ => (left - 1, top - 1, right + 1, bottom + 1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Projection;
+
+    #[test]
+    fn orthogonal_round_trip() {
+        let projection = Projection::orthogonal(32, 32);
+
+        assert_eq!(projection.tile_to_world(3, 4), (96.0, 128.0));
+        assert_eq!(projection.world_to_tile(96.0, 128.0), (3, 4));
+        assert_eq!(projection.world_to_tile(127.9, 159.9), (3, 4));
+        assert_eq!(projection.pixel_size(100, 100), (3200, 3200));
+    }
+
+    #[test]
+    fn isometric_round_trip() {
+        let projection = Projection::isometric(10, 64, 32);
+
+        for tile_y in 0..10 {
+            for tile_x in 0..10 {
+                let (cx, cy) = projection.tile_center(tile_x, tile_y);
+                assert_eq!(projection.world_to_tile(cx, cy), (tile_x, tile_y));
+            }
+        }
+
+        // 가장 왼쪽 타일은 x = 0 에서 시작한다.
+        assert_eq!(projection.tile_to_world(0, 9).0, 0.0);
+
+        // 객체 좌표는 타일 축을 따라 기록된다.
+        let (wx, wy) = projection.object_to_world(48.0, 80.0);
+        assert_eq!(projection.world_to_object(wx, wy), (48.0, 80.0));
+        assert_eq!(projection.pixel_size(10, 10), (640, 320));
+    }
+
+    #[test]
+    fn staggered_round_trip() {
+        let projection = Projection::staggered(64, 32);
+
+        for tile_y in 0..10 {
+            for tile_x in 0..10 {
+                let (cx, cy) = projection.tile_center(tile_x, tile_y);
+                assert_eq!(projection.world_to_tile(cx, cy), (tile_x, tile_y));
+            }
+        }
+    }
+}
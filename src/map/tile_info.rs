@@ -0,0 +1,505 @@
+//! 타일별 속성
+//! 타일셋의 각 타일에 지정한 custom property 와 충돌 도형(<tile> 안의 objectgroup)을 읽는다.
+//!
+//! - solid(bool) : 타일 전체가 막힌다. type 이 solid/wall 인 타일도 같다.
+//! - one_way(bool) : 한쪽 방향으로만 통과할 수 있다.
+//! - speed(float) : 타일 위에서의 이동 속도 배율 (기본 1.0)
+//! - damage(float) : 타일 위에 있을 때 받는 피해량 (기본 0.0)
+
+use sdl2::rect::Rect;
+
+use std::collections::HashMap;
+
+use crate::map::{Projection, TileLayer};
+use crate::physics::shape::CollisionShape;
+
+/// 타일셋의 타일 하나에 대한 속성
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileInfo {
+    pub solid: bool,
+    pub one_way: bool,
+    pub speed: f64,
+    pub damage: f64,
+    pub tile_type: Option<String>,
+    /// 타일 좌상단에 대한 상대좌표의 충돌 도형
+    pub shapes: Vec<CollisionShape>,
+    pub properties: tiled::Properties,
+}
+
+impl Default for TileInfo {
+    fn default() -> TileInfo {
+        TileInfo {
+            solid: false,
+            one_way: false,
+            speed: 1.0,
+            damage: 0.0,
+            tile_type: None,
+            shapes: vec![],
+            properties: tiled::Properties::new(),
+        }
+    }
+}
+
+impl TileInfo {
+    /// 타일셋의 타일로부터 속성을 만든다.
+    /// 타일 크기와 같은 사각형 충돌 도형은 solid 로 처리한다.
+    pub fn from_tile(tile: &tiled::Tile, tile_width: u32, tile_height: u32) -> TileInfo {
+        let get_bool = |key: &str| match tile.properties.get(key) {
+            Some(tiled::PropertyValue::BoolValue(v)) => *v,
+            _ => false,
+        };
+        let get_f64 = |key: &str| match tile.properties.get(key) {
+            Some(tiled::PropertyValue::FloatValue(v)) => Some(*v as f64),
+            Some(tiled::PropertyValue::IntValue(v)) => Some(*v as f64),
+            _ => None,
+        };
+
+        let mut solid =
+            get_bool("solid") || matches!(tile.tile_type.as_deref(), Some("solid") | Some("wall"));
+
+        let full_rect = CollisionShape::Rect {
+            x: 0.0,
+            y: 0.0,
+            w: tile_width as f64,
+            h: tile_height as f64,
+        };
+
+        let mut shapes = vec![];
+        if let Some(group) = &tile.objectgroup {
+            for shape in group.objects.iter().filter_map(tile_object_shape) {
+                if shape == full_rect {
+                    solid = true;
+                } else {
+                    shapes.push(shape);
+                }
+            }
+        }
+
+        TileInfo {
+            solid,
+            one_way: get_bool("one_way"),
+            speed: get_f64("speed").unwrap_or(1.0),
+            damage: get_f64("damage").unwrap_or(0.0),
+            tile_type: tile.tile_type.clone(),
+            shapes,
+            properties: tile.properties.clone(),
+        }
+    }
+}
+
+/// 타일 충돌 편집기에서 만든 객체를 도형으로 바꾼다.
+/// 좌표는 타일 이미지의 좌상단 기준이다.
+fn tile_object_shape(object: &tiled::Object) -> Option<CollisionShape> {
+    let (x, y) = (object.x as f64, object.y as f64);
+
+    match &object.shape {
+        tiled::ObjectShape::Rect { width, height } => Some(CollisionShape::Rect {
+            x,
+            y,
+            w: *width as f64,
+            h: *height as f64,
+        }),
+        tiled::ObjectShape::Ellipse { width, height } => Some(CollisionShape::Ellipse {
+            cx: x + *width as f64 / 2.0,
+            cy: y + *height as f64 / 2.0,
+            rx: *width as f64 / 2.0,
+            ry: *height as f64 / 2.0,
+        }),
+        tiled::ObjectShape::Polygon { points } => Some(CollisionShape::Polygon(
+            points
+                .iter()
+                .map(|(px, py)| (x + *px as f64, y + *py as f64))
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+/// 한 타일 위치에 겹쳐 있는 모든 레이어의 타일 정보
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileQuery {
+    /// (레이어 이름, gid)
+    pub tiles: Vec<(String, u32)>,
+    pub solid: bool,
+    pub one_way: bool,
+    /// 각 레이어의 speed 를 곱한 값
+    pub speed: f64,
+    /// 각 레이어의 damage 를 더한 값
+    pub damage: f64,
+}
+
+impl Default for TileQuery {
+    fn default() -> TileQuery {
+        TileQuery {
+            tiles: vec![],
+            solid: false,
+            one_way: false,
+            speed: 1.0,
+            damage: 0.0,
+        }
+    }
+}
+
+impl TileQuery {
+    /// 레이어의 타일 하나를 더한다.
+    pub fn add(&mut self, layer: &str, gid: u32, info: Option<&TileInfo>) {
+        self.tiles.push((layer.to_owned(), gid));
+
+        if let Some(info) = info {
+            self.solid = self.solid || info.solid;
+            self.one_way = self.one_way || info.one_way;
+            self.speed *= info.speed;
+            self.damage += info.damage;
+        }
+    }
+}
+
+/// 막힌 타일들을 가능한 큰 사각형으로 묶는다.
+/// solid 는 width * height 크기의 행 우선 배열이며
+/// 반환값은 타일 단위의 (x, y, w, h) 목록이다.
+///
+/// 왼쪽 위부터 가로로 최대한 늘린 후, 같은 폭으로 아래로 늘린다.
+pub fn merge_solid_tiles(solid: &[bool], width: u32, height: u32) -> Vec<(u32, u32, u32, u32)> {
+    let index = |x: u32, y: u32| (y * width + x) as usize;
+    let mut used = vec![false; solid.len()];
+    let mut rects = vec![];
+
+    for y in 0..height {
+        for x in 0..width {
+            if !solid[index(x, y)] || used[index(x, y)] {
+                continue;
+            }
+
+            let mut w = 1;
+            while x + w < width && solid[index(x + w, y)] && !used[index(x + w, y)] {
+                w += 1;
+            }
+
+            let mut h = 1;
+            while y + h < height
+                && (x..x + w).all(|cx| solid[index(cx, y + h)] && !used[index(cx, y + h)])
+            {
+                h += 1;
+            }
+
+            for cy in y..y + h {
+                for cx in x..x + w {
+                    used[index(cx, cy)] = true;
+                }
+            }
+
+            rects.push((x, y, w, h));
+        }
+    }
+
+    rects
+}
+
+/// 막힌 타일들을 충돌 요소로 바꾼다.
+/// orthogonal 지도는 묶은 사각형을 blocks 로,
+/// isometric 지도는 묶은 영역의 평행사변형을 충돌 도형으로 돌려준다.
+/// staggered 지도는 행마다 타일이 어긋나 있으므로 타일마다 다이아몬드 도형을 만든다.
+/// solid 의 첫 칸은 타일 좌표 (left, top) 에 해당한다.
+pub fn solid_colliders(
+    projection: &Projection,
+    solid: &[bool],
+    (left, top): (i32, i32),
+    width: u32,
+    height: u32,
+) -> (Vec<Rect>, Vec<CollisionShape>) {
+    let (tw, th) = projection.tile_size();
+    let mut blocks = vec![];
+    let mut shapes = vec![];
+
+    // isometric 타일 격자의 꼭지점. 타일 (x, y) 의 윗 꼭지점이 (x, y) 가 된다.
+    let corner = |x: u32, y: u32| {
+        let (wx, wy) = projection.tile_to_world(left + x as i32, top + y as i32);
+        (wx + tw as f64 / 2.0, wy)
+    };
+
+    match projection {
+        Projection::Orthogonal { .. } => {
+            for (x, y, w, h) in merge_solid_tiles(solid, width, height) {
+                let (wx, wy) = projection.tile_to_world(left + x as i32, top + y as i32);
+                blocks.push(Rect::new(wx as i32, wy as i32, w * tw, h * th));
+            }
+        }
+        Projection::Isometric { .. } => {
+            for (x, y, w, h) in merge_solid_tiles(solid, width, height) {
+                shapes.push(CollisionShape::Polygon(vec![
+                    corner(x, y),
+                    corner(x + w, y),
+                    corner(x + w, y + h),
+                    corner(x, y + h),
+                ]));
+            }
+        }
+        Projection::Staggered { .. } => {
+            for y in 0..height {
+                for x in 0..width {
+                    if solid[(y * width + x) as usize] {
+                        let (cx, cy) = projection.tile_center(left + x as i32, top + y as i32);
+                        let (hw, hh) = (tw as f64 / 2.0, th as f64 / 2.0);
+                        shapes.push(CollisionShape::Polygon(vec![
+                            (cx, cy - hh),
+                            (cx + hw, cy),
+                            (cx, cy + hh),
+                            (cx - hw, cy),
+                        ]));
+                    }
+                }
+            }
+        }
+    }
+
+    (blocks, shapes)
+}
+
+/// 타일 범위 (left, top) ~ (right, bottom) 의 충돌 요소
+/// solid 속성의 타일과 collision 레이어의 타일은 막힌 타일이다.
+/// 막힌 타일은 큰 사각형으로 묶고, 타일의 충돌 도형은 그대로 월드 좌표로 옮긴다.
+/// image_height 는 gid 의 타일 이미지 높이이다. 타일 이미지는 지도 타일의 아래쪽에 맞춰 그려진다.
+pub fn tile_colliders<F>(
+    projection: &Projection,
+    layers: &[TileLayer],
+    tile_infos: &HashMap<u32, TileInfo>,
+    image_height: F,
+    (left, top, right, bottom): (i32, i32, i32, i32),
+) -> (Vec<Rect>, Vec<CollisionShape>)
+where
+    F: Fn(u32) -> u32,
+{
+    if right < left || bottom < top {
+        return (vec![], vec![]);
+    }
+
+    let width = (right - left + 1) as u32;
+    let height = (bottom - top + 1) as u32;
+    let (_, tile_height) = projection.tile_size();
+
+    let mut solid = vec![false; (width * height) as usize];
+    let mut tile_shapes = vec![];
+
+    for layer in layers.iter() {
+        layer.for_each_in(left, top, right, bottom, |x, y, tile| {
+            let info = tile_infos.get(&tile.gid);
+            if layer.name == "collision" || info.is_some_and(|info| info.solid) {
+                let idx = (y - top) as u32 * width + (x - left) as u32;
+                solid[idx as usize] = true;
+            }
+
+            if let Some(info) = info {
+                let (world_x, world_y) = projection.tile_to_world(x, y);
+                let image_y = world_y + tile_height as f64 - image_height(tile.gid) as f64;
+
+                for shape in info.shapes.iter() {
+                    tile_shapes.push(shape.translate(world_x, image_y));
+                }
+            }
+        });
+    }
+
+    let (blocks, mut shapes) = solid_colliders(projection, &solid, (left, top), width, height);
+    shapes.extend(tile_shapes);
+
+    (blocks, shapes)
+}
+
+/// 청크마다 나누어 둔 지도의 충돌 요소
+/// 타일 하나를 바꾸면 그 청크만 다시 만든다.
+#[derive(Clone, Debug, Default)]
+pub struct ChunkColliders {
+    chunks: HashMap<(i32, i32), (Vec<Rect>, Vec<CollisionShape>)>,
+}
+
+impl ChunkColliders {
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// 청크의 충돌 요소를 바꾼다. 비어있다면 청크를 뺀다.
+    pub fn set(&mut self, key: (i32, i32), (blocks, shapes): (Vec<Rect>, Vec<CollisionShape>)) {
+        if blocks.is_empty() && shapes.is_empty() {
+            self.chunks.remove(&key);
+        } else {
+            self.chunks.insert(key, (blocks, shapes));
+        }
+    }
+
+    /// 모든 청크의 blocks 와 충돌 도형. 청크 좌표 순서로 늘어놓는다.
+    pub fn collect(&self) -> (Vec<Rect>, Vec<CollisionShape>) {
+        let mut keys: Vec<&(i32, i32)> = self.chunks.keys().collect();
+        keys.sort_by_key(|(x, y)| (*y, *x));
+
+        let mut blocks = vec![];
+        let mut shapes = vec![];
+        for key in keys {
+            let (chunk_blocks, chunk_shapes) = &self.chunks[key];
+            blocks.extend(chunk_blocks.iter().copied());
+            shapes.extend(chunk_shapes.iter().cloned());
+        }
+
+        (blocks, shapes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> Vec<bool> {
+        rows.iter()
+            .flat_map(|row| row.chars().map(|c| c == '#'))
+            .collect()
+    }
+
+    #[test]
+    fn merge_into_rectangles() {
+        let solid = grid(&["##..", "##..", "...#", "####"]);
+        let rects = merge_solid_tiles(&solid, 4, 4);
+
+        assert_eq!(rects, vec![(0, 0, 2, 2), (3, 2, 1, 2), (0, 3, 3, 1)]);
+
+        // 묶인 사각형의 면적 합은 막힌 타일 수와 같다.
+        let area: u32 = rects.iter().map(|(_, _, w, h)| w * h).sum();
+        assert_eq!(area, solid.iter().filter(|s| **s).count() as u32);
+    }
+
+    #[test]
+    fn orthogonal_blocks_and_isometric_shapes() {
+        let solid = grid(&["###", "###"]);
+
+        let (blocks, shapes) =
+            solid_colliders(&Projection::orthogonal(32, 32), &solid, (0, 0), 3, 2);
+        assert_eq!(blocks, vec![Rect::new(0, 0, 96, 64)]);
+        assert!(shapes.is_empty());
+
+        // 음수 좌표에서 시작하는 무한 지도
+        let (blocks, _) = solid_colliders(&Projection::orthogonal(32, 32), &solid, (-2, 1), 3, 2);
+        assert_eq!(blocks, vec![Rect::new(-64, 32, 96, 64)]);
+
+        let projection = Projection::isometric(2, 64, 32);
+        let (blocks, shapes) = solid_colliders(&projection, &solid, (0, 0), 3, 2);
+        assert!(blocks.is_empty());
+        assert_eq!(shapes.len(), 1);
+
+        // 막힌 영역의 가운데 타일 중심은 도형 안에 있다.
+        let (cx, cy) = projection.tile_center(1, 1);
+        assert!(shapes[0].intersects(&CollisionShape::Rect {
+            x: cx - 1.0,
+            y: cy - 1.0,
+            w: 2.0,
+            h: 2.0
+        }));
+    }
+
+    #[test]
+    fn query_combines_layers() {
+        let mud = TileInfo {
+            speed: 0.5,
+            ..TileInfo::default()
+        };
+        let lava = TileInfo {
+            damage: 10.0,
+            speed: 0.5,
+            ..TileInfo::default()
+        };
+
+        let mut query = TileQuery::default();
+        query.add("ground", 1, Some(&mud));
+        query.add("hazard", 2, Some(&lava));
+        query.add("deco", 3, None);
+
+        assert_eq!(query.tiles.len(), 3);
+        assert!(!query.solid);
+        assert_eq!(query.speed, 0.25);
+        assert_eq!(query.damage, 10.0);
+    }
+
+    #[test]
+    fn update_one_chunk() {
+        use crate::map::CHUNK_SIZE;
+
+        let tile = |gid: u32| tiled::LayerTile {
+            gid,
+            flip_h: false,
+            flip_v: false,
+            flip_d: false,
+        };
+        let chunk = |x: i32| {
+            (
+                x * CHUNK_SIZE,
+                0,
+                x * CHUNK_SIZE + CHUNK_SIZE - 1,
+                CHUNK_SIZE - 1,
+            )
+        };
+
+        let projection = Projection::orthogonal(32, 32);
+        let mut tile_infos = HashMap::new();
+        tile_infos.insert(
+            2,
+            TileInfo {
+                shapes: vec![CollisionShape::Rect {
+                    x: 0.0,
+                    y: 0.0,
+                    w: 8.0,
+                    h: 8.0,
+                }],
+                ..TileInfo::default()
+            },
+        );
+
+        let mut layers = vec![TileLayer::new("collision"), TileLayer::new("ground")];
+        layers[0].set(0, 0, tile(1));
+        layers[0].set(1, 0, tile(1));
+        layers[0].set(CHUNK_SIZE, 0, tile(1));
+
+        let mut colliders = ChunkColliders::default();
+        for x in 0..2 {
+            let chunk_colliders =
+                tile_colliders(&projection, &layers, &tile_infos, |_| 32, chunk(x));
+            colliders.set((x, 0), chunk_colliders);
+        }
+        assert_eq!(
+            colliders.collect().0,
+            vec![
+                Rect::new(0, 0, 64, 32),
+                Rect::new(CHUNK_SIZE * 32, 0, 32, 32)
+            ]
+        );
+
+        // 타일 하나를 바꾸면 그 청크만 다시 만든다.
+        layers[0].set(2, 0, tile(1));
+        layers[1].set(3, 1, tile(2));
+        colliders.set(
+            (0, 0),
+            tile_colliders(&projection, &layers, &tile_infos, |_| 32, chunk(0)),
+        );
+        let (blocks, shapes) = colliders.collect();
+        assert_eq!(
+            blocks,
+            vec![
+                Rect::new(0, 0, 96, 32),
+                Rect::new(CHUNK_SIZE * 32, 0, 32, 32)
+            ]
+        );
+        assert_eq!(
+            shapes,
+            vec![CollisionShape::Rect {
+                x: 96.0,
+                y: 32.0,
+                w: 8.0,
+                h: 8.0
+            }]
+        );
+
+        // 비게 된 청크는 빠진다.
+        layers[0].set(CHUNK_SIZE, 0, tile(0));
+        colliders.set(
+            (1, 0),
+            tile_colliders(&projection, &layers, &tile_infos, |_| 32, chunk(1)),
+        );
+        assert_eq!(colliders.collect().0, vec![Rect::new(0, 0, 96, 32)]);
+    }
+}
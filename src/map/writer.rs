@@ -0,0 +1,630 @@
+//! TMX 저장
+//! 편집한 지도를 Tiled 에서 다시 열 수 있는 .tmx 로 저장한다.
+//! 타일 데이터는 CSV 로 쓰고, 외부 타일셋(.tsx)도 지도 안에 풀어서 쓴다.
+//! 지도 범위가 (0, 0) 에서 시작하지 않는다면 무한 지도로 저장한다.
+//! 타일 레이어와 객체 레이어는 읽었을 때의 순서대로 쓴다.
+
+use std::io::{self, Write};
+
+use crate::map::layer::{TileLayer, CHUNK_SIZE};
+
+const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY_FLAG: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY_FLAG: u32 = 0x2000_0000;
+
+/// TMX 에 쓸 레이어 하나
+enum LayerEntry<'m> {
+    /// layers 의 index
+    Tile(usize),
+    Objects(&'m tiled::ObjectGroup),
+}
+
+/// TMX 로 쓸 지도 정보
+pub struct TmxDocument<'m> {
+    pub orientation: tiled::Orientation,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    /// 타일 범위 (left, top, width, height)
+    pub tile_left: i32,
+    pub tile_top: i32,
+    pub width: u32,
+    pub height: u32,
+    pub tilesets: &'m [tiled::Tileset],
+    pub layers: &'m [TileLayer],
+    pub object_groups: &'m [tiled::ObjectGroup],
+    pub properties: &'m tiled::Properties,
+}
+
+impl<'m> TmxDocument<'m> {
+    /// 무한 지도로 저장해야 하는가
+    pub fn is_infinite(&self) -> bool {
+        self.tile_left != 0
+            || self.tile_top != 0
+            || self.layers.iter().any(|layer| match layer.bounds() {
+                Some((left, top, _, _)) => left < 0 || top < 0,
+                None => false,
+            })
+    }
+
+    /// 타일 레이어와 객체 레이어를 TMX 의 순서대로 늘어놓는다.
+    /// 순서를 모르는 객체 레이어는 맨 위에 둔다.
+    fn ordered_layers(&self) -> Vec<LayerEntry<'m>> {
+        let mut entries: Vec<(u32, LayerEntry<'m>)> = self
+            .layers
+            .iter()
+            .enumerate()
+            .map(|(idx, layer)| (layer.layer_index, LayerEntry::Tile(idx)))
+            .chain(self.object_groups.iter().map(|group| {
+                (
+                    group.layer_index.unwrap_or(u32::MAX),
+                    LayerEntry::Objects(group),
+                )
+            }))
+            .collect();
+        entries.sort_by_key(|(index, _)| *index);
+
+        entries.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    /// 레이어마다 쓸 id 와 다음 레이어 id
+    /// id 가 없는 (0 인) 새 타일 레이어는 쓰고 있는 id 다음부터 차례로 붙인다.
+    fn layer_ids(&self) -> (Vec<u32>, u32) {
+        let mut next_id = self
+            .layers
+            .iter()
+            .map(|layer| layer.id)
+            .chain(self.object_groups.iter().map(|group| group.id))
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        let ids = self
+            .layers
+            .iter()
+            .map(|layer| {
+                if layer.id != 0 {
+                    layer.id
+                } else {
+                    next_id += 1;
+                    next_id - 1
+                }
+            })
+            .collect();
+
+        (ids, next_id)
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let infinite = self.is_infinite();
+        let (layer_ids, next_layer_id) = self.layer_ids();
+        let next_object_id = self
+            .object_groups
+            .iter()
+            .flat_map(|group| group.objects.iter().map(|object| object.id))
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let orientation = match self.orientation {
+            tiled::Orientation::Orthogonal => "orthogonal",
+            tiled::Orientation::Isometric => "isometric",
+            tiled::Orientation::Staggered => "staggered",
+            tiled::Orientation::Hexagonal => "hexagonal",
+        };
+        let stagger = match self.orientation {
+            tiled::Orientation::Staggered => " staggeraxis=\"y\" staggerindex=\"odd\"",
+            _ => "",
+        };
+
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            out,
+            "<map version=\"1.4\" orientation=\"{}\" renderorder=\"right-down\" width=\"{}\" height=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" infinite=\"{}\"{} nextlayerid=\"{}\" nextobjectid=\"{}\">",
+            orientation,
+            self.width,
+            self.height,
+            self.tile_width,
+            self.tile_height,
+            infinite as u8,
+            stagger,
+            next_layer_id,
+            next_object_id
+        )?;
+        write_properties(out, self.properties, 1)?;
+
+        for tileset in self.tilesets.iter() {
+            write_tileset(out, tileset)?;
+        }
+
+        for entry in self.ordered_layers() {
+            match entry {
+                LayerEntry::Tile(idx) => {
+                    self.write_layer(out, layer_ids[idx], &self.layers[idx], infinite)?;
+                }
+                LayerEntry::Objects(group) => {
+                    writeln!(
+                        out,
+                        " <objectgroup id=\"{}\" name=\"{}\">",
+                        group.id,
+                        escape(&group.name)
+                    )?;
+                    write_properties(out, &group.properties, 2)?;
+                    for object in group.objects.iter() {
+                        write_object(out, object, 2)?;
+                    }
+                    writeln!(out, " </objectgroup>")?;
+                }
+            }
+        }
+
+        writeln!(out, "</map>")
+    }
+
+    fn write_layer<W: Write>(
+        &self,
+        out: &mut W,
+        id: u32,
+        layer: &TileLayer,
+        infinite: bool,
+    ) -> io::Result<()> {
+        let visible = if layer.visible { "" } else { " visible=\"0\"" };
+        let opacity = if layer.opacity < 1.0 {
+            format!(" opacity=\"{}\"", layer.opacity)
+        } else {
+            String::new()
+        };
+
+        writeln!(
+            out,
+            " <layer id=\"{}\" name=\"{}\" width=\"{}\" height=\"{}\"{}{}>",
+            id,
+            escape(&layer.name),
+            self.width,
+            self.height,
+            visible,
+            opacity
+        )?;
+        write_properties(out, &layer.properties, 2)?;
+        writeln!(out, "  <data encoding=\"csv\">")?;
+
+        if infinite {
+            // 청크 좌표 순서대로 쓴다.
+            let mut keys: Vec<&(i32, i32)> = layer.chunks.keys().collect();
+            keys.sort_by_key(|(x, y)| (*y, *x));
+
+            for key in keys {
+                let chunk = &layer.chunks[key];
+                writeln!(
+                    out,
+                    "   <chunk x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\">",
+                    chunk.x, chunk.y, CHUNK_SIZE, CHUNK_SIZE
+                )?;
+                write_csv(
+                    out,
+                    layer,
+                    chunk.x,
+                    chunk.y,
+                    CHUNK_SIZE as u32,
+                    CHUNK_SIZE as u32,
+                )?;
+                writeln!(out, "   </chunk>")?;
+            }
+        } else {
+            write_csv(out, layer, 0, 0, self.width, self.height)?;
+        }
+
+        writeln!(out, "  </data>")?;
+        writeln!(out, " </layer>")
+    }
+}
+
+/// 레이어의 (left, top) 부터 width x height 타일을 CSV 로 쓴다.
+/// Tiled 와 같이 마지막 행을 제외한 모든 행은 쉼표로 끝난다.
+pub fn write_csv<W: Write>(
+    out: &mut W,
+    layer: &TileLayer,
+    left: i32,
+    top: i32,
+    width: u32,
+    height: u32,
+) -> io::Result<()> {
+    for y in 0..height as i32 {
+        let row: Vec<String> = (0..width as i32)
+            .map(|x| encode_gid(layer.get(left + x, top + y)).to_string())
+            .collect();
+
+        let separator = if y + 1 < height as i32 { "," } else { "" };
+        writeln!(out, "{}{}", row.join(","), separator)?;
+    }
+
+    Ok(())
+}
+
+/// 뒤집기 정보를 gid 의 상위 비트에 넣는다.
+pub fn encode_gid(tile: &tiled::LayerTile) -> u32 {
+    let mut gid = tile.gid;
+    if tile.flip_h {
+        gid |= FLIPPED_HORIZONTALLY_FLAG;
+    }
+    if tile.flip_v {
+        gid |= FLIPPED_VERTICALLY_FLAG;
+    }
+    if tile.flip_d {
+        gid |= FLIPPED_DIAGONALLY_FLAG;
+    }
+    gid
+}
+
+fn write_tileset<W: Write>(out: &mut W, tileset: &tiled::Tileset) -> io::Result<()> {
+    let (columns, rows) = match tileset.images.first() {
+        Some(image) => (
+            (image.width as u32 - tileset.margin * 2 + tileset.spacing)
+                / (tileset.tile_width + tileset.spacing),
+            (image.height as u32 - tileset.margin * 2 + tileset.spacing)
+                / (tileset.tile_height + tileset.spacing),
+        ),
+        None => (0, 0),
+    };
+    // 이미지 모음 타일셋은 타일 id 가 비어있을 수 있으므로 가장 큰 id 까지 센다.
+    let tilecount = match (tileset.tilecount, tileset.images.first()) {
+        (Some(tilecount), _) => tilecount,
+        (None, Some(_)) => columns * rows,
+        (None, None) => tileset
+            .tiles
+            .iter()
+            .map(|tile| tile.id + 1)
+            .max()
+            .unwrap_or(0),
+    };
+
+    writeln!(
+        out,
+        " <tileset firstgid=\"{}\" name=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" spacing=\"{}\" margin=\"{}\" tilecount=\"{}\" columns=\"{}\">",
+        tileset.first_gid,
+        escape(&tileset.name),
+        tileset.tile_width,
+        tileset.tile_height,
+        tileset.spacing,
+        tileset.margin,
+        tilecount,
+        columns
+    )?;
+    write_properties(out, &tileset.properties, 2)?;
+
+    for image in tileset.images.iter() {
+        write_image(out, image, 2)?;
+    }
+
+    for tile in tileset.tiles.iter() {
+        match &tile.tile_type {
+            Some(tile_type) => writeln!(
+                out,
+                "  <tile id=\"{}\" type=\"{}\">",
+                tile.id,
+                escape(tile_type)
+            )?,
+            None => writeln!(out, "  <tile id=\"{}\">", tile.id)?,
+        }
+        write_properties(out, &tile.properties, 3)?;
+
+        for image in tile.images.iter() {
+            write_image(out, image, 3)?;
+        }
+
+        if let Some(group) = &tile.objectgroup {
+            writeln!(out, "   <objectgroup draworder=\"index\">")?;
+            for object in group.objects.iter() {
+                write_object(out, object, 4)?;
+            }
+            writeln!(out, "   </objectgroup>")?;
+        }
+
+        if let Some(frames) = &tile.animation {
+            writeln!(out, "   <animation>")?;
+            for frame in frames.iter() {
+                writeln!(
+                    out,
+                    "    <frame tileid=\"{}\" duration=\"{}\"/>",
+                    frame.tile_id, frame.duration
+                )?;
+            }
+            writeln!(out, "   </animation>")?;
+        }
+
+        writeln!(out, "  </tile>")?;
+    }
+
+    writeln!(out, " </tileset>")
+}
+
+fn write_image<W: Write>(out: &mut W, image: &tiled::Image, depth: usize) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}<image source=\"{}\" width=\"{}\" height=\"{}\"/>",
+        " ".repeat(depth),
+        escape(&image.source),
+        image.width,
+        image.height
+    )
+}
+
+fn write_object<W: Write>(out: &mut W, object: &tiled::Object, depth: usize) -> io::Result<()> {
+    let indent = " ".repeat(depth);
+
+    let mut attributes = format!("id=\"{}\"", object.id);
+    if object.gid != 0 {
+        attributes += &format!(" gid=\"{}\"", object.gid);
+    }
+    if !object.name.is_empty() {
+        attributes += &format!(" name=\"{}\"", escape(&object.name));
+    }
+    if !object.obj_type.is_empty() {
+        attributes += &format!(" type=\"{}\"", escape(&object.obj_type));
+    }
+    attributes += &format!(" x=\"{}\" y=\"{}\"", object.x, object.y);
+
+    match &object.shape {
+        tiled::ObjectShape::Rect { width, height }
+        | tiled::ObjectShape::Ellipse { width, height } => {
+            attributes += &format!(" width=\"{}\" height=\"{}\"", width, height);
+        }
+        _ => (),
+    }
+    if object.rotation != 0.0 {
+        attributes += &format!(" rotation=\"{}\"", object.rotation);
+    }
+    if !object.visible {
+        attributes += " visible=\"0\"";
+    }
+
+    writeln!(out, "{}<object {}>", indent, attributes)?;
+    write_properties(out, &object.properties, depth + 1)?;
+
+    let points_attribute = |points: &[(f32, f32)]| {
+        points
+            .iter()
+            .map(|(x, y)| format!("{},{}", x, y))
+            .collect::<Vec<String>>()
+            .join(" ")
+    };
+
+    match &object.shape {
+        tiled::ObjectShape::Ellipse { .. } => writeln!(out, "{} <ellipse/>", indent)?,
+        tiled::ObjectShape::Point(_, _) => writeln!(out, "{} <point/>", indent)?,
+        tiled::ObjectShape::Polygon { points } => writeln!(
+            out,
+            "{} <polygon points=\"{}\"/>",
+            indent,
+            points_attribute(points)
+        )?,
+        tiled::ObjectShape::Polyline { points } => writeln!(
+            out,
+            "{} <polyline points=\"{}\"/>",
+            indent,
+            points_attribute(points)
+        )?,
+        tiled::ObjectShape::Rect { .. } => (),
+    }
+
+    writeln!(out, "{}</object>", indent)
+}
+
+fn write_properties<W: Write>(
+    out: &mut W,
+    properties: &tiled::Properties,
+    depth: usize,
+) -> io::Result<()> {
+    if properties.is_empty() {
+        return Ok(());
+    }
+
+    let indent = " ".repeat(depth);
+
+    // HashMap 순서에 관계없이 항상 같은 파일이 나오도록 이름순으로 쓴다.
+    let mut names: Vec<&String> = properties.keys().collect();
+    names.sort();
+
+    writeln!(out, "{}<properties>", indent)?;
+    for name in names {
+        let (property_type, value) = match &properties[name] {
+            tiled::PropertyValue::BoolValue(v) => ("bool", v.to_string()),
+            tiled::PropertyValue::FloatValue(v) => ("float", v.to_string()),
+            tiled::PropertyValue::IntValue(v) => ("int", v.to_string()),
+            tiled::PropertyValue::ColorValue(v) => ("color", format!("#{:08x}", v)),
+            tiled::PropertyValue::StringValue(v) => ("string", v.clone()),
+            tiled::PropertyValue::FileValue(v) => ("file", v.clone()),
+        };
+        writeln!(
+            out,
+            "{} <property name=\"{}\" type=\"{}\" value=\"{}\"/>",
+            indent,
+            escape(name),
+            property_type,
+            escape(&value)
+        )?;
+    }
+    writeln!(out, "{}</properties>", indent)
+}
+
+/// XML 속성값에 쓸 수 없는 문자를 바꾼다.
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(gid: u32) -> tiled::LayerTile {
+        tiled::LayerTile {
+            gid,
+            flip_h: false,
+            flip_v: false,
+            flip_d: false,
+        }
+    }
+
+    fn sample_layer() -> TileLayer {
+        let mut layer = TileLayer::new("ground");
+        layer.set(0, 0, tile(1));
+        layer.set(2, 1, tile(2));
+        layer.set(
+            1,
+            1,
+            tiled::LayerTile {
+                gid: 3,
+                flip_h: true,
+                flip_v: false,
+                flip_d: true,
+            },
+        );
+        layer
+    }
+
+    #[test]
+    fn csv_rows() {
+        let mut out = vec![];
+        write_csv(&mut out, &sample_layer(), 0, 0, 3, 2).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("1,0,0,\n0,{},2\n", 3 | 0xA000_0000u32)
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let layers = vec![sample_layer()];
+        let properties = tiled::Properties::new();
+        let tilesets = vec![tiled::Tileset {
+            first_gid: 1,
+            name: "tilesheet".to_owned(),
+            tile_width: 32,
+            tile_height: 32,
+            spacing: 0,
+            margin: 0,
+            tilecount: Some(84),
+            images: vec![tiled::Image {
+                source: "tilesheet.png".to_owned(),
+                width: 448,
+                height: 192,
+                transparent_colour: None,
+            }],
+            tiles: vec![],
+            properties: tiled::Properties::new(),
+        }];
+
+        let document = TmxDocument {
+            orientation: tiled::Orientation::Orthogonal,
+            tile_width: 32,
+            tile_height: 32,
+            tile_left: 0,
+            tile_top: 0,
+            width: 3,
+            height: 2,
+            tilesets: &tilesets,
+            layers: &layers,
+            object_groups: &[],
+            properties: &properties,
+        };
+
+        let mut out = vec![];
+        document.write(&mut out).unwrap();
+
+        let map = tiled::parse(out.as_slice()).unwrap();
+        let layer = TileLayer::from_tiled(&map.layers[0]);
+
+        assert_eq!((map.width, map.height), (3, 2));
+        assert_eq!(map.tilesets[0].images[0].source, "tilesheet.png");
+        assert_eq!(layer.get(0, 0).gid, 1);
+        assert_eq!(layer.get(2, 1).gid, 2);
+        assert_eq!(layer.get(1, 1), sample_layer().get(1, 1));
+    }
+
+    #[test]
+    fn layer_order_and_ids() {
+        let mut ground = sample_layer();
+        ground.id = 3;
+        ground.layer_index = 0;
+        let mut top = TileLayer::new("top");
+        top.layer_index = 2;
+        let layers = vec![top, ground];
+
+        let object_groups = vec![tiled::ObjectGroup {
+            name: "spawns".to_owned(),
+            opacity: 1.0,
+            visible: true,
+            objects: vec![tiled::Object {
+                id: 7,
+                gid: 0,
+                name: String::new(),
+                obj_type: "PLAYER".to_owned(),
+                width: 0.0,
+                height: 0.0,
+                x: 16.0,
+                y: 16.0,
+                rotation: 0.0,
+                visible: true,
+                shape: tiled::ObjectShape::Point(16.0, 16.0),
+                properties: tiled::Properties::new(),
+            }],
+            colour: None,
+            layer_index: Some(1),
+            properties: tiled::Properties::new(),
+            id: 5,
+        }];
+
+        // 이미지 모음 타일셋의 타일 id 는 비어있을 수 있다.
+        let tile = |id: u32| tiled::Tile {
+            id,
+            images: vec![],
+            properties: tiled::Properties::new(),
+            objectgroup: None,
+            animation: None,
+            tile_type: None,
+            probability: 1.0,
+        };
+        let tilesets = vec![tiled::Tileset {
+            first_gid: 1,
+            name: "images".to_owned(),
+            tile_width: 32,
+            tile_height: 32,
+            spacing: 0,
+            margin: 0,
+            tilecount: None,
+            images: vec![],
+            tiles: vec![tile(0), tile(4)],
+            properties: tiled::Properties::new(),
+        }];
+        let properties = tiled::Properties::new();
+
+        let document = TmxDocument {
+            orientation: tiled::Orientation::Orthogonal,
+            tile_width: 32,
+            tile_height: 32,
+            tile_left: 0,
+            tile_top: 0,
+            width: 3,
+            height: 2,
+            tilesets: &tilesets,
+            layers: &layers,
+            object_groups: &object_groups,
+            properties: &properties,
+        };
+
+        let mut out = vec![];
+        document.write(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.contains("nextlayerid=\"7\" nextobjectid=\"8\""));
+        assert!(text.contains("tilecount=\"5\""));
+
+        let ground = text.find("<layer id=\"3\" name=\"ground\"").unwrap();
+        let spawns = text.find("<objectgroup id=\"5\" name=\"spawns\"").unwrap();
+        let top = text.find("<layer id=\"6\" name=\"top\"").unwrap();
+        assert!(ground < spawns && spawns < top);
+    }
+}
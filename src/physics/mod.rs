@@ -1,2 +1,3 @@
 pub mod collision;
 pub mod shadow_casting;
+pub mod shape;
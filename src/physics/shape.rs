@@ -0,0 +1,121 @@
+//! 충돌 판정용 도형
+//! Tiled 객체 레이어의 사각형, 타원, 다각형을 월드 좌표의 도형으로 보관한다.
+//! 판정은 collision::sat_detection 을 이용하므로 다각형은 볼록(convex)해야 정확하다.
+
+use crate::physics::collision::sat_detection;
+use crate::*;
+
+/// 타원을 다각형으로 근사할 때 사용할 꼭지점 수
+const ELLIPSE_SEGMENTS: usize = 16;
+
+/// 월드 좌표상의 충돌 도형
+#[derive(Clone, Debug, PartialEq)]
+pub enum CollisionShape {
+    /// 좌상단 (x, y) 와 크기 (w, h)
+    Rect { x: f64, y: f64, w: f64, h: f64 },
+    /// 중심 (cx, cy) 와 반지름 (rx, ry)
+    Ellipse { cx: f64, cy: f64, rx: f64, ry: f64 },
+    /// 꼭지점 목록
+    Polygon(Vec<(f64, f64)>),
+}
+
+impl CollisionShape {
+    /// 도형을 다각형의 꼭지점 목록으로 바꾼다.
+    /// 타원은 ELLIPSE_SEGMENTS 개의 꼭지점으로 근사한다.
+    pub fn to_polygon(&self) -> Vec<(f64, f64)> {
+        match self {
+            CollisionShape::Rect { x, y, w, h } => {
+                vec![(*x, *y), (x + w, *y), (x + w, y + h), (*x, y + h)]
+            }
+            CollisionShape::Ellipse { cx, cy, rx, ry } => (0..ELLIPSE_SEGMENTS)
+                .map(|i| {
+                    let radian = i as f64 * 2.0 * std::f64::consts::PI / ELLIPSE_SEGMENTS as f64;
+                    (cx + rx * radian.cos(), cy + ry * radian.sin())
+                })
+                .collect(),
+            CollisionShape::Polygon(points) => points.clone(),
+        }
+    }
+
+    /// (dx, dy) 만큼 옮긴 도형
+    pub fn translate(&self, dx: f64, dy: f64) -> CollisionShape {
+        match self {
+            CollisionShape::Rect { x, y, w, h } => CollisionShape::Rect {
+                x: x + dx,
+                y: y + dy,
+                w: *w,
+                h: *h,
+            },
+            CollisionShape::Ellipse { cx, cy, rx, ry } => CollisionShape::Ellipse {
+                cx: cx + dx,
+                cy: cy + dy,
+                rx: *rx,
+                ry: *ry,
+            },
+            CollisionShape::Polygon(points) => {
+                CollisionShape::Polygon(points.iter().map(|(x, y)| (x + dx, y + dy)).collect())
+            }
+        }
+    }
+
+    /// 도형을 감싸는 사각형
+    pub fn bounding_box(&self) -> Rectangle {
+        match self {
+            CollisionShape::Rect { x, y, w, h } => Rectangle::new(*x, *y, *w, *h),
+            CollisionShape::Ellipse { cx, cy, rx, ry } => {
+                Rectangle::new(cx - rx, cy - ry, rx * 2.0, ry * 2.0)
+            }
+            CollisionShape::Polygon(points) => {
+                let min_x = points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+                let min_y = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+                let max_x = points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+                let max_y = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+
+                Rectangle::new(min_x, min_y, max_x - min_x, max_y - min_y)
+            }
+        }
+    }
+
+    /// 다른 도형과 겹치는가
+    pub fn intersects(&self, other: &CollisionShape) -> bool {
+        detect_collision(&self.bounding_box(), &other.bounding_box())
+            && sat_detection(&self.to_polygon(), &other.to_polygon())
+    }
+
+    /// hitbox 등의 사각형과 겹치는가
+    pub fn intersects_rect(&self, rect: &Rectangle) -> bool {
+        self.intersects(&CollisionShape::Rect {
+            x: rect.x,
+            y: rect.y,
+            w: rect.w,
+            h: rect.h,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ellipse_and_rect() {
+        let ellipse = CollisionShape::Ellipse {
+            cx: 50.0,
+            cy: 50.0,
+            rx: 20.0,
+            ry: 10.0,
+        };
+
+        assert!(ellipse.intersects_rect(&Rectangle::new(65.0, 45.0, 10.0, 10.0)));
+        // 바운딩 박스의 모서리에는 걸리지만 타원에는 닿지 않는다.
+        assert!(!ellipse.intersects_rect(&Rectangle::new(28.0, 38.0, 4.0, 4.0)));
+    }
+
+    #[test]
+    fn polygon_and_rect() {
+        let triangle = CollisionShape::Polygon(vec![(0.0, 0.0), (100.0, 0.0), (0.0, 100.0)]);
+
+        assert!(triangle.intersects_rect(&Rectangle::new(10.0, 10.0, 10.0, 10.0)));
+        assert!(!triangle.intersects_rect(&Rectangle::new(60.0, 60.0, 10.0, 10.0)));
+    }
+}
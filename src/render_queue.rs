@@ -0,0 +1,122 @@
+//! 깊이 정렬 렌더링
+//! 지도의 타일과 entity를 한 곳에 모은 후 깊이(depth) 순서로 정렬하여 그린다.
+//! 깊이는 월드 좌표상의 바닥(y) 값이므로, 아래쪽에 있는 것일수록 나중에 그려진다.
+//! isometric 지도도 월드 좌표가 이미 투영된 좌표이므로 같은 방식으로 정렬된다.
+
+use sdl2::rect::Rect;
+use uuid::Uuid;
+
+use crate::tile::TileFlip;
+
+/// 그리기 명령
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderCommand {
+    /// 지도의 타일
+    /// texture : 지도 텍스쳐 인덱스, src : 텍스쳐 영역, dest : 화면 영역, flip : 뒤집기
+    Tile {
+        texture: usize,
+        src: Rect,
+        dest: Rect,
+        flip: TileFlip,
+    },
+    /// entity 전체(animation, attack, hitbox)
+    Entity(Uuid),
+}
+
+/// 정렬키가 붙은 그리기 명령
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderItem {
+    /// 명시적인 높이 순서. 값이 크면 depth와 관계없이 위에 그린다.
+    pub z: i32,
+    /// 월드 좌표상의 바닥 y 값
+    pub depth: f64,
+    pub command: RenderCommand,
+}
+
+/// 한 프레임 동안 그릴 항목들을 모으는 큐
+#[derive(Default, Debug)]
+pub struct RenderQueue {
+    items: Vec<RenderItem>,
+}
+
+impl RenderQueue {
+    pub fn new() -> RenderQueue {
+        RenderQueue { items: vec![] }
+    }
+
+    /// 그리기 명령을 추가한다.
+    pub fn push(&mut self, z: i32, depth: f64, command: RenderCommand) {
+        self.items.push(RenderItem { z, depth, command });
+    }
+
+    /// z, depth 순서로 정렬한다.
+    /// 같은 값이라면 먼저 넣은 것이 먼저 그려진다.
+    pub fn sort(&mut self) {
+        self.items.sort_by(|a, b| {
+            a.z.cmp(&b.z).then(
+                a.depth
+                    .partial_cmp(&b.depth)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            )
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, RenderItem> {
+        self.items.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort_by_z_then_depth() {
+        let tree = RenderCommand::Tile {
+            texture: 0,
+            src: Rect::new(0, 0, 32, 32),
+            dest: Rect::new(0, 0, 32, 32),
+            flip: TileFlip::default(),
+        };
+        let player = RenderCommand::Entity(Uuid::new_v4());
+        let bird = RenderCommand::Entity(Uuid::new_v4());
+
+        let mut queue = RenderQueue::new();
+        queue.push(1, 0.0, bird);
+        queue.push(0, 64.0, tree);
+        queue.push(0, 48.0, player);
+        queue.sort();
+
+        let commands: Vec<RenderCommand> = queue.iter().map(|item| item.command).collect();
+
+        // 나무 뒤(위쪽)에 선 캐릭터는 나무보다 먼저 그려지고
+        // z 가 큰 새는 항상 마지막에 그려진다.
+        assert_eq!(commands, vec![player, tree, bird]);
+    }
+
+    #[test]
+    fn keep_insert_order_on_same_depth() {
+        let first = RenderCommand::Entity(Uuid::new_v4());
+        let second = RenderCommand::Entity(Uuid::new_v4());
+
+        let mut queue = RenderQueue::new();
+        queue.push(0, 10.0, first);
+        queue.push(0, 10.0, second);
+        queue.sort();
+
+        let commands: Vec<RenderCommand> = queue.iter().map(|item| item.command).collect();
+        assert_eq!(commands, vec![first, second]);
+    }
+}
@@ -0,0 +1,356 @@
+//! 사용자 설정 파일
+//! 키 설정, 화면 모드, 음량을 RON 으로 저장한다.
+//! 파일은 사용자 설정 디렉토리의 sdl_isometric/settings.ron 에 둔다.
+
+use crate::constant::*;
+use crate::input::action::{ActionMap, AxisBinding, Binding};
+use crate::viewport::ScaleMode;
+
+use sdl2::controller::{Axis, Button};
+use sdl2::keyboard::Scancode;
+use sdl2::mouse::MouseButton;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+lazy_static! {
+    /// 실행 중인 설정. main 에서 읽어 들이고 설정 화면에서 고친다.
+    pub static ref G_SETTINGS: RwLock<Settings> = RwLock::new(Settings::default());
+}
+
+/// G_SETTINGS 를 설정 파일에 저장해도 되는가?
+/// 녹화한 입력을 재생할 때는 녹화할 때의 설정이므로 저장하지 않는다.
+static PERSISTENT: AtomicBool = AtomicBool::new(true);
+
+pub fn set_persistent(persistent: bool) {
+    PERSISTENT.store(persistent, Ordering::SeqCst);
+}
+
+pub fn is_persistent() -> bool {
+    PERSISTENT.load(Ordering::SeqCst)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+    pub scale_mode: ScaleMode,
+    pub vsync: bool,
+    pub fps_cap: u32, // 초당 최대 frame 수. 0 이면 제한하지 않는다.
+}
+
+impl Default for DisplaySettings {
+    fn default() -> DisplaySettings {
+        DisplaySettings {
+            width: SCREEN_WIDTH,
+            height: SCREEN_HEIGHT,
+            fullscreen: false,
+            scale_mode: ScaleMode::Letterbox,
+            vsync: true,
+            fps_cap: 0,
+        }
+    }
+}
+
+/// 음량 (0.0 ~ 1.0)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master: f64,
+    pub music: f64,
+    pub effect: f64,
+}
+
+impl Default for AudioSettings {
+    fn default() -> AudioSettings {
+        AudioSettings {
+            master: 1.0,
+            music: 1.0,
+            effect: 1.0,
+        }
+    }
+}
+
+/// 설정 파일에 적는 Binding
+/// 키와 버튼은 SDL 의 이름으로 적는다. (예: Key("Left Ctrl"), Button("dpup"))
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BindingConfig {
+    Key(String),
+    Mouse(String),
+    Button(String),
+}
+
+/// 설정 파일에 적는 AxisBinding
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AxisConfig {
+    Keys(String, String),
+    Buttons(String, String),
+    Gamepad(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub display: DisplaySettings,
+    pub audio: AudioSettings,
+    pub controls: BTreeMap<String, Vec<BindingConfig>>,
+    pub axes: BTreeMap<String, Vec<AxisConfig>>,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        let mut settings = Settings {
+            display: DisplaySettings::default(),
+            audio: AudioSettings::default(),
+            controls: BTreeMap::new(),
+            axes: BTreeMap::new(),
+        };
+        settings.set_action_map(&ActionMap::default_map());
+
+        settings
+    }
+}
+
+impl Settings {
+    /// 설정 파일을 읽는다. 없거나 읽을 수 없다면 기본값을 쓴다.
+    pub fn load() -> Settings {
+        let path = config_path();
+
+        if !path.exists() {
+            return Settings::default();
+        }
+
+        match Settings::load_from(&path) {
+            Ok(settings) => settings,
+            Err(e) => {
+                println!("failed to load {} : {}", path.display(), e);
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        self.save_to(&config_path())
+    }
+
+    pub fn load_from(path: &Path) -> Result<Settings, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Settings::from_ron(&text)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, self.to_ron()?).map_err(|e| e.to_string())
+    }
+
+    pub fn from_ron(text: &str) -> Result<Settings, String> {
+        ron::from_str(text).map_err(|e| e.to_string())
+    }
+
+    pub fn to_ron(&self) -> Result<String, String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())
+    }
+
+    /// 저장된 키 설정으로 ActionMap 을 만든다.
+    /// 알 수 없는 이름은 건너뛴다.
+    pub fn action_map(&self) -> ActionMap {
+        let mut map = ActionMap::new();
+
+        for (action, bindings) in self.controls.iter() {
+            for binding in bindings.iter().filter_map(BindingConfig::to_binding) {
+                map.bind(action, binding);
+            }
+        }
+        for (axis, bindings) in self.axes.iter() {
+            for binding in bindings.iter().filter_map(AxisConfig::to_binding) {
+                map.bind_axis(axis, binding);
+            }
+        }
+
+        // 설정 파일에 없는 행동(새로 생긴 행동)은 기본 입력을 쓴다.
+        let defaults = ActionMap::default_map();
+        for (action, bindings) in defaults.iter() {
+            if !self.controls.contains_key(action) {
+                for binding in bindings.iter() {
+                    map.bind(action, *binding);
+                }
+            }
+        }
+
+        map
+    }
+
+    pub fn set_action_map(&mut self, map: &ActionMap) {
+        self.controls = map
+            .iter()
+            .map(|(action, bindings)| {
+                (
+                    action.clone(),
+                    bindings.iter().map(BindingConfig::from_binding).collect(),
+                )
+            })
+            .collect();
+        self.axes = map
+            .iter_axes()
+            .map(|(axis, bindings)| {
+                (
+                    axis.clone(),
+                    bindings.iter().map(AxisConfig::from_binding).collect(),
+                )
+            })
+            .collect();
+    }
+
+    /// 음량을 mixer 에 적용한다.
+    pub fn apply_audio(&self) {
+        let volume = |v: f64| (v.clamp(0.0, 1.0) * sdl2::mixer::MAX_VOLUME as f64) as i32;
+
+        sdl2::mixer::Music::set_volume(volume(self.audio.master * self.audio.music));
+        sdl2::mixer::Channel::all().set_volume(volume(self.audio.master * self.audio.effect));
+    }
+}
+
+impl BindingConfig {
+    pub fn from_binding(binding: &Binding) -> BindingConfig {
+        match *binding {
+            Binding::Key(key) => BindingConfig::Key(key.name().to_owned()),
+            Binding::Mouse(button) => BindingConfig::Mouse(mouse_name(button).to_owned()),
+            Binding::Button(button) => BindingConfig::Button(button.string()),
+        }
+    }
+
+    pub fn to_binding(&self) -> Option<Binding> {
+        match self {
+            BindingConfig::Key(name) => Scancode::from_name(name).map(Binding::Key),
+            BindingConfig::Mouse(name) => mouse_from_name(name).map(Binding::Mouse),
+            BindingConfig::Button(name) => Button::from_string(name).map(Binding::Button),
+        }
+    }
+}
+
+impl AxisConfig {
+    pub fn from_binding(binding: &AxisBinding) -> AxisConfig {
+        match *binding {
+            AxisBinding::Keys(negative, positive) => {
+                AxisConfig::Keys(negative.name().to_owned(), positive.name().to_owned())
+            }
+            AxisBinding::Buttons(negative, positive) => {
+                AxisConfig::Buttons(negative.string(), positive.string())
+            }
+            AxisBinding::Gamepad(axis) => AxisConfig::Gamepad(axis.string()),
+        }
+    }
+
+    pub fn to_binding(&self) -> Option<AxisBinding> {
+        match self {
+            AxisConfig::Keys(negative, positive) => Some(AxisBinding::Keys(
+                Scancode::from_name(negative)?,
+                Scancode::from_name(positive)?,
+            )),
+            AxisConfig::Buttons(negative, positive) => Some(AxisBinding::Buttons(
+                Button::from_string(negative)?,
+                Button::from_string(positive)?,
+            )),
+            AxisConfig::Gamepad(name) => Axis::from_string(name).map(AxisBinding::Gamepad),
+        }
+    }
+}
+
+fn mouse_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Middle => "middle",
+        MouseButton::Right => "right",
+        MouseButton::X1 => "x1",
+        MouseButton::X2 => "x2",
+        MouseButton::Unknown => "unknown",
+    }
+}
+
+fn mouse_from_name(name: &str) -> Option<MouseButton> {
+    match name {
+        "left" => Some(MouseButton::Left),
+        "middle" => Some(MouseButton::Middle),
+        "right" => Some(MouseButton::Right),
+        "x1" => Some(MouseButton::X1),
+        "x2" => Some(MouseButton::X2),
+        _ => None,
+    }
+}
+
+/// 설정 파일 위치
+/// XDG_CONFIG_HOME, APPDATA, HOME/.config 순서로 찾고 모두 없으면 현재 디렉토리에 둔다.
+pub fn config_path() -> PathBuf {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+    match dir {
+        Some(dir) => dir.join("sdl_isometric").join("settings.ron"),
+        None => PathBuf::from("settings.ron"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::action;
+
+    #[test]
+    fn ron_round_trip() {
+        let mut settings = Settings::default();
+        settings.display.fullscreen = true;
+        settings.display.fps_cap = 144;
+        settings.audio.music = 0.5;
+
+        let text = settings.to_ron().unwrap();
+        assert_eq!(Settings::from_ron(&text).unwrap(), settings);
+
+        // 빠진 항목은 기본값을 쓴다.
+        let partial = Settings::from_ron("(audio: (effect: 0.25))").unwrap();
+        assert_eq!(partial.audio.effect, 0.25);
+        assert_eq!(partial.audio.master, 1.0);
+        assert_eq!(partial.display, DisplaySettings::default());
+    }
+
+    #[test]
+    fn action_map_round_trip() {
+        let mut map = ActionMap::default_map();
+        map.set_key(action::ATTACK, Scancode::LCtrl);
+
+        let mut settings = Settings::default();
+        settings.set_action_map(&map);
+
+        assert_eq!(
+            settings.controls[action::ATTACK][0],
+            BindingConfig::Key("Left Ctrl".to_owned())
+        );
+
+        let loaded = settings.action_map();
+        for (name, bindings) in map.iter() {
+            assert_eq!(loaded.bindings(name), &bindings[..]);
+        }
+        for (name, bindings) in map.iter_axes() {
+            assert_eq!(loaded.axis_bindings(name), &bindings[..]);
+        }
+
+        // 예전 설정 파일에 없는 행동은 기본 입력을 받는다.
+        settings.controls.remove(action::PAUSE);
+        assert_eq!(
+            settings.action_map().bindings(action::PAUSE),
+            ActionMap::default_map().bindings(action::PAUSE)
+        );
+    }
+}
@@ -1,29 +1,59 @@
+use crate::camera::Camera;
 use crate::constant::*;
 use crate::entities::*;
+use crate::input::action::{self, ActionMap};
+use crate::input::Input;
 use crate::map::*;
+use crate::render_queue::*;
+use crate::settings::G_SETTINGS;
 use crate::states::*;
 
 use std::collections::HashMap;
-use std::collections::HashSet;
 
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Scancode};
 use sdl2::mixer::Chunk;
 use sdl2::mixer::Music;
+use sdl2::mouse::MouseButton;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::TextureCreator;
+use sdl2::video::WindowContext;
 
 use uuid::Uuid;
 
+/// 카메라 이동 속도 (초당 픽셀)
+const CAMERA_SPEED: f64 = 400.0;
+
+/// spawn 을 지울 때 마우스로부터의 거리
+const SPAWN_PICK_RADIUS: f64 = 12.0;
+
 /// 빌드 게임용 State
+/// 지도 타일을 찍고 지우는 편집기이다.
+///
+/// * 마우스 왼쪽 : 타일 찍기, 오른쪽 : 지우기, 가운데 : 타일 고르기
+/// * 방향키 / WASD : 카메라 이동, 마우스 휠 : 확대 / 축소
+/// * Tab : 타일 팔레트 열기, PageUp / PageDown : 타일셋 바꾸기
+/// * 1 ~ 9 : 편집할 레이어 고르기, C : collision 레이어 편집
+/// * P / M : 마우스 위치에 player / mob spawn 추가, X : spawn 지우기
+/// * F5 또는 Ctrl + S : 저장, Escape : 나가기
 pub struct BuilderState<'a> {
     texture_manager: TextureManager<'a>,
     entities: HashMap<Uuid, Entity>,
     music: Option<Music<'a>>,
     chunks: HashMap<String, Chunk>,
     map: Option<Map<'a>>,
+    map_path: &'static str, // ASSET_DIR 기준의 지도 파일
     state_result: StateResult,
-    keyboards: HashSet<sdl2::keyboard::Keycode>,
-    cx: i32, // 카메라 x좌표
-    cy: i32, // 카메라 Y좌표
-    cw: u32, // 카메라 폭
-    ch: u32, // 카메라 높이
+    input: Input,
+    actions: ActionMap,
+    palette_click: bool, // 팔레트를 닫은 클릭은 떼기 전까지 타일을 찍지 않는다.
+    camera: Camera,
+    mouse: (i32, i32), // 마우스의 가상 화면 좌표
+    gid: u32,          // 찍을 타일
+    atlas_idx: usize,  // 팔레트에 보일 타일셋 (지도 텍스쳐 index)
+    layer_idx: usize,  // 편집할 레이어
+    show_palette: bool,
+    show_collision: bool, // collision 레이어를 보이고 편집한다.
 }
 
 impl<'a> BuilderState<'a> {
@@ -36,41 +66,509 @@ impl<'a> BuilderState<'a> {
             entities,
             state_result: StateResult::Default,
             map: None,
+            map_path: "",
             music: None,
             chunks: HashMap::new(),
-            keyboards: HashSet::new(),
-            cx: 0,
-            cy: 0,
-            cw: VIRTUAL_WIDTH,
-            ch: VIRTUAL_HEIGHT,
+            input: Input::default(),
+            actions: G_SETTINGS.read().unwrap().action_map(),
+            palette_click: false,
+            camera: Camera::new(VIRTUAL_WIDTH, VIRTUAL_HEIGHT),
+            mouse: (0, 0),
+            gid: 1,
+            atlas_idx: 0,
+            layer_idx: 0,
+            show_palette: false,
+            show_collision: false,
+        }
+    }
+
+    pub fn init(
+        &mut self,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        _font_context: &'a sdl2::ttf::Sdl2TtfContext,
+    ) {
+        self.map_path = "tiled_base64_zlib.tmx";
+        let map = Map::new("map".to_owned(), texture_creator, self.map_path);
+
+        // 가장 앞의 타일셋에서 시작한다.
+        if let Some((idx, atlas)) = map.tile_atlases.iter().min_by_key(|(idx, _)| **idx) {
+            self.atlas_idx = *idx;
+            self.gid = atlas.first_gid;
+        }
+
+        // 카메라는 지도 밖으로 화면 절반까지 나갈 수 있다.
+        let world = map.world_bounds();
+        let (half_w, half_h) = (self.camera.w as f64 / 2.0, self.camera.h as f64 / 2.0);
+        self.camera.set_bounds(Rectangle::new(
+            world.x - half_w,
+            world.y - half_h,
+            world.w + half_w * 2.0,
+            world.h + half_h * 2.0,
+        ));
+
+        self.map = Some(map);
+    }
+
+    /// 마우스가 가리키는 월드 좌표
+    fn mouse_world(&self) -> (i32, i32) {
+        let (world_x, world_y) = self.camera.screen_to_world(self.mouse);
+        (world_x as i32, world_y as i32)
+    }
+
+    /// 마우스가 가리키는 타일. 지도가 없거나 지도 밖이라면 None 이다.
+    fn hovered_tile(&self) -> Option<(i32, i32)> {
+        let map = self.map.as_ref()?;
+        let (world_x, world_y) = self.mouse_world();
+        let (tile_x, tile_y) = map
+            .projection
+            .world_to_tile((world_x - map.x) as f64, (world_y - map.y) as f64);
+
+        if map.contains_tile(tile_x, tile_y) {
+            Some((tile_x, tile_y))
+        } else {
+            None
+        }
+    }
+
+    /// 타일을 찍을 레이어
+    /// collision 레이어를 편집 중이라면 collision 레이어를 (없다면 만들어서) 쓴다.
+    fn target_layer(&mut self) -> Option<usize> {
+        let map = self.map.as_mut()?;
+
+        if self.show_collision {
+            return Some(match map.layer_index("collision") {
+                Some(idx) => idx,
+                None => map.add_layer("collision"),
+            });
+        }
+
+        if map.layers.is_empty() {
+            map.add_layer("ground");
+        }
+        self.layer_idx = self.layer_idx.min(map.layers.len() - 1);
+
+        Some(self.layer_idx)
+    }
+
+    /// 마우스 아래의 타일에 gid 를 찍는다. gid 가 0 이면 지운다.
+    fn paint(&mut self, gid: u32) {
+        let (tile_x, tile_y) = match self.hovered_tile() {
+            Some(tile) => tile,
+            None => return,
+        };
+
+        if let Some(layer_idx) = self.target_layer() {
+            let map = self.map.as_mut().unwrap();
+            map.set_tile(
+                layer_idx,
+                tile_x,
+                tile_y,
+                tiled::LayerTile {
+                    gid,
+                    flip_h: false,
+                    flip_v: false,
+                    flip_d: false,
+                },
+            );
+        }
+    }
+
+    /// 마우스 아래의 타일을 찍을 타일로 고른다.
+    fn pick(&mut self) {
+        let (tile_x, tile_y) = match self.hovered_tile() {
+            Some(tile) => tile,
+            None => return,
+        };
+
+        if let Some(layer_idx) = self.target_layer() {
+            let map = self.map.as_ref().unwrap();
+            let gid = map.get_tile(layer_idx, tile_x, tile_y).gid;
+            if gid != 0 {
+                self.select_gid(gid);
+            }
+        }
+    }
+
+    /// 찍을 타일을 바꾸고, 그 타일이 속한 타일셋을 팔레트에 보인다.
+    fn select_gid(&mut self, gid: u32) {
+        self.gid = gid;
+
+        if let Some(source) = self.map.as_ref().and_then(|map| map.source(gid)) {
+            self.atlas_idx = source.texture;
+        }
+    }
+
+    /// 팔레트의 다음(또는 이전) 타일셋
+    fn cycle_atlas(&mut self, forward: bool) {
+        let map = match self.map.as_ref() {
+            Some(map) => map,
+            None => return,
+        };
+
+        let mut indices: Vec<usize> = map.tile_atlases.keys().copied().collect();
+        if indices.is_empty() {
+            return;
+        }
+        indices.sort_unstable();
+
+        let pos = indices
+            .iter()
+            .position(|idx| *idx == self.atlas_idx)
+            .unwrap_or(0);
+        let next = if forward {
+            (pos + 1) % indices.len()
+        } else {
+            (pos + indices.len() - 1) % indices.len()
+        };
+
+        self.atlas_idx = indices[next];
+        self.gid = map.tile_atlases[&self.atlas_idx].first_gid;
+    }
+
+    /// 팔레트가 화면에 차지할 영역
+    /// 타일셋 이미지가 화면보다 크다면 화면에 맞게 줄인다.
+    fn palette_rect(&self) -> Option<Rect> {
+        let atlas = self.map.as_ref()?.tile_atlases.get(&self.atlas_idx)?;
+
+        let scale = (self.camera.w as f64 / atlas.texture_width as f64)
+            .min(self.camera.h as f64 / atlas.texture_height as f64)
+            .min(1.0);
+
+        Some(Rect::new(
+            0,
+            0,
+            (atlas.texture_width as f64 * scale) as u32,
+            (atlas.texture_height as f64 * scale) as u32,
+        ))
+    }
+
+    /// 팔레트 위의 화면 좌표가 가리키는 타일의 gid
+    fn palette_gid(&self, x: i32, y: i32) -> Option<u32> {
+        let palette = self.palette_rect()?;
+        if !palette.contains_point(Point::new(x, y)) {
+            return None;
+        }
+
+        let atlas = &self.map.as_ref()?.tile_atlases[&self.atlas_idx];
+        let u = (x * atlas.texture_width as i32 / palette.width() as i32) as u32;
+        let v = (y * atlas.texture_height as i32 / palette.height() as i32) as u32;
+
+        atlas
+            .atlas
+            .iter()
+            .position(|(left, top, right, bottom)| {
+                *left <= u && u < *right && *top <= v && v < *bottom
+            })
+            .map(|idx| atlas.first_gid + idx as u32)
+    }
+
+    /// 마우스 위치에 spawn 을 추가한다.
+    fn place_spawn(&mut self, object_type: &str) {
+        let (world_x, world_y) = self.mouse_world();
+        if let Some(map) = self.map.as_mut() {
+            map.add_spawn(object_type, world_x as f64, world_y as f64);
+        }
+    }
+
+    fn save(&self) {
+        if let Some(map) = &self.map {
+            match map.save(self.map_path) {
+                Ok(()) => println!("saved {}{}", ASSET_DIR, self.map_path),
+                Err(e) => println!("failed to save {}{} : {}", ASSET_DIR, self.map_path, e),
+            }
+        }
+    }
+
+    /// 키보드로 카메라를 움직인다.
+    fn update_camera(&mut self, dt: f64) {
+        let dx = self.actions.axis(&self.input, action::MOVE_X);
+        // Ctrl + S 는 저장이므로 Ctrl 이 눌려 있을 때는 위아래로 움직이지 않는다.
+        let dy = if self.ctrl_pressed() {
+            0.0
+        } else {
+            self.actions.axis(&self.input, action::MOVE_Y)
+        };
+
+        // 확대해도 화면에서는 같은 빠르기로 움직이도록 한다.
+        self.camera.x += dx * CAMERA_SPEED * dt / self.camera.zoom;
+        self.camera.y += dy * CAMERA_SPEED * dt / self.camera.zoom;
+        self.camera.clamp();
+    }
+
+    fn ctrl_pressed(&self) -> bool {
+        self.input.is_key_held(Scancode::LCtrl) || self.input.is_key_held(Scancode::RCtrl)
+    }
+
+    /// 타일 하나의 바닥 외곽선을 화면 좌표로 구한다.
+    fn tile_outline(map: &Map, tile_x: i32, tile_y: i32, camera: &Camera) -> Vec<Point> {
+        let (world_x, world_y) = map.projection.tile_to_world(tile_x, tile_y);
+        let left = world_x + map.x as f64;
+        let top = world_y + map.y as f64;
+        let w = map.tile_width as f64;
+        let h = map.tile_height as f64;
+
+        let corners = match map.projection {
+            Projection::Orthogonal { .. } => [
+                (left, top),
+                (left + w, top),
+                (left + w, top + h),
+                (left, top + h),
+                (left, top),
+            ],
+            _ => [
+                (left + w / 2.0, top),
+                (left + w, top + h / 2.0),
+                (left + w / 2.0, top + h),
+                (left, top + h / 2.0),
+                (left + w / 2.0, top),
+            ],
+        };
+
+        corners
+            .iter()
+            .map(|corner| camera.world_to_screen(*corner).into())
+            .collect()
+    }
+
+    /// 막힌 영역을 그린다.
+    fn render_collision(&self, canvas: &mut WindowCanvas, map: &Map) {
+        if let Some(idx) = map.layer_index("collision") {
+            map.render_layer(canvas, idx, &self.camera);
+        }
+
+        canvas.set_draw_color(Color::RGBA(255, 0, 0, 255));
+        for block in map.blocks.iter() {
+            canvas
+                .draw_rect(self.camera.world_rect_to_screen(*block))
+                .unwrap();
+        }
+
+        canvas.set_draw_color(Color::RGBA(255, 128, 0, 255));
+        for shape in map.shapes.iter() {
+            let mut points: Vec<Point> = shape
+                .to_polygon()
+                .iter()
+                .map(|point| self.camera.world_to_screen(*point).into())
+                .collect();
+            if let Some(first) = points.first().copied() {
+                points.push(first);
+            }
+            canvas.draw_lines(&points[..]).unwrap();
+        }
+    }
+
+    /// spawn 위치를 표시한다.
+    /// player 는 초록색, mob 은 빨간색, 나머지는 노란색이다.
+    fn render_spawns(&self, canvas: &mut WindowCanvas, map: &Map) {
+        for spawn in map.spawns.iter() {
+            let color = match spawn.entity_type() {
+                Some(EntityType::PLAYER) => Color::RGBA(0, 255, 0, 255),
+                Some(EntityType::MOB) => Color::RGBA(255, 0, 0, 255),
+                _ => Color::RGBA(255, 255, 0, 255),
+            };
+            let (x, y) = spawn.center();
+
+            canvas.set_draw_color(color);
+            canvas
+                .fill_rect(Rect::from_center(
+                    Point::from(self.camera.world_to_screen((x, y))),
+                    8,
+                    8,
+                ))
+                .unwrap();
+        }
+    }
+
+    /// 타일셋 이미지와 고른 타일을 표시한다.
+    fn render_palette(&self, canvas: &mut WindowCanvas, map: &Map) {
+        let palette = match self.palette_rect() {
+            Some(palette) => palette,
+            None => return,
+        };
+
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 255));
+        canvas.fill_rect(palette).unwrap();
+        if let Some(texture) = map.textures.get(&self.atlas_idx) {
+            canvas.copy(texture, None, palette).unwrap();
+        }
+
+        let atlas = &map.tile_atlases[&self.atlas_idx];
+        if self.gid >= atlas.first_gid && self.gid < atlas.first_gid + atlas.atlas.len() as u32 {
+            let src = atlas.get_tile_rect(self.gid);
+            let scale = palette.width() as f64 / atlas.texture_width as f64;
+
+            canvas.set_draw_color(Color::RGBA(255, 255, 0, 255));
+            canvas
+                .draw_rect(Rect::new(
+                    (src.x as f64 * scale) as i32,
+                    (src.y as f64 * scale) as i32,
+                    (src.width() as f64 * scale).max(1.0) as u32,
+                    (src.height() as f64 * scale).max(1.0) as u32,
+                ))
+                .unwrap();
         }
     }
 }
 
 impl<'a> States for BuilderState<'a> {
-    fn process_event(&mut self, _event: &sdl2::event::Event, _dt: f64) -> StateResult {
+    fn process_event(&mut self, event: &sdl2::event::Event, _dt: f64) -> StateResult {
+        self.input.process_event(event);
+
+        if let Event::KeyDown {
+            keycode: Some(k), ..
+        } = event
+        {
+            match *k {
+                Keycode::Tab => self.show_palette = !self.show_palette,
+                Keycode::PageUp => self.cycle_atlas(false),
+                Keycode::PageDown => self.cycle_atlas(true),
+                Keycode::C => self.show_collision = !self.show_collision,
+                Keycode::P => self.place_spawn("player"),
+                Keycode::M => self.place_spawn("mob"),
+                Keycode::X => {
+                    let (world_x, world_y) = self.mouse_world();
+                    if let Some(map) = self.map.as_mut() {
+                        map.remove_spawn_at(world_x as f64, world_y as f64, SPAWN_PICK_RADIUS);
+                    }
+                }
+                Keycode::F5 => self.save(),
+                Keycode::S if self.ctrl_pressed() => self.save(),
+                _ => {
+                    // 숫자키로 편집할 레이어를 고른다.
+                    let layer_keys = [
+                        Keycode::Num1,
+                        Keycode::Num2,
+                        Keycode::Num3,
+                        Keycode::Num4,
+                        Keycode::Num5,
+                        Keycode::Num6,
+                        Keycode::Num7,
+                        Keycode::Num8,
+                        Keycode::Num9,
+                    ];
+                    if let Some(idx) = layer_keys.iter().position(|key| key == k) {
+                        if self.map.as_ref().is_some_and(|map| idx < map.layers.len()) {
+                            self.layer_idx = idx;
+                            self.show_collision = false;
+                        }
+                    }
+                }
+            }
+        }
+
         StateResult::Default
     }
 
-    fn process_mouse(
-        &mut self,
-        _x: i32,
-        _y: i32,
-        _new_buttons: &HashSet<sdl2::mouse::MouseButton>,
-        _old_buttons: &HashSet<sdl2::mouse::MouseButton>,
-        _dt: f64,
-    ) {
+    fn process_mouse(&mut self, mouse: &Input, _dt: f64) {
+        self.mouse = mouse.mouse_virtual();
+
+        // 휠로 확대, 축소한다.
+        let (_, wheel) = mouse.wheel();
+        if wheel != 0 {
+            self.camera.set_zoom(self.camera.zoom * 1.1f64.powi(wheel));
+        }
+
+        if !mouse.is_mouse_held(MouseButton::Left) {
+            self.palette_click = false;
+        }
+
+        // 팔레트가 열려있다면 팔레트에서 타일을 고른다.
+        if self.show_palette {
+            if mouse.was_mouse_pressed(MouseButton::Left) {
+                if let Some(gid) = self.palette_gid(self.mouse.0, self.mouse.1) {
+                    self.select_gid(gid);
+                    self.show_palette = false;
+                }
+                // 팔레트를 닫는 클릭으로 타일이 찍히지 않게 한다.
+                self.palette_click = true;
+            }
+            return;
+        }
+
+        // 버튼을 누르고 있는 동안 계속 찍는다.
+        if mouse.is_mouse_held(MouseButton::Left) && !self.palette_click {
+            self.paint(self.gid);
+        } else if mouse.is_mouse_held(MouseButton::Right) {
+            self.paint(0);
+        }
+
+        if mouse.was_mouse_pressed(MouseButton::Middle) {
+            self.pick();
+        }
     }
 
-    fn update(&mut self, _dt: f64) -> StateResult {
+    fn update(&mut self, dt: f64) -> StateResult {
+        if self.actions.pressed(&self.input, action::CANCEL) {
+            self.state_result = StateResult::Pop;
+        }
+
+        self.update_camera(dt);
+
+        if let Some(map) = self.map.as_mut() {
+            map.update(dt);
+        }
+
+        self.input.begin_new_frame();
+
         StateResult::Default
     }
 
-    fn render(&self, _canvas: &mut WindowCanvas) -> StateResult {
+    fn render(&self, canvas: &mut WindowCanvas) -> StateResult {
+        let map = match &self.map {
+            Some(map) => map,
+            None => return StateResult::Default,
+        };
+
+        map.render(canvas, &self.camera);
+
+        // 편집기에서는 entity 가 없으므로 깊이 정렬할 타일을 순서대로 그린다.
+        let mut queue = RenderQueue::new();
+        map.queue_tiles(&mut queue, &self.camera);
+        queue.sort();
+        for item in queue.iter() {
+            if let RenderCommand::Tile {
+                texture,
+                src,
+                dest,
+                flip,
+            } = item.command
+            {
+                map.render_tile(canvas, texture, src, dest, flip, self.camera.rotation);
+            }
+        }
+
+        if self.show_collision {
+            self.render_collision(canvas, map);
+        }
+
+        self.render_spawns(canvas, map);
+
+        if let Some((tile_x, tile_y)) = self.hovered_tile() {
+            let outline = BuilderState::tile_outline(map, tile_x, tile_y, &self.camera);
+            canvas.set_draw_color(if self.show_collision {
+                Color::RGBA(255, 0, 0, 255)
+            } else {
+                Color::RGBA(255, 255, 255, 255)
+            });
+            canvas.draw_lines(&outline[..]).unwrap();
+        }
+
+        if self.show_palette {
+            self.render_palette(canvas, map);
+        }
+
         StateResult::Default
     }
 
     fn next_result(&mut self) -> StateResult {
-        StateResult::Default
+        let result = self.state_result;
+        self.state_result = StateResult::Default;
+
+        result
+    }
+
+    fn resume(&mut self) {
+        // 위의 state 가 있는 동안 뗀 키는 알 수 없으므로 모두 뗀 것으로 한다.
+        self.input.release_all();
     }
 }
@@ -0,0 +1,279 @@
+use crate::gui::*;
+use crate::input::action::{self, ActionMap, Binding};
+use crate::input::Input;
+use crate::settings::*;
+use crate::states::*;
+
+use uuid::Uuid;
+
+use sdl2::event::Event;
+use sdl2::keyboard::Scancode;
+use sdl2::ttf::Font;
+
+use std::collections::HashMap;
+
+use std::path::Path;
+
+use sdl2::render::WindowCanvas;
+use sdl2::video::WindowContext;
+
+/// 첫 행동 줄의 y 좌표
+const ROW_TOP: i32 = 80;
+/// 행동 줄 사이의 간격
+const ROW_HEIGHT: i32 = 40;
+
+/// 키 설정 화면
+/// 행동 옆의 버튼을 누르면 다음에 누르는 키를 그 행동에 묶는다.
+/// 나갈 때 설정 파일에 저장한다.
+pub struct ControlsState<'a> {
+    texture_creator: Option<&'a TextureCreator<WindowContext>>,
+    font: Option<Font<'a, 'static>>,
+    texture_manager: Option<TextureManager<'a>>,
+    buttons: HashMap<String, GuiElement>,
+    labels: HashMap<String, GuiElement>,
+    input: Input,
+    actions: ActionMap,
+    waiting: Option<&'static str>, // 키를 기다리는 행동
+    state_result: StateResult,
+}
+
+impl<'a> Default for ControlsState<'a> {
+    fn default() -> ControlsState<'a> {
+        ControlsState::new()
+    }
+}
+
+impl<'a> ControlsState<'a> {
+    pub fn new() -> ControlsState<'a> {
+        ControlsState {
+            texture_creator: None,
+            font: None,
+            texture_manager: None,
+            buttons: HashMap::new(),
+            labels: HashMap::new(),
+            input: Input::default(),
+            actions: G_SETTINGS.read().unwrap().action_map(),
+            waiting: None,
+            state_result: StateResult::Default,
+        }
+    }
+
+    pub fn init(
+        &mut self,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        font_context: &'a sdl2::ttf::Sdl2TtfContext,
+    ) {
+        self.texture_creator = Some(texture_creator);
+        self.font = Some(
+            font_context
+                .load_font(Path::new("resources/hackr.ttf"), 24)
+                .unwrap(),
+        );
+
+        let mut texture_manager = TextureManager::new();
+        texture_manager.load_texture(
+            "normal_button".to_string(),
+            texture_creator,
+            Path::new("resources/btn_normal.png"),
+        );
+        texture_manager.load_texture(
+            "hover_button".to_string(),
+            texture_creator,
+            Path::new("resources/btn_hover.png"),
+        );
+        self.texture_manager = Some(texture_manager);
+
+        self.set_label(
+            "title".to_owned(),
+            "Controls (click to rebind, Esc to go back)".to_owned(),
+            0,
+            0,
+        );
+
+        for (i, name) in action::ACTIONS.iter().enumerate() {
+            let button = GuiElement::new(
+                Uuid::new_v4(),
+                ("normal_button".to_string(), "normal_button".to_string()),
+                ("hover_button".to_string(), "hover_button".to_string()),
+                40,
+                ROW_TOP + ROW_HEIGHT * i as i32,
+                32,
+                32,
+            );
+            self.buttons.insert(name.to_string(), button);
+            self.refresh_label(name);
+        }
+    }
+
+    /// 글자를 텍스쳐로 만들어 (x, y) 에 보이게 한다.
+    fn set_label(&mut self, name: String, text: String, x: i32, y: i32) {
+        let font = self.font.as_ref().unwrap();
+        let surface = font
+            .render(&text)
+            .blended(Color::RGBA(255, 255, 255, 255))
+            .unwrap();
+        let texture = self
+            .texture_creator
+            .unwrap()
+            .create_texture_from_surface(&surface)
+            .unwrap();
+
+        let texture_id = format!("label_{}", name);
+        self.texture_manager
+            .as_mut()
+            .unwrap()
+            .add_texture(texture_id.clone(), texture);
+
+        let label = GuiElement::new(
+            Uuid::new_v4(),
+            (texture_id.clone(), name.clone()),
+            (texture_id, name.clone()),
+            x,
+            y,
+            surface.width(),
+            surface.height(),
+        );
+        self.labels.insert(name, label);
+    }
+
+    /// 행동에 묶인 키를 다시 적는다.
+    fn refresh_label(&mut self, name: &'static str) {
+        let text = if self.waiting == Some(name) {
+            format!("{} : press a key...", name)
+        } else {
+            let keys: Vec<&str> = self
+                .actions
+                .bindings(name)
+                .iter()
+                .filter_map(|binding| match binding {
+                    Binding::Key(key) => Some(key.name()),
+                    _ => None,
+                })
+                .collect();
+            format!("{} : {}", name, keys.join(", "))
+        };
+
+        let y =
+            ROW_TOP + ROW_HEIGHT * action::ACTIONS.iter().position(|a| *a == name).unwrap() as i32;
+        self.set_label(name.to_owned(), text, 90, y);
+    }
+
+    /// 바꾼 키 설정을 저장하고 나간다.
+    /// 재생 중이라면 설정 파일에는 쓰지 않는다.
+    fn leave(&mut self) {
+        let mut settings = G_SETTINGS.write().unwrap();
+        settings.set_action_map(&self.actions);
+        if is_persistent() {
+            if let Err(e) = settings.save() {
+                println!("failed to save {} : {}", config_path().display(), e);
+            }
+        }
+
+        self.state_result = StateResult::Pop;
+    }
+}
+
+impl<'a> States for ControlsState<'a> {
+    fn process_event(&mut self, event: &sdl2::event::Event, _dt: f64) -> StateResult {
+        // 키를 기다리는 중이라면 다음 키를 잡아 묶는다. Escape 는 취소이다.
+        if let Some(name) = self.waiting {
+            if let Event::KeyDown {
+                scancode: Some(scancode),
+                repeat: false,
+                ..
+            } = event
+            {
+                if *scancode != Scancode::Escape {
+                    self.actions.set_key(name, *scancode);
+                }
+                self.waiting = None;
+                // 다른 행동의 키도 바뀌었을 수 있으므로 모두 다시 적는다.
+                for name in action::ACTIONS.iter() {
+                    self.refresh_label(name);
+                }
+                return StateResult::Default;
+            }
+        }
+
+        self.input.process_event(event);
+
+        for (_k, button) in self.buttons.iter_mut() {
+            button.process_event(event);
+        }
+        StateResult::Default
+    }
+
+    fn update(&mut self, _dt: f64) -> StateResult {
+        if self.waiting.is_none() && self.actions.pressed(&self.input, action::CANCEL) {
+            self.leave();
+        }
+
+        // 버튼이 눌린 행동의 키를 기다린다.
+        let mut clicked = None;
+        for name in action::ACTIONS.iter() {
+            let button = self.buttons.get_mut(*name).unwrap();
+            button.update();
+            if button.is_clicked {
+                button.reset();
+                clicked = Some(*name);
+            }
+        }
+
+        if let Some(name) = clicked {
+            if let Some(previous) = self.waiting.replace(name) {
+                self.refresh_label(previous);
+            }
+            self.refresh_label(name);
+        }
+
+        self.input.begin_new_frame();
+
+        StateResult::Default
+    }
+
+    fn render(&self, canvas: &mut WindowCanvas) -> StateResult {
+        let texture_manager = self.texture_manager.as_ref().unwrap();
+
+        for (_k, button) in self.buttons.iter() {
+            button.render(canvas, texture_manager);
+        }
+        for (_k, label) in self.labels.iter() {
+            label.render(canvas, texture_manager);
+        }
+
+        StateResult::Default
+    }
+
+    fn process_mouse(&mut self, input: &Input, _dt: f64) {
+        for (_k, button) in self.buttons.iter_mut() {
+            button.process_mouse(input);
+        }
+    }
+
+    fn next_result(&mut self) -> StateResult {
+        let result = self.state_result;
+        self.state_result = StateResult::Default;
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leave_without_saving_while_replaying() {
+        let dir = std::env::temp_dir().join(format!("controls_{}", std::process::id()));
+        std::env::set_var("XDG_CONFIG_HOME", &dir);
+        set_persistent(false);
+
+        let mut state = ControlsState::new();
+        state.leave();
+        set_persistent(true);
+
+        assert_eq!(state.next_result(), StateResult::Pop);
+        assert!(!config_path().exists());
+        assert!(!dir.exists());
+    }
+}
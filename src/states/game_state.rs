@@ -1,18 +1,23 @@
 use crate::actions::teleport;
+use crate::camera::{split_screen, Camera};
 use crate::components::*;
 use crate::constant::*;
 use crate::entities::*;
+use crate::input::action::{self, ActionMap};
+use crate::input::Input;
 use crate::map::*;
 use crate::quadtree::*;
+use crate::render_queue::*;
+use crate::settings::G_SETTINGS;
 use crate::states::*;
 use crate::timer::{Timer, TimerResult};
 
 use std::collections::HashMap;
-use std::collections::HashSet;
 use std::path::Path;
 
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::TextureCreator;
 use sdl2::render::WindowCanvas;
@@ -25,6 +30,15 @@ use uuid::Uuid;
 
 use rand::prelude::*;
 
+/// 한 화면에서 함께 할 수 있는 최대 인원
+const MAX_LOCAL_PLAYERS: usize = 4;
+
+/// 플레이어 한 명이 보는 화면
+struct PlayerView {
+    player: Uuid,
+    camera: Camera,
+}
+
 /// 게임 실행용 State
 pub struct GameState<'a> {
     texture_manager: TextureManager<'a>,
@@ -36,17 +50,23 @@ pub struct GameState<'a> {
     chunks: HashMap<String, Chunk>,
     state_result: StateResult,
     map: Option<Map<'a>>,
-    keyboards: HashSet<sdl2::keyboard::Keycode>,
-    cx: i32, // 카메라 X 좌표
-    cy: i32, // 카메라 Y 좌표
-    cw: u32, // 카메라 폭
-    ch: u32, // 카메라 높이
+    input: Input,
+    actions: ActionMap,
+    views: Vec<PlayerView>, // 참가한 순서대로 플레이어마다 하나씩
+    world: Rectangle,       // entity와 카메라가 움직일 수 있는 월드 영역
     timers: HashMap<Uuid, Timer>,
     entity_actions: Vec<EntityAction>,
+    rng: StdRng, // 녹화한 입력을 재생할 때 같은 결과가 나오도록 seed 를 받는다.
+    alpha: f64,  // 지난 update 와 다음 update 사이의 비율. 카메라를 그릴 때 쓴다.
 }
 
 impl<'a> GameState<'a> {
     pub fn new() -> GameState<'a> {
+        GameState::with_seed(rand::random())
+    }
+
+    pub fn with_seed(seed: u64) -> GameState<'a> {
+        let mut rng = StdRng::seed_from_u64(seed);
         let texture_manager = TextureManager::new();
         //let pc = UnitCharacter::new(16, 16, 2, 200., 1500., 900.);
         let mut entities = HashMap::new();
@@ -54,10 +74,10 @@ impl<'a> GameState<'a> {
         let mut entity = Entity::new(EntityType::PLAYER);
         entity.set_movement(0., 0., (0, 0), (0., 0.), 200., 1500., 900.);
 
+        let player = entity.id;
         entities.insert(entity.id, entity);
 
         for _ in 0..1 {
-            let mut rng = rand::thread_rng();
             let x: f64 = rng.gen::<f64>() * 300.0;
             let y: f64 = rng.gen::<f64>() * 200.0;
             let speed: f64 = 100.0;
@@ -85,13 +105,17 @@ impl<'a> GameState<'a> {
             map: None,
             music: None,
             chunks: HashMap::new(),
-            keyboards: HashSet::new(),
-            cx: 0,
-            cy: 0,
-            cw: VIRTUAL_WIDTH,
-            ch: VIRTUAL_HEIGHT,
+            input: Input::default(),
+            actions: G_SETTINGS.read().unwrap().action_map(),
+            views: vec![PlayerView {
+                player,
+                camera: GameState::new_camera(VIRTUAL_WIDTH, VIRTUAL_HEIGHT),
+            }],
+            world: Rectangle::new(0.0, 0.0, WORLD_WIDTH as f64, WORLD_HEIGHT as f64),
             timers: HashMap::new(),
             entity_actions: vec![],
+            rng,
+            alpha: 1.0,
         }
     }
 
@@ -253,22 +277,104 @@ impl<'a> GameState<'a> {
         );
 
         let map = Map::new("map".to_owned(), texture_creator, "tiled_base64_zlib.tmx");
-        // 장애물 등록
-        let mut blocks: Vec<(Uuid, Entity)> = vec![];
-        for block in map.blocks.iter() {
-            let mut entity = Entity::new(EntityType::BLOCK);
-            entity.set_movement(block.x as f64, block.y as f64, (0, 0), (0., 0.), 0., 0., 0.);
-            entity.set_hitbox(0.0, 0.0, block.w as f64, block.h as f64);
-            blocks.push((Uuid::new_v4(), entity));
+        // 지도의 막힌 타일은 entity 로 만들지 않고 update_collision_slide 에서 지도와 직접 판정한다.
+
+        // 객체 레이어의 spawn 정보로 entity 생성
+        let mut player_spawn: Option<(f64, f64)> = None;
+        let mut spawned: Vec<Entity> = vec![];
+        for spawn in map.spawns.iter() {
+            match spawn.entity_type() {
+                Some(EntityType::PLAYER) if player_spawn.is_none() => {
+                    player_spawn = Some((spawn.x, spawn.y));
+                }
+                Some(EntityType::MOB) => {
+                    let mut enemy = Entity::new(EntityType::MOB);
+                    enemy.set_movement(
+                        spawn.x,
+                        spawn.y,
+                        (0, 0),
+                        (0.0, 0.0),
+                        spawn.get_f64("speed").unwrap_or(100.0),
+                        1200.0,
+                        300.0,
+                    );
+                    spawned.push(enemy);
+                }
+                Some(EntityType::BLOCK) => {
+                    let mut entity = Entity::new(EntityType::BLOCK);
+                    entity.set_movement(spawn.x, spawn.y, (0, 0), (0., 0.), 0., 0., 0.);
+                    entity.set_hitbox(0.0, 0.0, spawn.w, spawn.h);
+                    spawned.push(entity);
+                }
+                _ => (),
+            }
         }
 
+        // 지도에 적의 위치가 정해져 있다면 임의로 만든 적은 없앤다.
+        if spawned.iter().any(|entity| entity.type_ == EntityType::MOB) {
+            self.entities
+                .retain(|_, entity| entity.type_ != EntityType::MOB);
+        }
+
+        // 지도 크기를 entity 들의 이동 범위로 한다.
+        self.world = map.world_bounds();
         self.map = Some(map);
 
-        for (uuid, entity) in blocks {
-            self.entities.insert(uuid, entity);
+        for entity in spawned {
+            self.entities.insert(entity.id, entity);
+        }
+
+        for entity in self.entities.values_mut() {
+            entity.set_bounds(self.world);
+        }
+
+        // 카메라는 지도 밖을 보이지 않는다.
+        for view in self.views.iter_mut() {
+            view.camera.set_bounds(self.world);
+            if let Some((x, y)) = player_spawn {
+                view.camera.look_at(x, y);
+            }
         }
 
         // 캐릭터 애니메이션 생성
+        self.add_animations();
+
+        // player, enemy 캐릭터에 대한 Hitbox 등록
+        self.add_hitboxes();
+
+        // player 캐릭터를 시작 위치로 옮긴다.
+        let players: Vec<(Uuid, Entity)> = self
+            .entities
+            .clone()
+            .into_iter()
+            .filter(|(_, entity)| entity.type_ == EntityType::PLAYER)
+            .map(|(uuid, mut entity)| {
+                match player_spawn {
+                    Some((x, y)) => entity.set_pos_xy(x, y),
+                    None => teleport(self.map.as_ref().unwrap(), &mut entity, 15, 15),
+                }
+                (uuid, entity)
+            })
+            .collect();
+
+        for (uuid, entity) in players {
+            self.entities.insert(uuid, entity);
+        }
+
+        //self.pc2.set_hitbox(0.0, 0.0, 2.0, 0.0, 12, 16);
+        //self.pc.set_hitbox(2, 0, 12, 16);
+        //self.enemy.set_hitbox(2, 0, 12, 16);
+
+        // 음원 등록
+        self.add_music("resources/beat.wav".to_owned());
+
+        self.add_sound("high".to_owned(), "resources/high.wav".to_owned());
+        self.add_sound("low".to_owned(), "resources/low.wav".to_owned());
+    }
+
+    /// 캐릭터, 적, 공격의 애니메이션 등록
+    /// 애니메이션은 텍스쳐의 이름과 영역만 가지므로 텍스쳐 없이도 쓸 수 있다.
+    fn add_animations(&mut self) {
         self.add_unit_char(
             EntityType::PLAYER,
             Direction::Up,
@@ -395,260 +501,255 @@ impl<'a> GameState<'a> {
             false,
             false,
         );
+    }
 
-        // player 캐릭터에 대한 Hitbox 등록
-        let players: Vec<(Uuid, Entity)> = self
-            .entities
-            .clone()
-            .into_iter()
-            .filter(|(_, entity)| entity.type_ == EntityType::PLAYER)
-            .map(|(uuid, mut entity)| {
-                teleport(self.map.as_ref().unwrap(), &mut entity, 15, 15);
-                entity.set_hitbox(0.0, 0.0, 16.0, 16.0);
-                (uuid, entity)
-            })
-            .collect();
+    /// player, enemy 캐릭터에 hitbox 를 붙인다.
+    fn add_hitboxes(&mut self) {
+        for entity in self.entities.values_mut() {
+            match entity.type_ {
+                EntityType::PLAYER => entity.set_hitbox(0.0, 0.0, 16.0, 16.0),
+                EntityType::MOB => entity.set_hitbox(2.0, 0.0, 12.0, 16.0),
+                _ => (),
+            }
+        }
+    }
 
-        for (uuid, entity) in players {
-            self.entities.insert(uuid, entity);
+    /// 창, 지도, 소리 없이 돌리는 state
+    /// world 영역 안에서 움직이며 render 와 소리 재생은 하지 않는다.
+    /// 자동화된 테스트에서 입력을 넣고 update 만 돌리는 용도
+    pub fn headless(seed: u64, world: Rectangle) -> GameState<'a> {
+        let mut state = GameState::with_seed(seed);
+
+        state.world = world;
+        for entity in state.entities.values_mut() {
+            entity.set_bounds(world);
+        }
+        for view in state.views.iter_mut() {
+            view.camera.set_bounds(world);
         }
-        // enemy 캐릭터에 대한 Hitbox 등록
-        let enemies: Vec<(Uuid, Entity)> = self
-            .entities
-            .clone()
-            .into_iter()
-            .filter(|(_, entity)| entity.type_ == EntityType::MOB)
-            .map(|(uuid, mut entity)| {
-                entity.set_hitbox(2.0, 0.0, 12.0, 16.0);
-                (uuid, entity)
-            })
-            .collect();
 
-        for (uuid, entity) in enemies {
-            self.entities.insert(uuid, entity);
+        state.add_animations();
+        state.add_hitboxes();
+
+        state
+    }
+
+    /// 첫번째 플레이어
+    pub fn player(&self) -> Option<&Entity> {
+        self.views
+            .first()
+            .and_then(|view| self.entities.get(&view.player))
+    }
+
+    pub fn entities(&self) -> &HashMap<Uuid, Entity> {
+        &self.entities
+    }
+
+    /// entity 를 더한다. 이동 범위는 지금의 world 로 한다.
+    pub fn add_entity(&mut self, mut entity: Entity) -> Uuid {
+        let id = entity.id;
+
+        entity.set_bounds(self.world);
+        self.entities.insert(id, entity);
+
+        id
+    }
+
+    /// 주어진 종류의 entity 를 모두 없앤다.
+    pub fn remove_entities(&mut self, type_: EntityType) {
+        self.entities.retain(|_, entity| entity.type_ != type_);
+    }
+
+    /// 플레이어를 따라갈 카메라
+    /// 화면 가운데 20% 안에서는 카메라가 움직이지 않는다.
+    fn new_camera(w: u32, h: u32) -> Camera {
+        let mut camera = Camera::new(w, h);
+        camera.set_dead_zone(w as f64 * 0.2, h as f64 * 0.2);
+        camera.set_smoothing(8.0);
+
+        camera
+    }
+
+    /// 새 플레이어를 첫번째 플레이어 옆에 만들고 화면을 나눈다.
+    fn join_player(&mut self) {
+        if self.views.len() >= MAX_LOCAL_PLAYERS {
+            return;
         }
 
-        //self.pc2.set_hitbox(0.0, 0.0, 2.0, 0.0, 12, 16);
-        //self.pc.set_hitbox(2, 0, 12, 16);
-        //self.enemy.set_hitbox(2, 0, 12, 16);
+        let first = match self
+            .views
+            .first()
+            .and_then(|view| self.entities.get(&view.player))
+        {
+            Some(entity) => entity,
+            None => return,
+        };
 
-        // 음원 등록
-        self.add_music("resources/beat.wav".to_owned());
+        let mut entity = first.clone();
+        entity.id = Uuid::new_v4();
+        if let Some(movement) = entity.movement.as_ref() {
+            let (x, y) = movement.get_pos();
+            entity.set_pos_xy(x + 16.0 * self.views.len() as f64, y);
+        }
 
-        self.add_sound("high".to_owned(), "resources/high.wav".to_owned());
-        self.add_sound("low".to_owned(), "resources/low.wav".to_owned());
+        let mut camera = GameState::new_camera(VIRTUAL_WIDTH, VIRTUAL_HEIGHT);
+        camera.set_bounds(self.world);
+        if let Some(movement) = entity.movement.as_ref() {
+            camera.look_at(movement.get_pos_x(), movement.get_pos_y());
+        }
+
+        self.views.push(PlayerView {
+            player: entity.id,
+            camera,
+        });
+        self.entities.insert(entity.id, entity);
+        self.layout_views();
     }
 
-    fn update_camera(&mut self) {
-        // cx, cy 를 기준으로 모든 좌표계를 이동해야한다.
-        // 예를 들어, 현재 world 기준으로 (100,100)인데 (cx,cy)가 (100,100)이라면
-        // 해당 좌표는 100,100만큼 작아져야한다.
+    /// 마지막에 참가한 플레이어를 뺀다. 첫번째 플레이어는 남긴다.
+    fn leave_player(&mut self) {
+        if self.views.len() > 1 {
+            let view = self.views.pop().unwrap();
+            self.entities.remove(&view.player);
+            self.layout_views();
+        }
+    }
 
-        // cx, cy를 구한다.
-        // cx, cy는 추적하는 캐릭터에 맞추어 정해진다.
-        // 여기서는 unit_char이다.
-        // cx + cw 구간 양쪽 10% 공간에 있다면 cx는 왼쪽으로는 10% 여백이 가능한 만큼 좌측으로 이동하고
-        // 우측으로는 10% 여백이 가능한 만큼 우측으로 이동해야한다.
-        // cy + ch 에 대해서도 동일한다.
+    /// 플레이어 수에 맞추어 화면을 나눈다.
+    fn layout_views(&mut self) {
+        let rects = split_screen(VIRTUAL_WIDTH, VIRTUAL_HEIGHT, self.views.len());
 
-        let player: Vec<(Uuid, Entity)> = self
-            .entities
-            .clone()
-            .into_iter()
-            .filter(|(_, entity)| entity.type_ == EntityType::PLAYER)
-            .collect();
+        for (view, rect) in self.views.iter_mut().zip(rects) {
+            view.camera.set_screen_rect(rect);
+            view.camera
+                .set_dead_zone(rect.width() as f64 * 0.2, rect.height() as f64 * 0.2);
+            view.camera.clamp();
+        }
+    }
 
-        let ux = player[0].1.movement.as_ref().unwrap().get_pos_x() as i32;
-        let uy = player[0].1.movement.as_ref().unwrap().get_pos_y() as i32;
+    /// 화면 좌표 위에 있는 view, 없으면 첫번째 view
+    fn view_at(&mut self, x: i32, y: i32) -> Option<&mut PlayerView> {
+        let idx = self
+            .views
+            .iter()
+            .position(|view| view.camera.screen_rect().contains_point((x, y)))
+            .unwrap_or(0);
 
-        let width_margin = (self.cw as f32 * 0.4) as u32; // 카메라 좌우측 여유 공간
-        let height_margin = (self.ch as f32 * 0.4) as u32; // 카메라 상하측 여유 공간
+        self.views.get_mut(idx)
+    }
 
-        // 카메라는 사용자를 쫓아간다.
-        // Follow mode
+    /// 카메라 하나로 지도와 entity 를 그린다.
+    fn render_view(&self, canvas: &mut WindowCanvas, camera: &Camera) {
+        let mut queue = RenderQueue::new();
 
-        let rx = ux - self.cx;
-        let ry = uy - self.cy;
+        // 바닥 레이어를 먼저 출력하고, 깊이 정렬할 타일을 모은다.
+        if let Some(map) = &self.map {
+            map.render(canvas, camera);
+            map.queue_tiles(&mut queue, camera);
+        }
 
-        if rx < width_margin as i32 {
-            // cx를 ux 위치가 left_limit인 곳까지 이동한다.
-            self.cx = ux - width_margin as i32;
-            if self.cx < 0 {
-                self.cx = 0;
-            }
-        } else if rx > (self.cw - width_margin) as i32 {
-            // cx를 ux 위치가 right_limit인 곳까지 이동한다.
-            let dx = width_margin as i32 - (self.cx + self.cw as i32 - ux);
-            self.cx += dx;
-            if self.cx as u32 + self.cw > WORLD_WIDTH {
-                self.cx = (WORLD_WIDTH - self.cw) as i32;
-            }
+        for (uuid, entity) in self.entities.iter() {
+            queue.push(
+                entity.z.unwrap_or(0),
+                entity.depth(),
+                RenderCommand::Entity(*uuid),
+            );
         }
 
-        if ry < height_margin as i32 {
-            // cy를 uy 위치가 top_limit인 곳까지 이동한다.
-            self.cy = uy - height_margin as i32;
-            if self.cy < 0 {
-                self.cy = 0;
-            }
-        } else if ry > (self.ch - height_margin) as i32 {
-            // cy를 uy 위치가 bottom_limit인 곳까지 이동한다.
-            let dy = height_margin as i32 - (self.cy + self.ch as i32 - uy);
-            self.cy += dy;
-            if self.cy as u32 + self.ch > WORLD_HEIGHT {
-                self.cy = (WORLD_HEIGHT - self.ch) as i32;
+        // 위쪽(뒤쪽)에 있는 것부터 그린다.
+        queue.sort();
+
+        for item in queue.iter() {
+            match item.command {
+                RenderCommand::Tile {
+                    texture,
+                    src,
+                    dest,
+                    flip,
+                } => {
+                    if let Some(map) = &self.map {
+                        map.render_tile(canvas, texture, src, dest, flip, camera.rotation);
+                    }
+                }
+                RenderCommand::Entity(uuid) => {
+                    if let Some(entity) = self.entities.get(&uuid) {
+                        entity.render(canvas, camera, Some(&self.texture_manager));
+                    }
+                }
             }
         }
     }
 
-    fn update_input(&mut self, dt: f64) {
-        // 키보드 처리
-
-        if self.keyboards.contains(&Keycode::Up) || self.keyboards.contains(&Keycode::W) {
-            //self.pc.move_forward((0., -1.), dt);
-            /*
-            self.pc2
-            .movement
-            .as_mut()
-            .unwrap()
-            .move_forward((0., -1.), dt);
-             */
-            let entities: Vec<(Uuid, Entity)> = self
+    /// 카메라가 각자의 플레이어를 따라가게 한다.
+    fn update_camera(&mut self, dt: f64) {
+        for view in self.views.iter_mut() {
+            let movement = self
                 .entities
-                .clone()
-                .into_iter()
-                .filter(|(_, entity)| {
-                    entity.type_ == EntityType::PLAYER && entity.movement.as_ref().is_some()
-                })
-                .map(|(uuid, mut entity)| {
-                    entity
-                        .movement
-                        .as_mut()
-                        .unwrap()
-                        .move_forward((0.0, -1.0), dt);
-                    (uuid, entity)
-                })
-                .collect();
-            for (uuid, entity) in entities {
-                self.entities.insert(uuid, entity);
+                .get(&view.player)
+                .and_then(|entity| entity.movement.as_ref());
+
+            view.camera.update(dt);
+
+            if let Some(movement) = movement {
+                view.camera
+                    .follow(movement.get_pos_x(), movement.get_pos_y(), dt);
             }
         }
-        if self.keyboards.contains(&Keycode::Down) || self.keyboards.contains(&Keycode::S) {
-            //self.pc.move_forward((0., 1.), dt);
-            /*
-            self.pc2
-            .movement
-            .as_mut()
-            .unwrap()
-            .move_forward((0., 1.), dt);
-             */
+    }
 
-            let entities: Vec<(Uuid, Entity)> = self
-                .entities
-                .clone()
-                .into_iter()
-                .filter(|(_, entity)| {
-                    entity.type_ == EntityType::PLAYER && entity.movement.as_ref().is_some()
-                })
-                .map(|(uuid, mut entity)| {
-                    entity
-                        .movement
-                        .as_mut()
-                        .unwrap()
-                        .move_forward((0.0, 1.0), dt);
-                    (uuid, entity)
-                })
-                .collect();
-            for (uuid, entity) in entities {
-                self.entities.insert(uuid, entity);
-            }
+    fn update_input(&mut self, dt: f64) {
+        if self.actions.pressed(&self.input, action::CANCEL) {
+            self.state_result = StateResult::Pop;
         }
-        if self.keyboards.contains(&Keycode::Left) || self.keyboards.contains(&Keycode::A) {
-            //self.pc.move_forward((-1., 0.), dt);
-            /*
-            self.pc2
-            .movement
-            .as_mut()
-            .unwrap()
-            .move_forward((-1., 0.), dt);
-             */
-            let entities: Vec<(Uuid, Entity)> = self
-                .entities
-                .clone()
-                .into_iter()
-                .filter(|(_, entity)| {
-                    entity.type_ == EntityType::PLAYER && entity.movement.as_ref().is_some()
-                })
-                .map(|(uuid, mut entity)| {
-                    entity
-                        .movement
-                        .as_mut()
-                        .unwrap()
-                        .move_forward((-1.0, 0.0), dt);
-                    (uuid, entity)
-                })
-                .collect();
-            for (uuid, entity) in entities {
-                self.entities.insert(uuid, entity);
-            }
+
+        // 일시 정지 (기본은 P 나 패드의 Start)
+        if self.actions.pressed(&self.input, action::PAUSE) {
+            self.state_result = StateResult::Push(StateInfo::Pause("pause"));
         }
-        if self.keyboards.contains(&Keycode::Right) || self.keyboards.contains(&Keycode::D) {
-            //self.pc.move_forward((1., 0.), dt);
-            /*
-            self.pc2
-            .movement
-            .as_mut()
-            .unwrap()
-            .move_forward((1., 0.), dt);
-             */
-            let entities: Vec<(Uuid, Entity)> = self
-                .entities
-                .clone()
-                .into_iter()
-                .filter(|(_, entity)| {
-                    entity.type_ == EntityType::PLAYER && entity.movement.as_ref().is_some()
-                })
-                .map(|(uuid, mut entity)| {
-                    entity
-                        .movement
-                        .as_mut()
-                        .unwrap()
-                        .move_forward((1.0, 0.0), dt);
-                    (uuid, entity)
-                })
-                .collect();
-            for (uuid, entity) in entities {
-                self.entities.insert(uuid, entity);
-            }
+
+        // 다음 자리의 패드가 확인 버튼을 누르면 참가한다.
+        if self
+            .actions
+            .pressed_by(&self.input, Some(self.views.len()), action::CONFIRM)
+        {
+            self.join_player();
         }
 
-        // 총알을 쏴라
-        if self.keyboards.contains(&Keycode::Space) {
-            // 타이머 생성
-            let entities: Vec<(Uuid, Entity)> = self
-                .entities
-                .clone()
-                .into_iter()
-                .filter(|(_, entity)| {
-                    entity.type_ == EntityType::PLAYER && entity.movement.as_ref().is_some()
-                })
-                .map(|(uuid, mut entity)| {
-                    entity.insert_timer(
-                        "SHOOT".to_owned(),
-                        Timer {
-                            t: 0.0,
-                            b: 0.0,
-                            c: 0.2,
-                            d: 1.0,
-                            result: Some(TimerResult::EntitySpwan("ATTACK".to_owned())),
-                        },
-                    );
+        // 플레이어마다 자기 입력으로 움직인다.
+        // 키보드와 마우스는 첫번째 플레이어, 패드는 연결된 순서대로 플레이어에 붙는다.
+        for (idx, view) in self.views.iter().enumerate() {
+            let player = Some(idx);
+            let entity = match self.entities.get_mut(&view.player) {
+                Some(entity) => entity,
+                None => continue,
+            };
+
+            // 스틱을 조금 기울이면 천천히 움직인다.
+            let direction = (
+                self.actions.axis_by(&self.input, player, action::MOVE_X),
+                self.actions.axis_by(&self.input, player, action::MOVE_Y),
+            );
+            if let Some(movement) = entity.movement.as_mut() {
+                if direction != (0.0, 0.0) {
+                    movement.move_forward(direction, dt);
+                }
+            } else {
+                continue;
+            }
 
-                    (uuid, entity)
-                })
-                .collect();
-            for (uuid, entity) in entities {
-                self.entities.insert(uuid, entity);
+            // 총알을 쏴라
+            if self.actions.held_by(&self.input, player, action::ATTACK) {
+                // 타이머 생성
+                entity.insert_timer(
+                    "SHOOT".to_owned(),
+                    Timer {
+                        t: 0.0,
+                        b: 0.0,
+                        c: 0.2,
+                        d: 1.0,
+                        result: Some(TimerResult::EntitySpwan("ATTACK".to_owned())),
+                    },
+                );
             }
         }
     }
@@ -674,10 +775,7 @@ impl<'a> GameState<'a> {
             .collect();
 
         // Quadtree를 생성하고, 미래 entity의 hitbox 정보를 넣는다.
-        let mut quadtree = QuadTree::new(
-            Rectangle::new(0.0, 0.0, WORLD_WIDTH as f64, WORLD_HEIGHT as f64),
-            4,
-        );
+        let mut quadtree = QuadTree::new(self.world, 4);
 
         for (p_uuid, p_entity) in &future_entities {
             if p_entity.hitbox.is_some() {
@@ -691,6 +789,10 @@ impl<'a> GameState<'a> {
             }
         }
 
+        // 지도의 막힌 타일과 충돌 도형
+        let map = self.map.as_ref();
+        let collides_with_map = |rect: &Rectangle| map.is_some_and(|map| map.collides(rect));
+
         // future entity 에서 충돌이 발생하는지를 계산한다.
         let moved_entities: HashMap<Uuid, Entity> = self
             .entities
@@ -732,59 +834,57 @@ impl<'a> GameState<'a> {
                         // quadtree에 포함된 항목과 충돌판정한다.
                         // X 좌표는 바꾸지 않은 상태(X 이동이 없을 때) 충돌이 일어나는가?
                         let original_hitbox = entity.hitbox.unwrap().get_rect();
-                        let is_collided = hash_uuid
-                            .iter()
-                            .filter(|(&quad_tree_uuid, _)| uuid != quad_tree_uuid)
-                            .any(|(uuid, _)| {
-                                let others = future_entities.get(uuid).unwrap();
-                                let other_hitbox = others.hitbox.as_ref().unwrap().get_rect();
-
-                                detect_collision(
-                                    &Rectangle {
-                                        x: future_entity_hitbox.x,
-                                        y: future_entity_hitbox.y,
-                                        w: future_entity_hitbox.w,
-                                        h: future_entity_hitbox.h,
-                                    },
-                                    &other_hitbox,
-                                )
-                            });
-
-                        let is_collided_when_x_move = hash_uuid
-                            .iter()
-                            .filter(|(&quad_tree_uuid, _)| uuid != quad_tree_uuid)
-                            .any(|(uuid, _)| {
-                                let others = future_entities.get(uuid).unwrap();
-                                let other_hitbox = others.hitbox.as_ref().unwrap().get_rect();
-
-                                detect_collision(
-                                    &Rectangle {
-                                        x: future_entity_hitbox.x,
-                                        y: original_hitbox.y,
-                                        w: future_entity_hitbox.w,
-                                        h: future_entity_hitbox.h,
-                                    },
-                                    &other_hitbox,
-                                )
-                            });
-
-                        let is_collided_when_y_move = hash_uuid
-                            .iter()
-                            .filter(|(&quad_tree_uuid, _)| uuid != quad_tree_uuid)
-                            .any(|(uuid, _)| {
-                                let others = future_entities.get(uuid).unwrap();
-                                let other_hitbox = others.hitbox.as_ref().unwrap().get_rect();
-
-                                detect_collision(
-                                    &Rectangle {
-                                        x: original_hitbox.x,
-                                        y: future_entity_hitbox.y,
-                                        w: future_entity_hitbox.w,
-                                        h: future_entity_hitbox.h,
-                                    },
-                                    &other_hitbox,
-                                )
-                            });
+                        let future_rect = Rectangle {
+                            x: future_entity_hitbox.x,
+                            y: future_entity_hitbox.y,
+                            w: future_entity_hitbox.w,
+                            h: future_entity_hitbox.h,
+                        };
+                        let x_moved_rect = Rectangle {
+                            x: future_entity_hitbox.x,
+                            y: original_hitbox.y,
+                            w: future_entity_hitbox.w,
+                            h: future_entity_hitbox.h,
+                        };
+                        let y_moved_rect = Rectangle {
+                            x: original_hitbox.x,
+                            y: future_entity_hitbox.y,
+                            w: future_entity_hitbox.w,
+                            h: future_entity_hitbox.h,
+                        };
+
+                        let is_collided = collides_with_map(&future_rect)
+                            || hash_uuid
+                                .iter()
+                                .filter(|(&quad_tree_uuid, _)| uuid != quad_tree_uuid)
+                                .any(|(uuid, _)| {
+                                    let others = future_entities.get(uuid).unwrap();
+                                    let other_hitbox = others.hitbox.as_ref().unwrap().get_rect();
+
+                                    detect_collision(&future_rect, &other_hitbox)
+                                });
+
+                        let is_collided_when_x_move = collides_with_map(&x_moved_rect)
+                            || hash_uuid
+                                .iter()
+                                .filter(|(&quad_tree_uuid, _)| uuid != quad_tree_uuid)
+                                .any(|(uuid, _)| {
+                                    let others = future_entities.get(uuid).unwrap();
+                                    let other_hitbox = others.hitbox.as_ref().unwrap().get_rect();
+
+                                    detect_collision(&x_moved_rect, &other_hitbox)
+                                });
+
+                        let is_collided_when_y_move = collides_with_map(&y_moved_rect)
+                            || hash_uuid
+                                .iter()
+                                .filter(|(&quad_tree_uuid, _)| uuid != quad_tree_uuid)
+                                .any(|(uuid, _)| {
+                                    let others = future_entities.get(uuid).unwrap();
+                                    let other_hitbox = others.hitbox.as_ref().unwrap().get_rect();
+
+                                    detect_collision(&y_moved_rect, &other_hitbox)
+                                });
 
                         if is_collided {
                             let mut new_v = entity.movement.unwrap().velocity;
@@ -829,8 +929,11 @@ impl<'a> GameState<'a> {
             })
             .collect();
 
-        let px = (player[0].1).movement.as_ref().unwrap().x;
-        let py = (player[0].1).movement.as_ref().unwrap().y;
+        let player_pos: Vec<(f64, f64)> = player
+            .iter()
+            .filter_map(|(_, entity)| entity.movement.as_ref())
+            .map(|movement| movement.get_pos())
+            .collect();
 
         let entities: Vec<(Uuid, Entity)> = self
             .entities
@@ -840,12 +943,15 @@ impl<'a> GameState<'a> {
             .map(|(uuid, mut entity)| {
                 entity.update(dt);
 
-                // 공격 선정
+                // 공격 선정. 가장 가까운 플레이어를 노린다.
                 let tmps = entity.clone();
                 if let Some(movement) = tmps.movement.as_ref() {
                     let direction = facing_to_direction(movement.get_facing());
-                    if let Some(animation) = tmps.animation.get(&direction) {
-                        entity.attack.as_mut().unwrap().set_deg((px, py), animation);
+                    let target = nearest(&player_pos, movement.get_pos());
+                    if let (Some(animation), Some(target)) =
+                        (tmps.animation.get(&direction), target)
+                    {
+                        entity.attack.as_mut().unwrap().set_deg(target, animation);
                     }
                 }
 
@@ -867,15 +973,15 @@ impl<'a> GameState<'a> {
         // MOB은 자신과 캐릭터간의 방향 벡터를 계산하여
         // 그만큼 움직이도록 스스로의 방향 벡터를 설정한다.
 
-        let pc_vec: Vec<(Uuid, Entity)> = self
+        // 플레이어마다 hitbox 의 가운데
+        let pc_centers: Vec<(f64, f64)> = self
             .entities
-            .clone()
-            .into_iter()
-            .filter(|(_, entity)| entity.type_ == EntityType::PLAYER)
+            .values()
+            .filter(|entity| entity.type_ == EntityType::PLAYER)
+            .filter_map(|entity| entity.hitbox.as_ref().map(hitbox_center))
             .collect();
 
-        let pc = &pc_vec[0].1;
-
+        // 가장 가까운 플레이어를 쫓는다.
         let enemies: Vec<(Uuid, Entity)> = self
             .entities
             .clone()
@@ -884,30 +990,15 @@ impl<'a> GameState<'a> {
                 entity.type_ == EntityType::MOB && entity.movement.as_ref().is_some()
             })
             .map(|(uuid, mut entity)| {
-                if pc.hitbox.is_some() && entity.hitbox.is_some() {
-                    let forwarding = facing_from_to(
-                        (
-                            pc.hitbox.unwrap().x
-                                + pc.hitbox.unwrap().hx
-                                + pc.hitbox.unwrap().w / 2.0,
-                            pc.hitbox.unwrap().y
-                                + pc.hitbox.unwrap().hy
-                                + pc.hitbox.unwrap().h / 2.0,
-                        ),
-                        (
-                            entity.hitbox.unwrap().x
-                                + entity.hitbox.unwrap().hx
-                                + entity.hitbox.unwrap().w / 2.0,
-                            entity.hitbox.unwrap().y
-                                + entity.hitbox.unwrap().hy
-                                + entity.hitbox.unwrap().h / 2.0,
-                        ),
-                    );
-                    entity
-                        .movement
-                        .as_mut()
-                        .unwrap()
-                        .move_forward(forwarding, dt);
+                if let Some(center) = entity.hitbox.as_ref().map(hitbox_center) {
+                    if let Some(target) = nearest(&pc_centers, center) {
+                        let forwarding = facing_from_to(target, center);
+                        entity
+                            .movement
+                            .as_mut()
+                            .unwrap()
+                            .move_forward(forwarding, dt);
+                    }
                 }
                 (uuid, entity)
             })
@@ -992,9 +1083,8 @@ impl<'a> GameState<'a> {
             match action {
                 EntityAction::CREATE(etype) => match etype {
                     EntityType::ATTACK => {
-                        let mut rng = rand::thread_rng();
-                        let x: f64 = rng.gen::<f64>() * 300.0;
-                        let y: f64 = rng.gen::<f64>() * 200.0;
+                        let x: f64 = self.rng.gen::<f64>() * 300.0;
+                        let y: f64 = self.rng.gen::<f64>() * 200.0;
                         let speed: f64 = 100.0;
                         let mut entity = Entity::new(EntityType::ATTACK);
 
@@ -1009,6 +1099,7 @@ impl<'a> GameState<'a> {
                             1200.0,
                             0.0,
                         );
+                        entity.set_bounds(self.world);
                         let entity_id = entity.id;
 
                         self.entities.insert(entity.id, entity);
@@ -1037,20 +1128,22 @@ impl<'a> GameState<'a> {
 
 impl<'a> States for GameState<'a> {
     fn process_event(&mut self, event: &sdl2::event::Event, _dt: f64) -> StateResult {
+        self.input.process_event(event);
+
         match event {
             Event::KeyDown {
                 keycode: Some(k), ..
             } => {
-                self.keyboards.insert(*k);
+                // 소리를 읽지 않았다면 (headless) 재생하지 않는다.
                 if *k == Keycode::Num1 {
-                    let chunk = self.chunks.get(&"high".to_owned()).unwrap();
-                    sdl2::mixer::Channel::all().play(chunk, 0).unwrap();
+                    if let Some(chunk) = self.chunks.get("high") {
+                        sdl2::mixer::Channel::all().play(chunk, 0).unwrap();
+                    }
                 } else if *k == Keycode::Num2 {
-                    let chunk = self.chunks.get(&"low".to_owned()).unwrap();
-                    sdl2::mixer::Channel::all().play(chunk, 0).unwrap();
-                } else if *k == Keycode::Num0 {
-                    let music = self.music.as_ref().unwrap();
-
+                    if let Some(chunk) = self.chunks.get("low") {
+                        sdl2::mixer::Channel::all().play(chunk, 0).unwrap();
+                    }
+                } else if let (Keycode::Num0, Some(music)) = (*k, self.music.as_ref()) {
                     if !sdl2::mixer::Music::is_playing() {
                         music.play(-1).unwrap();
                     } else if sdl2::mixer::Music::is_paused() {
@@ -1060,16 +1153,29 @@ impl<'a> States for GameState<'a> {
                     }
                 }
 
-                if *k == Keycode::Escape {
-                    self.state_result = StateResult::Pop;
-                } else {
-                    self.state_result = StateResult::Default;
+                // 카메라 확대, 회전은 모든 화면에 적용한다.
+                for view in self.views.iter_mut() {
+                    let camera = &mut view.camera;
+                    match *k {
+                        Keycode::Equals | Keycode::KpPlus => camera.set_zoom(camera.zoom * 1.25),
+                        Keycode::Minus | Keycode::KpMinus => camera.set_zoom(camera.zoom / 1.25),
+                        Keycode::LeftBracket => camera.set_rotation(camera.rotation - 15.0),
+                        Keycode::RightBracket => camera.set_rotation(camera.rotation + 15.0),
+                        Keycode::Backspace => {
+                            camera.set_zoom(1.0);
+                            camera.set_rotation(0.0);
+                        }
+                        _ => (),
+                    }
                 }
-            }
-            Event::KeyUp {
-                keycode: Some(k), ..
-            } => {
-                self.keyboards.remove(k);
+
+                // 플레이어 참가, 나가기
+                match *k {
+                    Keycode::F2 => self.join_player(),
+                    Keycode::F3 => self.leave_player(),
+                    _ => (),
+                }
+
                 self.state_result = StateResult::Default;
             }
             _ => self.state_result = StateResult::Default,
@@ -1098,129 +1204,106 @@ impl<'a> States for GameState<'a> {
         // 캐릭터 실제 업데이트 처리
         self.update_entities(dt);
 
+        // 지도의 타일 애니메이션
+        if let Some(map) = self.map.as_mut() {
+            map.update(dt);
+        }
+
         // 카메라 위치 변경
-        self.update_camera();
+        self.update_camera(dt);
+
+        // 이번 프레임에 눌리고 떼어진 입력을 지운다.
+        self.input.begin_new_frame();
 
         StateResult::Default
     }
 
+    fn interpolate(&mut self, alpha: f64) {
+        for entity in self.entities.values_mut() {
+            entity.interpolate(alpha);
+        }
+        self.alpha = alpha;
+    }
+
     fn render(&self, canvas: &mut WindowCanvas) -> StateResult {
-        let camera_rect = Rect::new(self.cx, self.cy, self.cw, self.ch);
-        // map 먼저 출력
-        if let Some(map) = &self.map {
-            map.render(canvas, &camera_rect);
+        // 화면마다 자기 영역에만 그린다.
+        for view in self.views.iter() {
+            canvas.set_clip_rect(view.camera.screen_rect());
+            self.render_view(canvas, &view.camera.lerp(self.alpha));
         }
+        canvas.set_clip_rect(None);
 
-        for (_, entity) in self.entities.clone().into_iter() {
-            entity.render(canvas, &camera_rect, Some(&self.texture_manager));
-
-            /*
-            if entity.type_ == EntityType::PLAYER {
-                    entity.render(canvas, &camera_rect, Some(&self.texture_manager));
-                } else if entity.type_ == EntityType::MOB {
-                    //dbg!(&entity);
-                    entity.render(canvas, &camera_rect, Some(&self.texture_manager));
-                } else {
-                    entity.render(canvas, &camera_rect, None);
-                }
-             */
+        // 나뉜 화면의 경계선
+        if self.views.len() > 1 {
+            canvas.set_draw_color(Color::RGB(0, 0, 0));
+            for view in self.views.iter() {
+                canvas.draw_rect(view.camera.screen_rect()).unwrap();
+            }
         }
 
         StateResult::Default
     }
 
-    fn process_mouse(
-        &mut self,
-        x: i32,
-        y: i32,
-        new_buttons: &HashSet<sdl2::mouse::MouseButton>,
-        old_buttons: &HashSet<sdl2::mouse::MouseButton>,
-        _dt: f64,
-    ) {
-        let v_x = transform_value(x, REVERSE_WIDTH_RATIO) + self.cx;
-        let v_y = transform_value(y, REVERSE_HEIGHT_RATIO) + self.cy;
-
-        // 가상좌표에 따라 캐릭터의 바라보는 위치를 바꾼다.
-        let entities: Vec<(Uuid, Entity)> = self
-            .entities
-            .clone()
-            .into_iter()
-            .filter(|(_, entity)| {
-                entity.type_ == EntityType::PLAYER && entity.movement.as_ref().is_some()
-            })
-            .map(move |(uuid, mut entity)| {
-                let entity_x = entity.movement.as_ref().unwrap().get_pos_x();
-                let entity_y = entity.movement.as_ref().unwrap().get_pos_y();
-                let diff_x = (entity_x - v_x as f64).abs();
-                let diff_y = (entity_y - v_y as f64).abs();
-
-                if diff_x > diff_y {
-                    if entity_x > v_x as f64 {
-                        //self.pc.direction = Direction::Left;
-                        //self.pc.facing = (-1, 0);
-                        entity.movement.as_mut().unwrap().set_facing((-1, 0));
-                    } else if entity_x < v_x as f64 {
-                        //self.pc.direction = Direction::Right;
-                        //self.pc.facing = (1, 0);
-                        entity.movement.as_mut().unwrap().set_facing((1, 0));
-                    }
-                } else if entity_y > v_y as f64 {
-                    //self.pc.direction = Direction::Up;
-                    //self.pc.facing = (0, -1);
-                    entity.movement.as_mut().unwrap().set_facing((0, -1));
-                } else if entity_y < v_y as f64 {
-                    //self.pc.direction = Direction::Down;
-                    //self.pc.facing = (0, 1);
-                    entity.movement.as_mut().unwrap().set_facing((0, 1));
-                }
-                (uuid, entity)
-            })
-            .collect();
-
-        for (uuid, entity) in entities {
-            self.entities.insert(uuid, entity);
-        }
-        if !new_buttons.is_empty() || !old_buttons.is_empty() {
-            // 버튼이 클릭되거나, 놓여짐..
-            /*
-                println!(
-                "X = {:?}, Y = {:?} : {:?} -> {:?}",
-                v_x, v_y, new_buttons, old_buttons
-            );
-                 */
+    fn process_mouse(&mut self, mouse: &Input, _dt: f64) {
+        // 휠로 모든 화면을 확대, 축소한다.
+        let (_, wheel) = mouse.wheel();
+        if wheel != 0 {
+            for view in self.views.iter_mut() {
+                view.camera.set_zoom(view.camera.zoom * 1.1f64.powi(wheel));
+            }
         }
 
-        let entities: Vec<(Uuid, Entity)> = self
-            .entities
-            .clone()
-            .into_iter()
-            .filter(|(_, entity)| {
-                entity.type_ == EntityType::PLAYER && entity.movement.as_ref().is_some()
-            })
-            .map(move |(uuid, mut entity)| {
-                //self.pc.set_deg((v_x as f32, v_y as f32));
+        let (x, y) = mouse.mouse_virtual();
+        let attack = mouse.was_mouse_pressed(sdl2::mouse::MouseButton::Left);
+        let (v_x, v_y) = match self.view_at(x, y) {
+            Some(view) => view.camera.screen_to_world((x, y)),
+            None => return,
+        };
 
-                {
-                    let tmps = entity.clone();
-                    let movement = tmps.movement.as_ref().unwrap();
-                    let direction = facing_to_direction(movement.get_facing());
-                    let animation = tmps.animation.get(&direction).unwrap();
+        // 마우스는 첫번째 플레이어만 움직인다.
+        let player = match self.views.first() {
+            Some(view) => view.player,
+            None => return,
+        };
+        let entity = match self.entities.get_mut(&player) {
+            Some(entity) if entity.movement.is_some() => entity,
+            _ => return,
+        };
 
-                    entity
-                        .attack
-                        .as_mut()
-                        .unwrap()
-                        .set_deg((v_x as f64, v_y as f64), animation);
-                }
+        // 가상좌표에 따라 캐릭터의 바라보는 위치를 바꾼다.
+        let movement = entity.movement.as_mut().unwrap();
+        let entity_x = movement.get_pos_x();
+        let entity_y = movement.get_pos_y();
+        let diff_x = (entity_x - v_x).abs();
+        let diff_y = (entity_y - v_y).abs();
+
+        if diff_x > diff_y {
+            if entity_x > v_x {
+                movement.set_facing((-1, 0));
+            } else if entity_x < v_x {
+                movement.set_facing((1, 0));
+            }
+        } else if entity_y > v_y {
+            movement.set_facing((0, -1));
+        } else if entity_y < v_y {
+            movement.set_facing((0, 1));
+        }
 
-                if new_buttons.contains(&sdl2::mouse::MouseButton::Left) {
-                    entity.attack.as_mut().unwrap().attack();
-                }
-                (uuid, entity)
-            })
-            .collect();
-        for (uuid, entity) in entities {
-            self.entities.insert(uuid, entity);
+        let direction = facing_to_direction(movement.get_facing());
+        if let (Some(attack_box), Some(animation)) =
+            (entity.attack.as_mut(), entity.animation.get(&direction))
+        {
+            attack_box.set_deg((v_x, v_y), animation);
+            if attack {
+                attack_box.attack();
+            }
+        }
+
+        // 공격할 때 화면을 살짝 흔든다.
+        if attack {
+            if let Some(view) = self.view_at(x, y) {
+                view.camera.shake(0.4);
+            }
         }
     }
 
@@ -1230,4 +1313,33 @@ impl<'a> States for GameState<'a> {
 
         result
     }
+
+    fn exit(&mut self) {
+        // 게임을 떠나면 배경 음악도 멈춘다.
+        if self.music.is_some() {
+            sdl2::mixer::Music::halt();
+        }
+    }
+
+    fn resume(&mut self) {
+        // 위의 state 가 있는 동안 뗀 키는 알 수 없으므로 모두 뗀 것으로 한다.
+        self.input.release_all();
+    }
+}
+
+/// hitbox 의 가운데 좌표
+fn hitbox_center(hitbox: &HitboxComponent) -> (f64, f64) {
+    (
+        hitbox.x + hitbox.hx + hitbox.w / 2.0,
+        hitbox.y + hitbox.hy + hitbox.h / 2.0,
+    )
+}
+
+/// points 중에서 (x, y) 에 가장 가까운 점
+fn nearest(points: &[(f64, f64)], (x, y): (f64, f64)) -> Option<(f64, f64)> {
+    points.iter().copied().min_by(|a, b| {
+        let da = (a.0 - x).powi(2) + (a.1 - y).powi(2);
+        let db = (b.0 - x).powi(2) + (b.1 - y).powi(2);
+        da.total_cmp(&db)
+    })
 }
@@ -1,4 +1,5 @@
 use crate::gui::*;
+use crate::input::Input;
 use crate::states::*;
 
 use uuid::Uuid;
@@ -7,7 +8,6 @@ use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 
 use std::collections::HashMap;
-use std::collections::HashSet;
 
 use std::path::Path;
 
@@ -92,6 +92,32 @@ impl<'a> InitState<'a> {
         );
 
         self.buttons.insert("start_button".to_owned(), start_button);
+
+        let builder_button = GuiElement::new(
+            Uuid::new_v4(),
+            ("normal_button".to_string(), "normal_button".to_string()),
+            ("hover_button".to_string(), "hover_button".to_string()),
+            150,
+            100,
+            32,
+            32,
+        );
+
+        self.buttons
+            .insert("builder_button".to_owned(), builder_button);
+
+        let controls_button = GuiElement::new(
+            Uuid::new_v4(),
+            ("normal_button".to_string(), "normal_button".to_string()),
+            ("hover_button".to_string(), "hover_button".to_string()),
+            200,
+            100,
+            32,
+            32,
+        );
+
+        self.buttons
+            .insert("controls_button".to_owned(), controls_button);
     }
 }
 
@@ -127,6 +153,22 @@ impl<'a> States for InitState<'a> {
             self.state_result = StateResult::Push(StateInfo::Game("stage_1"))
         }
 
+        // builder_button이 클릭되었다면 BuilderState로 이동한다.
+        let builder_button = self.buttons.get_mut("builder_button").unwrap();
+
+        if builder_button.is_clicked {
+            builder_button.reset();
+            self.state_result = StateResult::Push(StateInfo::Builder("stage_1"))
+        }
+
+        // controls_button이 클릭되었다면 키 설정 화면으로 이동한다.
+        let controls_button = self.buttons.get_mut("controls_button").unwrap();
+
+        if controls_button.is_clicked {
+            controls_button.reset();
+            self.state_result = StateResult::Push(StateInfo::Controls("controls"))
+        }
+
         StateResult::Default
     }
 
@@ -139,31 +181,11 @@ impl<'a> States for InitState<'a> {
         StateResult::Default
     }
 
-    fn process_mouse(
-        &mut self,
-        x: i32,
-        y: i32,
-        new_buttons: &HashSet<sdl2::mouse::MouseButton>,
-        old_buttons: &HashSet<sdl2::mouse::MouseButton>,
-        _dt: f64,
-    ) {
+    fn process_mouse(&mut self, input: &Input, _dt: f64) {
         // 화면의 버튼을 이용
         for (_k, button) in self.buttons.iter_mut() {
-            button.process_mouse(x, y, new_buttons, old_buttons);
-        }
-
-        // 물리적인 좌표를 가상위치값으로 바꾼다.
-
-        /*
-        let v_x = transform_value(x, REVERSE_WIDTH_RATIO);
-        let v_y = transform_value(y, REVERSE_WIDTH_RATIO);
-        if !new_buttons.is_empty() || !old_buttons.is_empty() {
-            println!(
-                "X = {:?}, Y = {:?} : {:?} -> {:?}",
-                v_x, v_y, new_buttons, old_buttons
-            );
+            button.process_mouse(input);
         }
-        */
     }
 
     fn next_result(&mut self) -> StateResult {
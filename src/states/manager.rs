@@ -0,0 +1,658 @@
+//! state 스택 관리
+//! StateInfo 의 종류로 등록된 생성 함수를 찾아 state 를 만들고
+//! StateResult 에 따라 스택을 바꾸며 enter/exit/pause/resume 을 부른다.
+//! 종류에 화면 전환이 정해져 있다면 전환하는 동안 입력을 막는다.
+
+use crate::constant::*;
+use crate::input::Input;
+use crate::states::transition::*;
+use crate::states::*;
+
+use std::collections::HashMap;
+
+use sdl2::event::Event;
+use sdl2::render::Texture;
+use sdl2::video::WindowContext;
+
+/// state 생성 함수. StateInfo 의 이름을 받는다.
+pub type StateFactory<'a> = Box<dyn FnMut(&'static str) -> Box<dyn States + 'a> + 'a>;
+
+/// 스택에 올라간 state
+struct StateEntry<'a> {
+    kind: &'static str,
+    state: Box<dyn States + 'a>,
+    loaded: bool,
+}
+
+impl<'a> StateEntry<'a> {
+    /// 불러오기를 마쳤는지 확인한다.
+    fn load(&mut self) -> bool {
+        if !self.loaded {
+            self.loaded = self.state.load();
+        }
+        self.loaded
+    }
+}
+
+/// 진행 중인 화면 전환
+/// 스택은 전환을 시작할 때 바뀌고, 내려간 state 는 전환이 끝날 때 exit 한다.
+struct ActiveTransition<'a> {
+    transition: Transition,
+    elapsed: f64,
+    keep: usize,                   // 이전 화면에 남아 있는 아래쪽 state 의 수
+    outgoing: Vec<StateEntry<'a>>, // 내려갔지만 이전 화면으로 그릴 state
+    frame: Option<Texture<'a>>,    // 겹치는 효과의 새 화면. 전환을 시작할 때 한 번 만든다.
+}
+
+pub struct StateManager<'a> {
+    states: Vec<StateEntry<'a>>,
+    registry: HashMap<&'static str, StateFactory<'a>>,
+    transitions: HashMap<&'static str, Transition>,
+    active: Option<ActiveTransition<'a>>,
+    texture_creator: Option<&'a TextureCreator<WindowContext>>,
+}
+
+impl<'a> Default for StateManager<'a> {
+    fn default() -> StateManager<'a> {
+        StateManager::new()
+    }
+}
+
+impl<'a> StateManager<'a> {
+    pub fn new() -> StateManager<'a> {
+        StateManager {
+            states: vec![],
+            registry: HashMap::new(),
+            transitions: HashMap::new(),
+            active: None,
+            texture_creator: None,
+        }
+    }
+
+    /// 겹치는 화면 전환에서 새 화면을 그릴 텍스쳐를 만든다.
+    /// 정하지 않으면 겹치는 효과도 가운데에서 새 화면으로 바꾼다.
+    pub fn set_texture_creator(&mut self, texture_creator: &'a TextureCreator<WindowContext>) {
+        self.texture_creator = Some(texture_creator);
+    }
+
+    /// kind 는 StateInfo::kind() 의 값
+    pub fn register<F>(&mut self, kind: &'static str, factory: F)
+    where
+        F: FnMut(&'static str) -> Box<dyn States + 'a> + 'a,
+    {
+        self.registry.insert(kind, Box::new(factory));
+    }
+
+    /// kind 의 state 가 올라오거나 내려갈 때의 화면 전환
+    pub fn set_transition(&mut self, kind: &'static str, transition: Transition) {
+        self.transitions.insert(kind, transition);
+    }
+
+    fn create(&mut self, info: StateInfo) -> Result<StateEntry<'a>, String> {
+        let factory = self
+            .registry
+            .get_mut(info.kind())
+            .ok_or_else(|| format!("state is not registered : {:?}", info))?;
+
+        Ok(StateEntry {
+            kind: info.kind(),
+            state: factory(info.name()),
+            loaded: false,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// 맨 위의 state
+    pub fn current(&mut self) -> Option<&mut Box<dyn States + 'a>> {
+        self.states.last_mut().map(|entry| &mut entry.state)
+    }
+
+    pub fn is_transitioning(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// kind 에 화면 전환이 있다면 시작한다. 없다면 내려간 state 를 바로 exit 한다.
+    /// 이미 진행 중인 전환은 먼저 끝낸다.
+    fn begin_transition(&mut self, kind: &'static str, keep: usize, outgoing: Vec<StateEntry<'a>>) {
+        self.finish_transition();
+
+        match self.transitions.get(kind) {
+            Some(&transition) => {
+                let frame = match self.texture_creator {
+                    Some(texture_creator) if transition.effect.is_overlay() => texture_creator
+                        .create_texture_target(None, VIRTUAL_WIDTH, VIRTUAL_HEIGHT)
+                        .ok(),
+                    _ => None,
+                };
+
+                self.active = Some(ActiveTransition {
+                    transition,
+                    elapsed: 0.0,
+                    keep,
+                    outgoing,
+                    frame,
+                });
+            }
+            None => {
+                for mut entry in outgoing.into_iter().rev() {
+                    entry.state.exit();
+                }
+            }
+        }
+    }
+
+    fn finish_transition(&mut self) {
+        if let Some(active) = self.active.take() {
+            for mut entry in active.outgoing.into_iter().rev() {
+                entry.state.exit();
+            }
+        }
+    }
+
+    pub fn push(&mut self, info: StateInfo) -> Result<(), String> {
+        let mut entry = self.create(info)?;
+
+        if let Some(current) = self.states.last_mut() {
+            current.state.pause();
+        }
+        entry.state.enter();
+        self.states.push(entry);
+        self.begin_transition(info.kind(), self.states.len() - 1, vec![]);
+
+        Ok(())
+    }
+
+    pub fn pop(&mut self) {
+        if let Some(entry) = self.states.pop() {
+            self.begin_transition(entry.kind, self.states.len(), vec![entry]);
+        }
+        if let Some(current) = self.states.last_mut() {
+            current.state.resume();
+        }
+    }
+
+    /// 맨 위의 state 를 바꾼다. 아래의 state 는 멈춘 그대로 둔다.
+    pub fn trans(&mut self, info: StateInfo) -> Result<(), String> {
+        let mut entry = self.create(info)?;
+
+        let outgoing: Vec<_> = self.states.pop().into_iter().collect();
+        self.begin_transition(info.kind(), self.states.len(), outgoing);
+        entry.state.enter();
+        self.states.push(entry);
+
+        Ok(())
+    }
+
+    /// 모든 state 를 위에서부터 내리고 새 state 하나만 남긴다.
+    pub fn replace_all(&mut self, info: StateInfo) -> Result<(), String> {
+        let mut entry = self.create(info)?;
+
+        let outgoing = std::mem::take(&mut self.states);
+        self.begin_transition(info.kind(), 0, outgoing);
+        entry.state.enter();
+        self.states.push(entry);
+
+        Ok(())
+    }
+
+    pub fn apply(&mut self, result: StateResult) -> Result<(), String> {
+        match result {
+            StateResult::Push(info) => self.push(info),
+            StateResult::Trans(info) => self.trans(info),
+            StateResult::ReplaceAll(info) => self.replace_all(info),
+            StateResult::Pop => {
+                self.pop();
+                Ok(())
+            }
+            StateResult::Default => Ok(()),
+        }
+    }
+
+    /// 화면 전환 중에는 입력을 넘기지 않는다.
+    pub fn process_event(&mut self, event: &Event, dt: f64) {
+        if self.is_transitioning() {
+            return;
+        }
+        if let Some(entry) = self.states.last_mut() {
+            entry.state.process_event(event, dt);
+        }
+    }
+
+    pub fn process_mouse(&mut self, input: &Input, dt: f64) {
+        if self.is_transitioning() {
+            return;
+        }
+        if let Some(entry) = self.states.last_mut() {
+            entry.state.process_mouse(input, dt);
+        }
+    }
+
+    /// 위에서부터 처음 만나는 막는(blocking) state 까지 update 한다.
+    /// 입력은 맨 위의 state 만 받는다.
+    /// 화면 전환 중에는 시간만 흐르고, 새 state 가 불러오기를 마칠 때까지
+    /// 전환은 hold_at 에서 기다린다.
+    pub fn update(&mut self, dt: f64) {
+        if let Some(active) = self.active.as_mut() {
+            let loaded = match self.states.last_mut() {
+                Some(entry) => entry.load(),
+                None => true,
+            };
+            let transition = active.transition;
+
+            active.elapsed += dt;
+            if !loaded {
+                let hold = transition.effect.hold_at() * transition.duration;
+                active.elapsed = active.elapsed.min(hold);
+            } else if active.elapsed >= transition.duration {
+                self.finish_transition();
+            }
+            return;
+        }
+
+        for entry in self.states.iter_mut().rev() {
+            if entry.load() {
+                entry.state.update(dt);
+            }
+            if entry.state.is_blocking() {
+                break;
+            }
+        }
+    }
+
+    /// 그려질 수 있는 state 에 고정 간격 update 사이의 비율을 넘긴다.
+    pub fn interpolate(&mut self, alpha: f64) {
+        let outgoing = self
+            .active
+            .iter_mut()
+            .flat_map(|active| active.outgoing.iter_mut());
+
+        for entry in self.states.iter_mut().chain(outgoing) {
+            entry.state.interpolate(alpha);
+        }
+    }
+
+    /// 위에서부터 투명한 state 를 지나 처음 만나는 불투명한 state 부터
+    /// 아래에서 위로 그린다.
+    /// 화면 전환 중에는 이전 화면과 새 화면을 효과에 맞추어 그린다.
+    pub fn render(&mut self, canvas: &mut WindowCanvas) {
+        let active = match self.active.as_mut() {
+            Some(active) => active,
+            None => {
+                for entry in self.states.iter().skip(self.render_from()) {
+                    entry.state.render(canvas);
+                }
+                return;
+            }
+        };
+
+        let transition = active.transition;
+        let progress = if transition.duration > 0.0 {
+            (active.elapsed / transition.duration).min(1.0)
+        } else {
+            1.0
+        };
+        let old: Vec<_> = self.states[..active.keep]
+            .iter()
+            .chain(active.outgoing.iter())
+            .collect();
+        let new: Vec<_> = self.states.iter().collect();
+
+        // 겹치는 효과는 새 화면을 텍스쳐에 그려서 이전 화면 위에 겹친다.
+        let overlay = transition.effect.is_overlay();
+        let frame = match active.frame.as_mut() {
+            Some(texture) if overlay && canvas.render_target_supported() => {
+                let drawn = canvas.with_texture_canvas(texture, |texture_canvas| {
+                    texture_canvas.set_draw_color(Color::RGBA(0, 0, 0, 255));
+                    texture_canvas.clear();
+                    render_list(texture_canvas, &new);
+                });
+                drawn.ok().map(|_| texture)
+            }
+            _ => None,
+        };
+
+        match frame {
+            Some(texture) => {
+                render_list(canvas, &old);
+                transition.render(canvas, progress, Some(texture));
+            }
+            None => {
+                // 텍스쳐를 쓸 수 없다면 겹치는 효과는 가운데에서 새 화면으로 바꾼다.
+                let shows_old = if overlay {
+                    progress < 0.5
+                } else {
+                    transition.shows_old(progress)
+                };
+                if shows_old {
+                    render_list(canvas, &old);
+                } else {
+                    render_list(canvas, &new);
+                }
+                transition.render(canvas, progress, None);
+            }
+        }
+    }
+
+    /// 그리기 시작할 state 의 index
+    fn render_from(&self) -> usize {
+        visible_from(&self.states.iter().collect::<Vec<_>>())
+    }
+
+    /// 맨 위 state 의 결과를 스택에 반영한다.
+    /// 화면 전환 중에는 결과를 꺼내지 않고 전환이 끝날 때까지 둔다.
+    pub fn handle_result(&mut self) -> Result<(), String> {
+        if self.is_transitioning() {
+            return Ok(());
+        }
+
+        let result = match self.states.last_mut() {
+            Some(entry) => entry.state.next_result(),
+            None => return Ok(()),
+        };
+
+        self.apply(result)
+    }
+}
+
+/// 위에서부터 투명한 state 를 지나 처음 만나는 불투명한 state 의 index
+fn visible_from(states: &[&StateEntry]) -> usize {
+    states
+        .iter()
+        .rposition(|entry| !entry.state.is_transparent())
+        .unwrap_or(0)
+}
+
+fn render_list(canvas: &mut WindowCanvas, states: &[&StateEntry]) {
+    for entry in states.iter().skip(visible_from(states)) {
+        entry.state.render(canvas);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    struct TestState {
+        name: &'static str,
+        log: Log,
+        result: StateResult,
+        transparent: bool,
+        blocking: bool,
+        loading: u32, // 불러오기를 마칠 때까지 남은 frame
+    }
+
+    impl TestState {
+        fn log(&self, hook: &str) {
+            self.log
+                .borrow_mut()
+                .push(format!("{} {}", hook, self.name));
+        }
+    }
+
+    impl States for TestState {
+        fn process_event(&mut self, _event: &Event, _dt: f64) -> StateResult {
+            self.log("event");
+            StateResult::Default
+        }
+
+        fn process_mouse(&mut self, _input: &Input, _dt: f64) {}
+
+        fn update(&mut self, _dt: f64) -> StateResult {
+            self.log("update");
+            StateResult::Default
+        }
+
+        fn render(&self, _canvas: &mut WindowCanvas) -> StateResult {
+            StateResult::Default
+        }
+
+        fn next_result(&mut self) -> StateResult {
+            std::mem::replace(&mut self.result, StateResult::Default)
+        }
+
+        fn enter(&mut self) {
+            self.log("enter");
+        }
+
+        fn exit(&mut self) {
+            self.log("exit");
+        }
+
+        fn pause(&mut self) {
+            self.log("pause");
+        }
+
+        fn resume(&mut self) {
+            self.log("resume");
+        }
+
+        fn load(&mut self) -> bool {
+            if self.loading == 0 {
+                return true;
+            }
+            self.loading -= 1;
+            false
+        }
+
+        fn is_transparent(&self) -> bool {
+            self.transparent
+        }
+
+        fn is_blocking(&self) -> bool {
+            self.blocking
+        }
+    }
+
+    fn manager(log: &Log) -> StateManager<'static> {
+        let mut manager = StateManager::new();
+        for &kind in ["init", "game", "pause"].iter() {
+            let log = log.clone();
+            manager.register(kind, move |name| {
+                Box::new(TestState {
+                    name,
+                    log: log.clone(),
+                    result: StateResult::Default,
+                    // hud 는 게임을 멈추지 않는 겹쳐진 화면
+                    transparent: kind == "pause",
+                    blocking: name != "hud",
+                    loading: if name == "slow" { 2 } else { 0 },
+                })
+            });
+        }
+        manager
+    }
+
+    fn take(log: &Log) -> Vec<String> {
+        log.borrow_mut().drain(..).collect()
+    }
+
+    #[test]
+    fn push_and_pop() {
+        let log = Log::default();
+        let mut manager = manager(&log);
+
+        manager.push(StateInfo::Init("title")).unwrap();
+        manager.push(StateInfo::Game("stage_1")).unwrap();
+        assert_eq!(manager.len(), 2);
+        assert_eq!(
+            take(&log),
+            vec!["enter title", "pause title", "enter stage_1"]
+        );
+
+        // 맨 위의 state 만 update 된다.
+        manager.update(0.1);
+        assert_eq!(take(&log), vec!["update stage_1"]);
+
+        manager.apply(StateResult::Pop).unwrap();
+        assert_eq!(take(&log), vec!["exit stage_1", "resume title"]);
+
+        manager.pop();
+        assert!(manager.is_empty());
+        assert_eq!(take(&log), vec!["exit title"]);
+    }
+
+    #[test]
+    fn trans_and_replace_all() {
+        let log = Log::default();
+        let mut manager = manager(&log);
+
+        manager.push(StateInfo::Init("title")).unwrap();
+        manager.push(StateInfo::Game("stage_1")).unwrap();
+        take(&log);
+
+        manager
+            .apply(StateResult::Trans(StateInfo::Game("stage_2")))
+            .unwrap();
+        assert_eq!(manager.len(), 2);
+        assert_eq!(take(&log), vec!["exit stage_1", "enter stage_2"]);
+
+        manager
+            .apply(StateResult::ReplaceAll(StateInfo::Init("menu")))
+            .unwrap();
+        assert_eq!(manager.len(), 1);
+        assert_eq!(take(&log), vec!["exit stage_2", "exit title", "enter menu"]);
+    }
+
+    #[test]
+    fn result_from_state() {
+        let log = Log::default();
+        let mut manager = manager(&log);
+
+        manager.push(StateInfo::Init("title")).unwrap();
+        manager.handle_result().unwrap();
+        assert_eq!(manager.len(), 1);
+
+        // 등록되지 않은 state 는 스택을 바꾸지 않는다.
+        assert!(manager.push(StateInfo::Builder("stage_1")).is_err());
+        assert_eq!(manager.len(), 1);
+        take(&log);
+
+        // 어떤 state 의 next_result 를 흉내내기 위해 직접 Push 를 돌려주게 한다.
+        let mut state = TestState {
+            name: "pushing",
+            log: log.clone(),
+            result: StateResult::Push(StateInfo::Game("stage_1")),
+            transparent: false,
+            blocking: true,
+            loading: 0,
+        };
+        state.enter();
+        manager.states.push(StateEntry {
+            kind: "init",
+            state: Box::new(state),
+            loaded: true,
+        });
+        manager.handle_result().unwrap();
+        assert_eq!(manager.len(), 3);
+        assert_eq!(
+            take(&log),
+            vec!["enter pushing", "pause pushing", "enter stage_1"]
+        );
+    }
+
+    #[test]
+    fn layered_states() {
+        let log = Log::default();
+        let mut manager = manager(&log);
+
+        manager.push(StateInfo::Init("title")).unwrap();
+        manager.push(StateInfo::Game("stage_1")).unwrap();
+        assert_eq!(manager.render_from(), 1);
+
+        // 투명한 pause 아래의 게임도 그리지만 update 는 pause 에서 멈춘다.
+        manager.push(StateInfo::Pause("pause")).unwrap();
+        manager.push(StateInfo::Pause("hud")).unwrap();
+        assert_eq!(manager.render_from(), 1);
+        take(&log);
+
+        manager.update(0.1);
+        assert_eq!(take(&log), vec!["update hud", "update pause"]);
+
+        manager.pop();
+        manager.pop();
+        take(&log);
+        manager.update(0.1);
+        assert_eq!(take(&log), vec!["update stage_1"]);
+        assert_eq!(manager.render_from(), 1);
+    }
+
+    #[test]
+    fn transition_blocks_input() {
+        let log = Log::default();
+        let mut manager = manager(&log);
+        manager.set_transition(
+            "game",
+            Transition::new(TransitionEffect::Fade(Color::RGB(0, 0, 0)), 1.0),
+        );
+
+        manager.push(StateInfo::Init("title")).unwrap();
+        manager.push(StateInfo::Game("stage_1")).unwrap();
+        assert!(manager.is_transitioning());
+        take(&log);
+
+        // 전환 중에는 입력도 update 도 없다.
+        manager.process_event(&Event::Quit { timestamp: 0 }, 0.5);
+        manager.update(0.5);
+        assert!(take(&log).is_empty());
+
+        manager.update(0.6);
+        assert!(!manager.is_transitioning());
+        manager.process_event(&Event::Quit { timestamp: 0 }, 0.1);
+        manager.update(0.1);
+        assert_eq!(take(&log), vec!["event stage_1", "update stage_1"]);
+    }
+
+    #[test]
+    fn transition_waits_for_loading() {
+        let log = Log::default();
+        let mut manager = manager(&log);
+        manager.set_transition(
+            "game",
+            Transition::new(TransitionEffect::Fade(Color::RGB(0, 0, 0)), 1.0),
+        );
+
+        manager.push(StateInfo::Game("slow")).unwrap();
+
+        // 불러오는 동안 화면이 다 덮인 곳에서 멈춘다.
+        manager.update(0.4);
+        manager.update(0.4);
+        let elapsed = manager.active.as_ref().unwrap().elapsed;
+        assert!((elapsed - 0.5).abs() < 1e-9);
+
+        manager.update(0.4);
+        assert!(manager.is_transitioning());
+        manager.update(0.2);
+        assert!(!manager.is_transitioning());
+    }
+
+    #[test]
+    fn transition_defers_exit() {
+        let log = Log::default();
+        let mut manager = manager(&log);
+        manager.set_transition("pause", Transition::new(TransitionEffect::Crossfade, 0.2));
+
+        manager.push(StateInfo::Init("title")).unwrap();
+        manager.push(StateInfo::Pause("pause")).unwrap();
+        manager.update(0.3);
+        take(&log);
+
+        // 내려간 state 는 전환이 끝날 때까지 이전 화면으로 남는다.
+        manager.pop();
+        assert_eq!(manager.len(), 1);
+        assert_eq!(take(&log), vec!["resume title"]);
+        manager.update(0.3);
+        assert_eq!(take(&log), vec!["exit pause"]);
+        assert!(!manager.is_transitioning());
+    }
+}
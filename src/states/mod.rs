@@ -1,8 +1,7 @@
+use crate::input::Input;
 use crate::texture_manager::*;
 use crate::*;
 
-use std::collections::HashSet;
-
 use sdl2::pixels::Color;
 use sdl2::render::TextureCreator;
 use sdl2::render::WindowCanvas;
@@ -12,14 +11,8 @@ pub trait States {
     fn process_event(&mut self, event: &sdl2::event::Event, dt: f64) -> StateResult;
 
     /// 마우스 입력부분만 여기서 처리
-    fn process_mouse(
-        &mut self,
-        x: i32,
-        y: i32,
-        new_buttons: &HashSet<sdl2::mouse::MouseButton>,
-        old_buttons: &HashSet<sdl2::mouse::MouseButton>,
-        dt: f64,
-    );
+    /// 마우스 좌표는 input.mouse_virtual() 의 가상화면 좌표를 쓴다.
+    fn process_mouse(&mut self, input: &Input, dt: f64);
 
     /// state 값을 변경시키는 부분에 대한 처리
     fn update(&mut self, dt: f64) -> StateResult;
@@ -27,11 +20,46 @@ pub trait States {
     /// 화면에 노출시키기
     fn render(&self, canvas: &mut WindowCanvas) -> StateResult;
 
+    /// 그리기 전에 지난 update 와 다음 update 사이의 비율(0.0 ~ 1.0)을 받는다.
+    fn interpolate(&mut self, _alpha: f64) {}
+
     /// main loop에서 States의 다음 상태를 요청할 때
     fn next_result(&mut self) -> StateResult;
+
+    /// 스택에 올라갈 때
+    fn enter(&mut self) {}
+
+    /// 스택에서 내려갈 때
+    fn exit(&mut self) {}
+
+    /// 위에 다른 state 가 올라왔을 때
+    fn pause(&mut self) {}
+
+    /// 위의 state 가 내려가서 다시 맨 위가 되었을 때
+    fn resume(&mut self) {}
+
+    /// 처음 update 하기 전에 불러오기를 마쳤는지 물어본다.
+    /// false 를 돌려주는 동안 다음 frame 에 다시 부르며, 화면 전환은 덮인 채로 기다린다.
+    fn load(&mut self) -> bool {
+        true
+    }
+
+    /// 투명하다면 아래의 state 도 그린다.
+    fn is_transparent(&self) -> bool {
+        false
+    }
+
+    /// 막는다면 아래의 state 는 update 하지 않는다.
+    fn is_blocking(&self) -> bool {
+        true
+    }
 }
 
 pub mod builder_state;
+pub mod controls_state;
 pub mod game_state;
 pub mod init_state;
+pub mod manager;
+pub mod pause_state;
 pub mod timer_state;
+pub mod transition;
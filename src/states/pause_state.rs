@@ -0,0 +1,125 @@
+use crate::constant::*;
+use crate::gui::*;
+use crate::input::action::{self, ActionMap};
+use crate::input::Input;
+use crate::settings::*;
+use crate::states::*;
+
+use uuid::Uuid;
+
+use sdl2::rect::Rect;
+
+use std::path::Path;
+
+use sdl2::render::WindowCanvas;
+use sdl2::video::WindowContext;
+
+/// 일시 정지 화면
+/// 아래의 state 를 어둡게 그대로 보이고, 아래의 state 는 update 하지 않는다.
+pub struct PauseState<'a> {
+    texture_manager: Option<TextureManager<'a>>,
+    label: Option<GuiElement>,
+    input: Input,
+    actions: ActionMap,
+    state_result: StateResult,
+}
+
+impl<'a> Default for PauseState<'a> {
+    fn default() -> PauseState<'a> {
+        PauseState::new()
+    }
+}
+
+impl<'a> PauseState<'a> {
+    pub fn new() -> PauseState<'a> {
+        PauseState {
+            texture_manager: None,
+            label: None,
+            input: Input::default(),
+            actions: G_SETTINGS.read().unwrap().action_map(),
+            state_result: StateResult::Default,
+        }
+    }
+
+    pub fn init(
+        &mut self,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        font_context: &'a sdl2::ttf::Sdl2TtfContext,
+    ) {
+        let font = font_context
+            .load_font(Path::new("resources/hackr.ttf"), 24)
+            .unwrap();
+        let surface = font
+            .render("PAUSED (P or Esc to resume)")
+            .blended(Color::RGBA(255, 255, 255, 255))
+            .unwrap();
+        let texture = texture_creator
+            .create_texture_from_surface(&surface)
+            .unwrap();
+
+        let mut texture_manager = TextureManager::new();
+        texture_manager.add_texture("label_pause".to_owned(), texture);
+        self.texture_manager = Some(texture_manager);
+
+        // 가상화면 가운데에 놓는다.
+        self.label = Some(GuiElement::new(
+            Uuid::new_v4(),
+            ("label_pause".to_owned(), "pause".to_owned()),
+            ("label_pause".to_owned(), "pause".to_owned()),
+            (VIRTUAL_WIDTH as i32 - surface.width() as i32) / 2,
+            (VIRTUAL_HEIGHT as i32 - surface.height() as i32) / 2,
+            surface.width(),
+            surface.height(),
+        ));
+    }
+}
+
+impl<'a> States for PauseState<'a> {
+    fn process_event(&mut self, event: &sdl2::event::Event, _dt: f64) -> StateResult {
+        self.input.process_event(event);
+
+        StateResult::Default
+    }
+
+    fn process_mouse(&mut self, _input: &Input, _dt: f64) {}
+
+    fn update(&mut self, _dt: f64) -> StateResult {
+        if self.actions.pressed(&self.input, action::CANCEL)
+            || self.actions.pressed(&self.input, action::PAUSE)
+        {
+            self.state_result = StateResult::Pop;
+        }
+
+        self.input.begin_new_frame();
+
+        StateResult::Default
+    }
+
+    fn render(&self, canvas: &mut WindowCanvas) -> StateResult {
+        // 아래 state 를 어둡게 덮는다.
+        let blend_mode = canvas.blend_mode();
+        canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 160));
+        canvas
+            .fill_rect(Rect::new(0, 0, VIRTUAL_WIDTH, VIRTUAL_HEIGHT))
+            .unwrap();
+        canvas.set_blend_mode(blend_mode);
+
+        if let Some(label) = self.label.as_ref() {
+            label.render(canvas, self.texture_manager.as_ref().unwrap());
+        }
+
+        StateResult::Default
+    }
+
+    fn next_result(&mut self) -> StateResult {
+        let result = self.state_result;
+        self.state_result = StateResult::Default;
+
+        result
+    }
+
+    fn is_transparent(&self) -> bool {
+        true
+    }
+}
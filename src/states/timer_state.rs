@@ -1,3 +1,4 @@
+use crate::input::Input;
 use crate::states::*;
 use crate::timer::*;
 
@@ -5,7 +6,6 @@ use sdl2::gfx::primitives::DrawRenderer;
 use sdl2::pixels::Color;
 
 use std::collections::HashMap;
-use std::collections::HashSet;
 use std::sync::{Arc, RwLock};
 
 use std::path::Path;
@@ -123,15 +123,7 @@ impl<'a> States for TimerState<'a> {
         StateResult::Default
     }
 
-    fn process_mouse(
-        &mut self,
-        _x: i32,
-        _y: i32,
-        _new_buttons: &HashSet<sdl2::mouse::MouseButton>,
-        _old_buttons: &HashSet<sdl2::mouse::MouseButton>,
-        _dt: f64,
-    ) {
-    }
+    fn process_mouse(&mut self, _input: &Input, _dt: f64) {}
 
     fn next_result(&mut self) -> StateResult {
         self.state_result
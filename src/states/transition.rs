@@ -0,0 +1,223 @@
+//! state 가 바뀔 때의 화면 전환 효과
+//! 진행 정도(0.0 ~ 1.0)는 tween 의 easing 함수로 바꾸어 쓴다.
+
+use crate::constant::*;
+use crate::tween;
+use crate::Direction;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, WindowCanvas};
+
+/// 화면 전환 효과
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransitionEffect {
+    /// 한 색으로 덮었다가 걷어낸다.
+    Fade(Color),
+    /// 이전 화면 위로 새 화면이 서서히 나타난다.
+    Crossfade,
+    /// 새 화면이 주어진 방향으로 밀려 들어온다.
+    Wipe(Direction),
+    /// 가운데로 원이 닫혔다가 새 화면에서 다시 열린다.
+    Iris,
+}
+
+impl TransitionEffect {
+    /// 새 state 를 불러오는 동안 멈추어 기다리는 진행 정도
+    /// 화면을 덮는 효과는 다 덮었을 때, 나머지는 시작할 때 기다린다.
+    pub fn hold_at(&self) -> f64 {
+        match self {
+            TransitionEffect::Fade(_) | TransitionEffect::Iris => 0.5,
+            TransitionEffect::Crossfade | TransitionEffect::Wipe(_) => 0.0,
+        }
+    }
+
+    /// 새 화면을 텍스쳐에 그려서 이전 화면 위에 겹치는 효과인가?
+    pub fn is_overlay(&self) -> bool {
+        matches!(
+            self,
+            TransitionEffect::Crossfade | TransitionEffect::Wipe(_)
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Transition {
+    pub effect: TransitionEffect,
+    pub duration: f64,                         // 초
+    pub easing: fn(f64, f64, f64, f64) -> f64, // tween 의 easing 함수
+}
+
+impl Transition {
+    pub fn new(effect: TransitionEffect, duration: f64) -> Transition {
+        Transition {
+            effect,
+            duration,
+            easing: tween::in_out_quad,
+        }
+    }
+
+    /// 0.0 ~ 1.0 의 값을 easing 으로 바꾼다.
+    pub fn ease(&self, x: f64) -> f64 {
+        (self.easing)(x.clamp(0.0, 1.0), 0.0, 1.0, 1.0).clamp(0.0, 1.0)
+    }
+
+    /// 진행 정도 p 에서 이전 화면을 보여야 하는가?
+    /// 겹치는 효과는 이전 화면을 늘 먼저 그린다.
+    pub fn shows_old(&self, p: f64) -> bool {
+        self.effect.is_overlay() || p < 0.5
+    }
+
+    /// 이전 화면이나 새 화면을 그린 뒤 그 위에 덮을 것을 그린다.
+    /// 겹치는 효과라면 new_frame 에 새 화면을 그려서 넘긴다.
+    pub fn render(
+        &self,
+        canvas: &mut WindowCanvas,
+        p: f64,
+        new_frame: Option<&mut sdl2::render::Texture>,
+    ) {
+        let blend_mode = canvas.blend_mode();
+        canvas.set_blend_mode(BlendMode::Blend);
+
+        match self.effect {
+            TransitionEffect::Fade(color) => {
+                let alpha = if p < 0.5 {
+                    self.ease(p * 2.0)
+                } else {
+                    1.0 - self.ease((p - 0.5) * 2.0)
+                };
+                canvas.set_draw_color(Color::RGBA(
+                    color.r,
+                    color.g,
+                    color.b,
+                    (alpha * color.a as f64) as u8,
+                ));
+                canvas
+                    .fill_rect(Rect::new(0, 0, VIRTUAL_WIDTH, VIRTUAL_HEIGHT))
+                    .unwrap();
+            }
+            TransitionEffect::Iris => {
+                let max_radius =
+                    ((VIRTUAL_WIDTH.pow(2) + VIRTUAL_HEIGHT.pow(2)) as f64).sqrt() / 2.0;
+                let radius = if p < 0.5 {
+                    (1.0 - self.ease(p * 2.0)) * max_radius
+                } else {
+                    self.ease((p - 0.5) * 2.0) * max_radius
+                };
+                canvas.set_draw_color(Color::RGBA(0, 0, 0, 255));
+                for rect in iris_mask(radius) {
+                    canvas.fill_rect(rect).unwrap();
+                }
+            }
+            TransitionEffect::Crossfade => {
+                if let Some(texture) = new_frame {
+                    texture.set_blend_mode(BlendMode::Blend);
+                    texture.set_alpha_mod((self.ease(p) * 255.0) as u8);
+                    canvas.copy(texture, None, None).unwrap();
+                }
+            }
+            TransitionEffect::Wipe(direction) => {
+                if let Some(texture) = new_frame {
+                    texture.set_blend_mode(BlendMode::None);
+                    if let Some(rect) = wipe_rect(direction, self.ease(p)) {
+                        canvas.copy(texture, rect, rect).unwrap();
+                    }
+                }
+            }
+        }
+
+        canvas.set_blend_mode(blend_mode);
+    }
+}
+
+/// 가상화면 가운데의 반지름 radius 인 원 바깥을 덮는 사각형들
+/// 한 줄마다 원의 왼쪽, 오른쪽을 덮는다.
+pub fn iris_mask(radius: f64) -> Vec<Rect> {
+    let (w, h) = (VIRTUAL_WIDTH as i32, VIRTUAL_HEIGHT as i32);
+    let (cx, cy) = (w as f64 / 2.0, h as f64 / 2.0);
+    let mut rects = vec![];
+
+    for y in 0..h {
+        let dy = y as f64 + 0.5 - cy;
+        let half = if dy.abs() < radius {
+            (radius * radius - dy * dy).sqrt()
+        } else {
+            0.0
+        };
+        let left = ((cx - half).round() as i32).clamp(0, w);
+        let right = ((cx + half).round() as i32).clamp(0, w);
+
+        if left > 0 {
+            rects.push(Rect::new(0, y, left as u32, 1));
+        }
+        if right < w {
+            rects.push(Rect::new(right, y, (w - right) as u32, 1));
+        }
+    }
+
+    rects
+}
+
+/// 새 화면이 드러난 영역. 진행 정도 p 는 0.0 ~ 1.0
+/// Direction::Right 라면 왼쪽에서 오른쪽으로 드러난다.
+pub fn wipe_rect(direction: Direction, p: f64) -> Option<Rect> {
+    let (w, h) = (VIRTUAL_WIDTH, VIRTUAL_HEIGHT);
+    let shown_w = (w as f64 * p).round() as u32;
+    let shown_h = (h as f64 * p).round() as u32;
+
+    // Rect 는 크기 0 을 1 로 바꾸므로 아무것도 드러나지 않았다면 None
+    match direction {
+        Direction::Left | Direction::IdleLeft if shown_w > 0 => {
+            Some(Rect::new((w - shown_w) as i32, 0, shown_w, h))
+        }
+        Direction::Up | Direction::IdleUp if shown_h > 0 => {
+            Some(Rect::new(0, (h - shown_h) as i32, w, shown_h))
+        }
+        Direction::Down | Direction::IdleDown if shown_h > 0 => Some(Rect::new(0, 0, w, shown_h)),
+        Direction::Left
+        | Direction::IdleLeft
+        | Direction::Up
+        | Direction::IdleUp
+        | Direction::Down
+        | Direction::IdleDown => None,
+        _ if shown_w > 0 => Some(Rect::new(0, 0, shown_w, h)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wipe_and_iris() {
+        assert_eq!(wipe_rect(Direction::Right, 0.0), None);
+        assert_eq!(
+            wipe_rect(Direction::Right, 0.5),
+            Some(Rect::new(0, 0, VIRTUAL_WIDTH / 2, VIRTUAL_HEIGHT))
+        );
+        assert_eq!(
+            wipe_rect(Direction::Up, 1.0),
+            Some(Rect::new(0, 0, VIRTUAL_WIDTH, VIRTUAL_HEIGHT))
+        );
+
+        // 다 닫힌 원은 화면 전체를 덮고, 다 열린 원은 아무것도 덮지 않는다.
+        let covered: u32 = iris_mask(0.0).iter().map(|r| r.width() * r.height()).sum();
+        assert_eq!(covered, VIRTUAL_WIDTH * VIRTUAL_HEIGHT);
+        let max_radius = ((VIRTUAL_WIDTH.pow(2) + VIRTUAL_HEIGHT.pow(2)) as f64).sqrt() / 2.0;
+        assert!(iris_mask(max_radius + 1.0).is_empty());
+    }
+
+    #[test]
+    fn eased_progress() {
+        let transition = Transition::new(TransitionEffect::Fade(Color::RGB(0, 0, 0)), 1.0);
+        assert_eq!(transition.ease(0.0), 0.0);
+        assert_eq!(transition.ease(0.5), 0.5);
+        assert_eq!(transition.ease(2.0), 1.0);
+        assert!(transition.shows_old(0.4));
+        assert!(!transition.shows_old(0.6));
+        assert_eq!(transition.effect.hold_at(), 0.5);
+        assert_eq!(TransitionEffect::Iris.hold_at(), 0.5);
+        assert_eq!(TransitionEffect::Crossfade.hold_at(), 0.0);
+    }
+}
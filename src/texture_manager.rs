@@ -1,5 +1,3 @@
-use crate::constant::*;
-use crate::*;
 use sdl2::image::LoadTexture;
 use sdl2::rect::Point;
 use sdl2::rect::Rect;
@@ -93,7 +91,7 @@ impl Sprite {
             .copy_ex(
                 texture,
                 Some(self.src),
-                Some(transform_rect(&self.dest, WIDTH_RATIO, HEIGHT_RATIO)),
+                Some(self.dest),
                 self.rotation,
                 self.orientation,
                 self.flip_h,
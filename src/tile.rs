@@ -22,34 +22,43 @@ pub struct TileAtlas {
 
 impl TileAtlas {
     pub fn new(texture: &Texture, first_gid: u32, w: u32, h: u32) -> TileAtlas {
+        TileAtlas::new_with_spacing(texture, first_gid, w, h, 0, 0)
+    }
+
+    /// 타일셋 이미지의 가장자리 여백(margin)과 타일 사이 간격(spacing)을 고려한 atlas
+    pub fn new_with_spacing(
+        texture: &Texture,
+        first_gid: u32,
+        w: u32,
+        h: u32,
+        margin: u32,
+        spacing: u32,
+    ) -> TileAtlas {
         let query: TextureQuery = texture.query();
 
         let mut atlas: Vec<(u32, u32, u32, u32)> = vec![];
-        let x_size: u32 = query.width / w;
-        let y_size: u32 = query.height / h;
+        let x_size: u32 = (query.width - margin + spacing) / (w + spacing);
+        let y_size: u32 = (query.height - margin + spacing) / (h + spacing);
 
         let mut u_acc: u32;
-        let mut v_acc: u32 = 0;
+        let mut v_acc: u32 = margin;
         for _ in 0..y_size {
-            u_acc = 0;
+            u_acc = margin;
             let next_v_acc = v_acc + h;
             for _ in 0..x_size {
                 let next_u_acc = u_acc + w;
                 atlas.push((u_acc, v_acc, next_u_acc, next_v_acc));
-                u_acc = next_u_acc;
+                u_acc = next_u_acc + spacing;
             }
-            v_acc = next_v_acc;
+            v_acc = next_v_acc + spacing;
         }
 
-        let hor_length: u32 = query.width / w;
-        let ver_length: u32 = query.height / h;
-
         TileAtlas {
             first_gid,
             texture_width: query.width,
             texture_height: query.height,
-            hor_length,
-            ver_length,
+            hor_length: x_size,
+            ver_length: y_size,
             atlas,
         }
     }
@@ -65,3 +74,157 @@ impl TileAtlas {
         )
     }
 }
+
+/// gid 하나를 그리는 데 필요한 정보
+/// texture : 지도 텍스쳐 인덱스, src : 텍스쳐 영역
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileSource {
+    pub texture: usize,
+    pub src: Rect,
+}
+
+/// Tiled 의 타일 뒤집기 정보
+/// Tiled는 대각선 뒤집기(d)를 먼저, 가로(h), 세로(v) 뒤집기를 나중에 적용한다.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TileFlip {
+    pub h: bool,
+    pub v: bool,
+    pub d: bool,
+}
+
+impl TileFlip {
+    pub fn from_layer_tile(tile: &tiled::LayerTile) -> TileFlip {
+        TileFlip {
+            h: tile.flip_h,
+            v: tile.flip_v,
+            d: tile.flip_d,
+        }
+    }
+
+    /// SDL copy_ex 에 넘길 (회전 각도, flip_horizontal, flip_vertical)
+    /// SDL은 뒤집은 후 시계방향으로 회전하므로
+    /// 대각선 뒤집기는 세로로 뒤집은 후 90도 회전하는 것과 같다.
+    pub fn to_copy_ex(&self) -> (f64, bool, bool) {
+        match (self.d, self.h, self.v) {
+            (false, h, v) => (0.0, h, v),
+            (true, false, false) => (90.0, false, true),
+            (true, true, false) => (90.0, false, false),
+            (true, false, true) => (270.0, false, false),
+            (true, true, true) => (90.0, true, false),
+        }
+    }
+
+    /// 화면에 차지할 영역(dest)에 맞춰 copy_ex 에 넘길 영역을 구한다.
+    /// 90도 회전할 때는 영역의 중심을 유지한 채 가로 세로를 바꾼다.
+    pub fn draw_rect(&self, dest: Rect) -> Rect {
+        if self.d && dest.width() != dest.height() {
+            Rect::from_center(dest.center(), dest.height(), dest.width())
+        } else {
+            dest
+        }
+    }
+}
+
+/// 타일 애니메이션
+/// Tiled 타일셋의 <animation> 프레임 목록이다.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileAnimation {
+    /// (gid, 초 단위 지속시간)
+    pub frames: Vec<(u32, f64)>,
+    /// 한 바퀴 도는 데 걸리는 시간
+    pub total: f64,
+}
+
+impl TileAnimation {
+    /// 타일셋의 프레임 목록으로 애니메이션을 만든다.
+    /// Tiled의 frame은 타일셋 안의 id 와 1000분의 1초 단위의 지속시간을 가진다.
+    pub fn new(first_gid: u32, frames: &[tiled::Frame]) -> TileAnimation {
+        let frames: Vec<(u32, f64)> = frames
+            .iter()
+            .map(|frame| (first_gid + frame.tile_id, frame.duration as f64 / 1000.))
+            .collect();
+        let total = frames.iter().map(|(_, duration)| duration).sum();
+
+        TileAnimation { frames, total }
+    }
+
+    /// elapsed 초가 지났을 때 그릴 프레임의 gid
+    pub fn frame_at(&self, elapsed: f64) -> u32 {
+        if self.total <= 0. {
+            return self.frames[0].0;
+        }
+
+        let mut time = elapsed.rem_euclid(self.total);
+        for (gid, duration) in self.frames.iter() {
+            if time < *duration {
+                return *gid;
+            }
+            time -= duration;
+        }
+
+        self.frames[self.frames.len() - 1].0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagonal_flip() {
+        // 대각선 뒤집기 후 가로 뒤집기는 시계방향 90도 회전과 같다.
+        let flip = TileFlip {
+            h: true,
+            v: false,
+            d: true,
+        };
+        assert_eq!(flip.to_copy_ex(), (90.0, false, false));
+
+        let flip = TileFlip {
+            h: false,
+            v: false,
+            d: true,
+        };
+        assert_eq!(flip.to_copy_ex(), (90.0, false, true));
+
+        // 회전 후 32x64 영역을 차지하도록 64x32 로 그린다.
+        let dest = Rect::new(0, 0, 32, 64);
+        assert_eq!(flip.draw_rect(dest), Rect::new(-16, 16, 64, 32));
+    }
+
+    #[test]
+    fn animation_frames() {
+        let animation = TileAnimation::new(
+            10,
+            &[
+                tiled::Frame {
+                    tile_id: 0,
+                    duration: 100,
+                },
+                tiled::Frame {
+                    tile_id: 3,
+                    duration: 300,
+                },
+            ],
+        );
+
+        assert_eq!(animation.total, 0.4);
+        assert_eq!(animation.frame_at(0.0), 10);
+        assert_eq!(animation.frame_at(0.15), 13);
+        // 한 바퀴가 지나면 처음 프레임으로 돌아온다.
+        assert_eq!(animation.frame_at(0.45), 10);
+    }
+
+    #[test]
+    fn load_animation() {
+        let map = tiled::parse_file(std::path::Path::new("assets/tiled_animation.tmx")).unwrap();
+        let tileset = &map.tilesets[0];
+        let tile = tileset.tiles.iter().find(|tile| tile.id == 1).unwrap();
+
+        let animation = TileAnimation::new(tileset.first_gid, tile.animation.as_ref().unwrap());
+
+        assert_eq!(animation.frames, vec![(2, 0.2), (16, 0.2), (30, 0.4)]);
+        assert_eq!(animation.frame_at(0.3), 16);
+        assert_eq!(animation.frame_at(0.7), 30);
+    }
+}
@@ -0,0 +1,200 @@
+//! 가상 화면과 실제 창의 좌표 변환
+//! 게임은 VIRTUAL_WIDTH x VIRTUAL_HEIGHT 크기의 가상 화면에 그린다.
+//! 창 크기가 바뀌면 Viewport 가 가상 화면을 창의 어느 영역에 얼마나 키워서 보일지 다시 정하고,
+//! canvas 의 scale 과 viewport 를 그에 맞춘다.
+
+use sdl2::event::{Event, WindowEvent};
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+use serde::{Deserialize, Serialize};
+
+/// 가상 화면을 창에 맞추는 방법
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScaleMode {
+    /// 비율을 유지한 채 가장 크게 키우고 남는 부분은 검은 띠로 둔다.
+    Letterbox,
+    /// 정수 배로만 키운다. 픽셀이 고르게 보인다.
+    IntegerScale,
+    /// 창 전체를 채운다. 비율이 달라질 수 있다.
+    Stretch,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Viewport {
+    pub virtual_width: u32,
+    pub virtual_height: u32,
+    pub window_width: u32,
+    pub window_height: u32,
+    pub mode: ScaleMode,
+    /// 가상 화면 1 픽셀이 창에서 차지하는 크기
+    pub scale_x: f64,
+    pub scale_y: f64,
+    /// 가상 화면이 그려지는 창의 영역
+    pub rect: Rect,
+}
+
+impl Viewport {
+    pub fn new(
+        virtual_width: u32,
+        virtual_height: u32,
+        window_width: u32,
+        window_height: u32,
+        mode: ScaleMode,
+    ) -> Viewport {
+        let mut viewport = Viewport {
+            virtual_width,
+            virtual_height,
+            window_width,
+            window_height,
+            mode,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            rect: Rect::new(0, 0, virtual_width, virtual_height),
+        };
+        viewport.update();
+
+        viewport
+    }
+
+    /// 창 크기가 바뀌었을 때
+    pub fn resize(&mut self, window_width: u32, window_height: u32) {
+        self.window_width = window_width.max(1);
+        self.window_height = window_height.max(1);
+        self.update();
+    }
+
+    pub fn set_mode(&mut self, mode: ScaleMode) {
+        self.mode = mode;
+        self.update();
+    }
+
+    /// 창 크기 변경 이벤트라면 처리하고 true 를 돌려준다.
+    pub fn process_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::Window {
+                win_event: WindowEvent::Resized(w, h),
+                ..
+            }
+            | Event::Window {
+                win_event: WindowEvent::SizeChanged(w, h),
+                ..
+            } => {
+                self.resize(*w as u32, *h as u32);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// scale 과 rect 를 다시 구한다.
+    fn update(&mut self) {
+        let ratio_x = self.window_width as f64 / self.virtual_width as f64;
+        let ratio_y = self.window_height as f64 / self.virtual_height as f64;
+
+        let (scale_x, scale_y) = match self.mode {
+            ScaleMode::Stretch => (ratio_x, ratio_y),
+            ScaleMode::Letterbox => {
+                let scale = ratio_x.min(ratio_y);
+                (scale, scale)
+            }
+            ScaleMode::IntegerScale => {
+                // 창이 가상 화면보다 작다면 비율을 유지한 채 줄인다.
+                let scale = ratio_x.min(ratio_y);
+                let scale = if scale >= 1.0 { scale.floor() } else { scale };
+                (scale, scale)
+            }
+        };
+
+        let width = (self.virtual_width as f64 * scale_x).round() as u32;
+        let height = (self.virtual_height as f64 * scale_y).round() as u32;
+
+        self.scale_x = scale_x;
+        self.scale_y = scale_y;
+        self.rect = Rect::new(
+            (self.window_width as i32 - width as i32) / 2,
+            (self.window_height as i32 - height as i32) / 2,
+            width.max(1),
+            height.max(1),
+        );
+    }
+
+    /// canvas 가 가상 화면 좌표로 그리도록 한다.
+    /// SDL 은 viewport 를 지정할 때의 scale 로 viewport 를 환산하므로 scale 1 에서 창 좌표로 지정한다.
+    pub fn apply(&self, canvas: &mut WindowCanvas) {
+        canvas.set_scale(1.0, 1.0).unwrap();
+        canvas.set_viewport(self.rect);
+        canvas
+            .set_scale(self.scale_x as f32, self.scale_y as f32)
+            .unwrap();
+    }
+
+    /// 창 좌표(마우스 위치 등)를 가상 화면 좌표로 바꾼다.
+    /// 검은 띠 위의 좌표는 가상 화면 밖의 값이 된다.
+    pub fn to_virtual(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        (
+            ((x - self.rect.x) as f64 / self.scale_x).floor() as i32,
+            ((y - self.rect.y) as f64 / self.scale_y).floor() as i32,
+        )
+    }
+
+    /// 가상 화면 좌표를 창 좌표로 바꾼다.
+    pub fn to_physical(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        (
+            (x as f64 * self.scale_x).round() as i32 + self.rect.x,
+            (y as f64 * self.scale_y).round() as i32 + self.rect.y,
+        )
+    }
+
+    /// 가상 화면의 영역을 창의 영역으로 바꾼다.
+    pub fn to_physical_rect(&self, rect: Rect) -> Rect {
+        let (left, top) = self.to_physical((rect.left(), rect.top()));
+        let (right, bottom) = self.to_physical((rect.right(), rect.bottom()));
+
+        Rect::new(
+            left,
+            top,
+            (right - left).max(1) as u32,
+            (bottom - top).max(1) as u32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letterbox() {
+        let viewport = Viewport::new(800, 600, 1000, 600, ScaleMode::Letterbox);
+
+        assert_eq!(viewport.rect, Rect::new(100, 0, 800, 600));
+        assert_eq!(viewport.to_virtual((100, 0)), (0, 0));
+        // 왼쪽 검은 띠는 가상 화면 밖이다.
+        assert_eq!(viewport.to_virtual((50, 10)).0, -50);
+    }
+
+    #[test]
+    fn integer_scale() {
+        let mut viewport = Viewport::new(800, 600, 800, 600, ScaleMode::IntegerScale);
+        viewport.resize(1700, 1300);
+
+        assert_eq!(viewport.scale_x, 2.0);
+        assert_eq!(viewport.rect, Rect::new(50, 50, 1600, 1200));
+        assert_eq!(viewport.to_virtual((851, 651)), (400, 300));
+        assert_eq!(viewport.to_physical((400, 300)), (850, 650));
+    }
+
+    #[test]
+    fn stretch() {
+        let viewport = Viewport::new(800, 600, 1600, 900, ScaleMode::Stretch);
+
+        assert_eq!((viewport.scale_x, viewport.scale_y), (2.0, 1.5));
+        assert_eq!(viewport.rect, Rect::new(0, 0, 1600, 900));
+        assert_eq!(viewport.to_virtual((1600, 900)), (800, 600));
+        assert_eq!(
+            viewport.to_physical_rect(Rect::new(10, 10, 20, 20)),
+            Rect::new(20, 15, 40, 30)
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! 창과 소리 없이 GameState 를 돌리는 테스트
+//! GPU 가 없는 CI 에서도 돌 수 있도록 render 는 부르지 않는다.
+
+use sdl2::event::Event;
+use sdl2::keyboard::{Mod, Scancode};
+
+use sdl_isometric::constant::DELTA_T;
+use sdl_isometric::entities::*;
+use sdl_isometric::states::States;
+use sdl_isometric::*;
+
+fn world() -> Rectangle {
+    Rectangle::new(0.0, 0.0, 400.0, 400.0)
+}
+
+fn key_down(scancode: Scancode) -> Event {
+    Event::KeyDown {
+        timestamp: 0,
+        window_id: 0,
+        keycode: None,
+        scancode: Some(scancode),
+        keymod: Mod::NOMOD,
+        repeat: false,
+    }
+}
+
+fn key_up(scancode: Scancode) -> Event {
+    Event::KeyUp {
+        timestamp: 0,
+        window_id: 0,
+        keycode: None,
+        scancode: Some(scancode),
+        keymod: Mod::NOMOD,
+        repeat: false,
+    }
+}
+
+/// 주어진 시간 동안 DELTA_T 간격으로 update 한다.
+fn run(state: &mut GameState, seconds: f64) {
+    for _ in 0..(seconds / DELTA_T).round() as u32 {
+        state.update(DELTA_T);
+    }
+}
+
+fn player_pos(state: &GameState) -> (f64, f64) {
+    state
+        .player()
+        .and_then(|player| player.movement.as_ref())
+        .map(|movement| movement.get_pos())
+        .unwrap()
+}
+
+#[test]
+fn player_walks_while_key_is_held() {
+    let mut state = GameState::headless(1, world());
+    state.remove_entities(EntityType::MOB);
+
+    state.process_event(&key_down(Scancode::Right), DELTA_T);
+    run(&mut state, 0.5);
+    let (x, y) = player_pos(&state);
+    assert!(x > 30.0, "x = {}", x);
+    assert_eq!(y, 0.0);
+
+    // 키를 떼면 감속해서 멈춘다.
+    state.process_event(&key_up(Scancode::Right), DELTA_T);
+    run(&mut state, 1.0);
+    let stopped = player_pos(&state);
+    run(&mut state, 0.5);
+    assert_eq!(player_pos(&state), stopped);
+}
+
+#[test]
+fn player_walks_into_wall_and_stops() {
+    let mut state = GameState::headless(1, world());
+    state.remove_entities(EntityType::MOB);
+
+    let mut wall = Entity::new(EntityType::BLOCK);
+    wall.set_movement(100.0, 0.0, (0, 0), (0.0, 0.0), 0.0, 0.0, 0.0);
+    wall.set_hitbox(0.0, 0.0, 16.0, 16.0);
+    state.add_entity(wall);
+
+    state.process_event(&key_down(Scancode::Right), DELTA_T);
+    run(&mut state, 1.5);
+    let (x, _) = player_pos(&state);
+
+    // 한 update 에 움직이는 거리 안쪽에서 벽 앞에 멈춘다.
+    assert!(x + 16.0 <= 100.0, "x = {}", x);
+    assert!(x + 16.0 > 100.0 - 200.0 * DELTA_T, "x = {}", x);
+
+    run(&mut state, 0.5);
+    assert_eq!(player_pos(&state).0, x);
+}
+
+#[test]
+fn player_stops_at_world_edge() {
+    let mut state = GameState::headless(1, world());
+    state.remove_entities(EntityType::MOB);
+
+    state.process_event(&key_down(Scancode::Up), DELTA_T);
+    state.process_event(&key_down(Scancode::Left), DELTA_T);
+    run(&mut state, 0.5);
+
+    assert_eq!(player_pos(&state), (0.0, 0.0));
+}
+
+#[test]
+fn pause_key_requests_pause() {
+    let mut state = GameState::headless(1, world());
+
+    state.process_event(&key_down(Scancode::P), DELTA_T);
+    state.update(DELTA_T);
+
+    assert_eq!(
+        state.next_result(),
+        StateResult::Push(StateInfo::Pause("pause"))
+    );
+    assert_eq!(state.next_result(), StateResult::Default);
+}
+
+#[test]
+fn same_seed_same_simulation() {
+    // 공격 키를 누르고 있으면 ATTACK entity 가 난수로 생긴다.
+    let positions = |seed: u64| {
+        let mut state = GameState::headless(seed, world());
+        state.process_event(&key_down(Scancode::Space), DELTA_T);
+        run(&mut state, 1.0);
+
+        // entities 는 HashMap 이므로 순서를 맞춘다.
+        let mut positions = state
+            .entities()
+            .values()
+            .filter(|entity| entity.type_ != EntityType::PLAYER)
+            .map(|entity| (entity.type_, entity.movement.as_ref().unwrap().get_pos()))
+            .collect::<Vec<_>>();
+        positions.sort_by(|a, b| {
+            a.1.partial_cmp(&b.1)
+                .unwrap()
+                .then_with(|| format!("{:?}", a.0).cmp(&format!("{:?}", b.0)))
+        });
+        positions
+    };
+
+    let first = positions(7);
+    assert!(first.iter().any(|(type_, _)| *type_ == EntityType::ATTACK));
+    assert_eq!(first, positions(7));
+}